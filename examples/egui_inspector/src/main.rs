@@ -1,10 +1,12 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 #![allow(rustdoc::missing_crate_level_docs)] // it's an example
 
+use std::cell::Cell;
 use std::collections::{HashMap, HashSet};
 use std::sync::{Mutex, OnceLock};
 
-use eframe::{egui, emath};
+use eframe::egui;
+use quicksilver::numeric::Number;
 use quicksilver::reflections::*;
 use quicksilver::*;
 #[cfg(target_os = "linux")]
@@ -143,96 +145,125 @@ fn draw_struct_reflection(ui: &mut egui::Ui, r: &mut StructReflection) {
         .num_columns(2)
         .striped(true)
         .show(ui, |ui| {
-            for field in &mut r.fields {
-                ui.label(field.name);
-                draw_value(ui, &mut field.value);
+            for (name, mut value) in r.fields_mut() {
+                ui.label(name);
+                draw_value(ui, &mut value);
                 ui.end_row();
             }
         });
 }
 
 fn draw_enum_reflection(ui: &mut egui::Ui, r: &mut RustEnumReflection) {
-    ui.heading(format!("{}::{}", r.name, r.variant_name));
+    ui.heading(r.name);
+    let Type::RustEnum(mirror) = r.ty() else {
+        unreachable!()
+    };
+    let mut selected = r.variant_idx;
+    egui::ComboBox::from_id_salt(next_id())
+        .selected_text(r.variant_name)
+        .show_ui(ui, |ui| {
+            for (i, variant) in mirror.variants.iter().enumerate() {
+                ui.selectable_value(&mut selected, i, variant.name);
+            }
+        });
+    if selected != r.variant_idx {
+        r.switch_variant(selected);
+    }
     egui::Grid::new(next_id())
         .min_col_width(50.)
         .num_columns(2)
         .striped(true)
         .show(ui, |ui| {
-            for field in &mut r.fields {
-                ui.label(field.name);
-                draw_value(ui, &mut field.value);
+            for (name, mut value) in r.fields_mut() {
+                ui.label(name);
+                draw_value(ui, &mut value);
                 ui.end_row();
             }
         });
 }
 
-fn draw_struct_reflection_ref(ui: &mut egui::Ui, r: &StructReflection) {
+fn draw_struct_reflection_ref(ui: &mut egui::Ui, r: &StructReflection<Ref>) {
     ui.heading(r.name);
     egui::Grid::new(next_id())
         .min_col_width(50.)
         .num_columns(2)
         .striped(true)
         .show(ui, |ui| {
-            for field in &r.fields {
-                ui.label(field.name);
-                draw_value_ref(ui, &field.value);
+            for field in r.fields() {
+                ui.label(field.name());
+                draw_value_ref(ui, field.value());
                 ui.end_row();
             }
         });
 }
 
-fn draw_enum_reflection_ref(ui: &mut egui::Ui, r: &RustEnumReflection) {
+fn draw_enum_reflection_ref(ui: &mut egui::Ui, r: &RustEnumReflection<Ref>) {
     ui.heading(format!("{}::{}", r.name, r.variant_name));
     egui::Grid::new(next_id())
         .min_col_width(50.)
         .num_columns(2)
         .striped(true)
         .show(ui, |ui| {
-            for field in &r.fields {
-                ui.label(field.name);
-                draw_value_ref(ui, &field.value);
+            for field in r.fields() {
+                ui.label(field.name());
+                draw_value_ref(ui, field.value());
                 ui.end_row();
             }
         });
 }
 
-fn draw_numeric<Num: emath::Numeric>(ui: &mut egui::Ui, value: &mut RefOrMut<Num>) {
-    match value {
-        RefOrMut::Ref(_val) => {
-            unreachable!();
-        }
-        RefOrMut::Mut(val) => {
-            ui.add(egui::DragValue::new(*val));
-        }
+fn draw_numeric(ui: &mut egui::Ui, value: &mut ValueMut) {
+    let kind = value.numeric_kind().unwrap();
+    let range = kind.min().as_f64()..=kind.max().as_f64();
+    if kind.is_float() {
+        let drag = egui::DragValue::from_get_set(|new| {
+            if let Some(new) = new {
+                _ = value.set_from_f64(new);
+            }
+            value.as_f64().unwrap()
+        });
+        ui.add(drag.range(range));
+        return;
     }
+    // DragValue works with f64, which can't hold 64 bit integers above 2^53,
+    // so typed numbers are parsed and drags are applied to the exact value
+    let old = value.as_i128().unwrap();
+    let typed = Cell::new(None);
+    let drag = egui::DragValue::from_get_set(|new| {
+        if let Some(new) = new {
+            let new = typed
+                .take()
+                .unwrap_or_else(|| old.saturating_add((new - old as f64).round() as i128));
+            // values which don't fit, e.g. u64::MAX + 1, are ignored
+            _ = value.set_from_i128(new);
+        }
+        value.as_i128().unwrap() as f64
+    })
+    .custom_parser(|text| {
+        let new: i128 = text.trim().parse().ok()?;
+        typed.set(Some(new));
+        Some(new as f64)
+    })
+    .custom_formatter(|shown, _| {
+        if shown == old as f64 {
+            old.to_string()
+        } else {
+            format!("{shown:.0}")
+        }
+    });
+    ui.add(drag.range(range));
 }
 
-fn draw_value(ui: &mut egui::Ui, value: &mut ValueReflection) {
+fn draw_value(ui: &mut egui::Ui, value: &mut ValueMut) {
     match value {
-        ValueReflection::I32(it) => {
-            draw_numeric(ui, it);
-        }
-        ValueReflection::U32(it) => {
-            draw_numeric(ui, it);
-        }
-        ValueReflection::F32(it) => {
-            draw_numeric(ui, it);
-        }
-        ValueReflection::I64(it) => {
-            draw_numeric(ui, it);
-        }
-        ValueReflection::U64(it) => {
-            draw_numeric(ui, it);
-        }
-        ValueReflection::F64(it) => {
-            draw_numeric(ui, it);
-        }
-        ValueReflection::ISize(it) => {
-            draw_numeric(ui, it);
-        }
-        ValueReflection::USize(it) => {
-            draw_numeric(ui, it);
-        }
+        ValueReflection::I32(_)
+        | ValueReflection::U32(_)
+        | ValueReflection::F32(_)
+        | ValueReflection::I64(_)
+        | ValueReflection::U64(_)
+        | ValueReflection::F64(_)
+        | ValueReflection::ISize(_)
+        | ValueReflection::USize(_) => draw_numeric(ui, value),
         ValueReflection::Bool(it) => {
             ui.checkbox(it, "");
         }
@@ -251,9 +282,21 @@ fn draw_value(ui: &mut egui::Ui, value: &mut ValueReflection) {
         }
         ValueReflection::Vec(vec) => {
             ui.vertical(|ui| {
+                let mut remove = None;
                 let len = vec.len();
                 for i in 0..len {
-                    draw_value(ui, &mut vec.get(i));
+                    ui.horizontal(|ui| {
+                        if ui.small_button("-").clicked() {
+                            remove = Some(i);
+                        }
+                        draw_value(ui, &mut vec.get(i));
+                    });
+                }
+                if let Some(i) = remove {
+                    vec.remove(i);
+                }
+                if ui.small_button("+").clicked() {
+                    vec.push_default();
                 }
             });
         }
@@ -266,10 +309,9 @@ fn draw_value(ui: &mut egui::Ui, value: &mut ValueReflection) {
                     ui.label("Key");
                     ui.label("Value");
                     ui.end_row();
-                    for mut element in hmreflection.get_elements() {
-                        let [key, value] = element.fields.get_disjoint_mut([0, 1]).unwrap();
-                        draw_value_ref(ui, &key.value);
-                        draw_value(ui, &mut value.value);
+                    for (key, mut value) in hmreflection.iter_mut() {
+                        draw_value_ref(ui, &key);
+                        draw_value(ui, &mut value);
                         ui.end_row();
                     }
                 });
@@ -277,7 +319,7 @@ fn draw_value(ui: &mut egui::Ui, value: &mut ValueReflection) {
         ValueReflection::CEnum(e) => {
             let name = e.variants.iter().find(|it| it.0 == *e.val).unwrap().1;
             egui::ComboBox::from_id_salt(next_id())
-                .selected_text(format!("{name}"))
+                .selected_text(name.to_string())
                 .show_ui(ui, |ui| {
                     for (i, name) in e.variants {
                         ui.selectable_value(&mut *e.val, *i, *name);
@@ -286,47 +328,43 @@ fn draw_value(ui: &mut egui::Ui, value: &mut ValueReflection) {
         }
         ValueReflection::Option(o) => {
             if let Some(ref mut inner) = o.get() {
+                let mut set_none = false;
                 ui.vertical(|ui| {
-                    ui.label("Some:");
+                    ui.horizontal(|ui| {
+                        ui.label("Some:");
+                        set_none = ui.small_button("-").clicked();
+                    });
                     draw_value(ui, inner);
                 });
+                if set_none {
+                    o.set_none();
+                }
             } else {
-                ui.label("None");
+                ui.horizontal(|ui| {
+                    ui.label("None");
+                    if ui.small_button("+").clicked() {
+                        o.set_some_default();
+                    }
+                });
             }
         }
-        ref outer @ ValueReflection::HashSet(_) => {
-            draw_value_ref(ui, outer);
+        ValueReflection::HashSet(_) => {
+            draw_value_ref(ui, &value.to_ref());
         }
-        ValueReflection::Box(box_reflection) => draw_value(ui, &mut box_reflection.inner),
+        ValueReflection::Box(box_reflection) => draw_value(ui, &mut box_reflection.inner_mut()),
     }
 }
 
-fn draw_value_ref(ui: &mut egui::Ui, value: &ValueReflection) {
+fn draw_value_ref(ui: &mut egui::Ui, value: &ValueRef) {
     match value {
-        ValueReflection::I32(it) => {
-            draw_numeric_ref(ui, it);
-        }
-        ValueReflection::U32(it) => {
-            draw_numeric_ref(ui, it);
-        }
-        ValueReflection::F32(it) => {
-            draw_numeric_ref(ui, it);
-        }
-        ValueReflection::I64(it) => {
-            draw_numeric_ref(ui, it);
-        }
-        ValueReflection::U64(it) => {
-            draw_numeric_ref(ui, it);
-        }
-        ValueReflection::F64(it) => {
-            draw_numeric_ref(ui, it);
-        }
-        ValueReflection::ISize(it) => {
-            draw_numeric_ref(ui, it);
-        }
-        ValueReflection::USize(it) => {
-            draw_numeric_ref(ui, it);
-        }
+        ValueReflection::I32(_)
+        | ValueReflection::U32(_)
+        | ValueReflection::F32(_)
+        | ValueReflection::I64(_)
+        | ValueReflection::U64(_)
+        | ValueReflection::F64(_)
+        | ValueReflection::ISize(_)
+        | ValueReflection::USize(_) => draw_numeric_ref(ui, value),
         ValueReflection::Bool(it) => {
             let mut copy: bool = **it;
             ui.add_enabled(false, egui::Checkbox::new(&mut copy, ""));
@@ -358,7 +396,7 @@ fn draw_value_ref(ui: &mut egui::Ui, value: &ValueReflection) {
                 .num_columns(2)
                 .striped(true)
                 .show(ui, |ui| {
-                    for element in hsreflection.get_elements_ref() {
+                    for element in hsreflection.iter() {
                         draw_value_ref(ui, &element);
                         ui.end_row();
                     }
@@ -370,18 +408,16 @@ fn draw_value_ref(ui: &mut egui::Ui, value: &ValueReflection) {
                 .num_columns(2)
                 .striped(true)
                 .show(ui, |ui| {
-                    for element in hmreflection.get_elements_ref() {
-                        let key = &element.fields[0].value;
-                        let value = &element.fields[1].value;
-                        draw_value_ref(ui, key);
-                        draw_value_ref(ui, value);
+                    for (key, value) in hmreflection.iter() {
+                        draw_value_ref(ui, &key);
+                        draw_value_ref(ui, &value);
                         ui.end_row();
                     }
                 });
         }
         ValueReflection::CEnum(e) => {
             let name = e.variants.iter().find(|it| it.0 == *e.val).unwrap().1;
-            ui.label(&format!("{}", name));
+            ui.label(name.to_string());
         }
         ValueReflection::Option(o) => {
             if let Some(ref inner) = o.get_ref() {
@@ -393,20 +429,17 @@ fn draw_value_ref(ui: &mut egui::Ui, value: &ValueReflection) {
                 ui.label("None");
             }
         }
-        ValueReflection::Box(box_reflection) => draw_value_ref(ui, &box_reflection.inner),
+        ValueReflection::Box(box_reflection) => draw_value_ref(ui, box_reflection.inner()),
     }
 }
 
-fn draw_numeric_ref<Num: emath::Numeric + std::fmt::Display>(
-    ui: &mut egui::Ui,
-    value: &RefOrMut<Num>,
-) {
-    match value {
-        RefOrMut::Ref(val) => {
-            ui.label(&format!("{}", *val));
-        }
-        RefOrMut::Mut(_val) => {
-            unreachable!();
-        }
-    }
+fn draw_numeric_ref(ui: &mut egui::Ui, value: &ValueRef) {
+    let mut copy = value.as_f64().unwrap();
+    let drag = egui::DragValue::new(&mut copy);
+    let drag = match value.as_number().unwrap() {
+        // shown exactly, as f64 it would be rounded above 2^53
+        Number::Int(int) => drag.custom_formatter(move |_, _| int.to_string()),
+        Number::Float(_) => drag,
+    };
+    ui.add_enabled(false, drag);
 }
@@ -0,0 +1,170 @@
+//! Serializes a large tilemap, run with `cargo bench -p quicksilver`.
+use std::hint::black_box;
+use std::io;
+use std::time::{Duration, Instant};
+
+use quicksilver::Quicksilver;
+use quicksilver::json::{to_json, to_json_writer};
+use quicksilver::reflections::{FieldReflection, Ref, ValueRef, ValueReflection};
+use quicksilver::reflections_ref::reflect_ref;
+
+#[derive(Quicksilver)]
+struct Tilemap {
+    tiles: Vec<Tile>,
+    width: i32,
+    height: i32,
+}
+
+#[derive(Quicksilver, Clone)]
+struct Tile {
+    kind: TileKind,
+    height: f32,
+    walkable: bool,
+    item: Option<u32>,
+}
+
+#[allow(unused)]
+#[derive(Quicksilver, Clone)]
+enum TileKind {
+    Floor,
+    Wall { hp: u32 },
+    Door(bool),
+}
+
+fn tilemap() -> Tilemap {
+    let tiles = (0..100_000)
+        .map(|i| Tile {
+            kind: match i % 3 {
+                0 => TileKind::Floor,
+                1 => TileKind::Wall { hp: i },
+                _ => TileKind::Door(i % 2 == 0),
+            },
+            height: i as f32 * 0.5,
+            walkable: i % 3 == 0,
+            item: (i % 7 == 0).then_some(i),
+        })
+        .collect();
+    Tilemap {
+        tiles,
+        width: 1000,
+        height: 100,
+    }
+}
+
+/// Counts the bytes written to it, like a file without the disk.
+struct Counter(usize);
+
+impl io::Write for Counter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The json writer from before cursors, which formats every value into its own String.
+/// Kept to measure against, it uses the default encoding.
+fn reflection_to_json(value: &ValueRef) -> String {
+    match value {
+        ValueReflection::I32(val) => format!("{}", **val),
+        ValueReflection::CEnum(cenum) => format!("{}", *cenum.val),
+        ValueReflection::U32(val) => format!("{}", **val),
+        ValueReflection::F32(val) => format!("{}", **val),
+        ValueReflection::I64(val) => format!("{}", **val),
+        ValueReflection::U64(val) => format!("{}", **val),
+        ValueReflection::F64(val) => format!("{}", **val),
+        ValueReflection::ISize(val) => format!("{}", **val),
+        ValueReflection::USize(val) => format!("{}", **val),
+        ValueReflection::Bool(val) => format!("{}", **val),
+        ValueReflection::String(val) => {
+            let escaped = val.replace('\\', r"\\").replace('"', r#"\""#);
+            format!("\"{escaped}\"")
+        }
+        ValueReflection::Struct(s) => fields_to_json(None, s.fields()),
+        ValueReflection::Vec(v) if v.skip => "[]".to_string(),
+        ValueReflection::Vec(v) => {
+            let elements: Vec<String> = (0..v.len())
+                .map(|i| reflection_to_json(&v.get_ref(i)))
+                .collect();
+            format!("[{}]", elements.join(","))
+        }
+        ValueReflection::HashSet(hs) if hs.skip => "[]".to_string(),
+        ValueReflection::HashSet(hs) => {
+            let elements: Vec<String> = hs.iter().map(|it| reflection_to_json(&it)).collect();
+            format!("[{}]", elements.join(","))
+        }
+        ValueReflection::HashMap(hm) if hm.skip => "[]".to_string(),
+        ValueReflection::HashMap(hm) => {
+            let entries: Vec<String> = hm
+                .iter()
+                .map(|(key, value)| {
+                    format!(
+                        r#"{{"key":{},"value":{}}}"#,
+                        reflection_to_json(&key),
+                        reflection_to_json(&value)
+                    )
+                })
+                .collect();
+            format!("[{}]", entries.join(","))
+        }
+        ValueReflection::Option(o) if o.skip => "[]".to_string(),
+        ValueReflection::Option(o) => match o.get_ref() {
+            Some(inner) => format!("[{}]", reflection_to_json(&inner)),
+            None => "[]".to_string(),
+        },
+        ValueReflection::RustEnum(renum) => {
+            fields_to_json(Some(renum.variant_name), renum.fields())
+        }
+        ValueReflection::Box(b) => reflection_to_json(b.inner()),
+    }
+}
+
+fn fields_to_json(variant: Option<&str>, fields: &[FieldReflection<'_, Ref>]) -> String {
+    let mut parts = Vec::new();
+    if let Some(variant) = variant {
+        parts.push(format!(r#""__enum_variant":"{variant}""#));
+    }
+    for field in fields {
+        parts.push(format!(
+            "\"{}\":{}",
+            field.name(),
+            reflection_to_json(field.value())
+        ));
+    }
+    format!("{{{}}}", parts.join(","))
+}
+
+/// `f` returns the number of bytes written.
+fn bench(name: &str, mut f: impl FnMut() -> usize) {
+    // warm up
+    let len = f();
+    let runs = 10;
+    let mut total = Duration::ZERO;
+    for _ in 0..runs {
+        let start = Instant::now();
+        black_box(f());
+        total += start.elapsed();
+    }
+    println!("{name:<12} {:>10.2?} per run, {len} bytes", total / runs);
+}
+
+fn main() {
+    let map = tilemap();
+
+    bench("cursor", || to_json(black_box(&map)).len());
+
+    // streams in chunks instead of building the whole string
+    bench("writer", || {
+        let mut out = Counter(0);
+        to_json_writer(black_box(&map), &mut out).unwrap();
+        out.0
+    });
+
+    // reflects every tile up front and formats every value into its own String
+    bench("reflection", || {
+        reflection_to_json(&reflect_ref(black_box(&map))).len()
+    });
+}
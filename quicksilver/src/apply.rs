@@ -0,0 +1,247 @@
+use crate::Type;
+use crate::default::{box_default, drop_alloc};
+use crate::dynamic::{
+    DynamicError, DynamicValue, box_value, error, find_field, validate, validate_fields,
+    validate_list,
+};
+use crate::reflections::{Access, ValueMut, ValueReflection, reflect_value};
+
+/// What happens to a collection in the target, if the patch contains one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CollectionPolicy {
+    /// The collection is emptied before the elements of the patch are added.
+    #[default]
+    Replace,
+    /// Elements of a `Vec` are appended, elements of a `HashSet` are inserted.
+    /// Entries of a `HashMap` with an existing key are patched, other entries are inserted.
+    Merge,
+}
+
+/// Merges `patch` into `target` in place.
+///
+/// Primitives in the patch overwrite the target,
+/// structs are patched field by field and fields missing from the patch are left alone.
+/// If the patch names a different enum variant, the variant is switched first
+/// and the missing fields of the new variant are default constructed.
+/// New elements of a `Vec`, new values of a `HashMap` and `Some` values replacing `None`
+/// are default constructed before they are patched.
+/// Collections are handled according to `policy`.
+///
+/// The whole patch is checked against the target first, nothing is changed if it doesn't fit.
+/// Elements of a `HashSet` and keys of a `HashMap` have to be complete values.
+pub fn apply(
+    mut target: ValueMut,
+    patch: &DynamicValue,
+    policy: CollectionPolicy,
+) -> Result<(), DynamicError> {
+    check(patch, &target, &mut String::new())?;
+    apply_checked(patch, &mut target, policy);
+    Ok(())
+}
+
+fn check<A: Access>(
+    patch: &DynamicValue,
+    target: &ValueReflection<'_, A>,
+    path: &mut String,
+) -> Result<(), DynamicError> {
+    match (patch, target) {
+        (DynamicValue::I32(_), ValueReflection::I32(_))
+        | (DynamicValue::U32(_), ValueReflection::U32(_))
+        | (DynamicValue::F32(_), ValueReflection::F32(_))
+        | (DynamicValue::I64(_), ValueReflection::I64(_))
+        | (DynamicValue::U64(_), ValueReflection::U64(_))
+        | (DynamicValue::F64(_), ValueReflection::F64(_))
+        | (DynamicValue::ISize(_), ValueReflection::ISize(_))
+        | (DynamicValue::USize(_), ValueReflection::USize(_))
+        | (DynamicValue::Bool(_), ValueReflection::Bool(_))
+        | (DynamicValue::String(_), ValueReflection::String(_)) => Ok(()),
+        (_, ValueReflection::Box(b)) => check(patch, &b.inner, path),
+        (DynamicValue::Struct(s), ValueReflection::Struct(target)) => {
+            let expected = target.mirror().fields.iter().map(|f| (f.name, &f.ty));
+            validate_fields(&s.fields, expected, path, true)
+        }
+        (DynamicValue::Enum(e), ValueReflection::CEnum(target)) => {
+            if !target.variants.iter().any(|(_, name)| *name == e.variant) {
+                return error(
+                    path,
+                    format!("{} has no variant {}", target.name, e.variant),
+                );
+            }
+            if !e.fields.is_empty() {
+                return error(
+                    path,
+                    format!("{}::{} has no fields", target.name, e.variant),
+                );
+            }
+            Ok(())
+        }
+        (DynamicValue::Enum(_), ValueReflection::RustEnum(target)) => {
+            validate(patch, target.ty(), path, true)
+        }
+        (DynamicValue::List(elements), ValueReflection::Vec(target)) => {
+            validate_list(elements, target.element, target.skip, path, true)
+        }
+        (DynamicValue::List(elements), ValueReflection::HashSet(target)) => {
+            validate_list(elements, target.element, target.skip, path, false)
+        }
+        (DynamicValue::Map(entries), ValueReflection::HashMap(target)) => {
+            if target.skip && !entries.is_empty() {
+                return error(path, "skipped HashMap has to be empty".to_string());
+            }
+            for (i, (key, value)) in entries.iter().enumerate() {
+                let len = path.len();
+                path.push_str(&format!("[{i}].key"));
+                validate(key, target.key, path, false)?;
+                path.truncate(len);
+                path.push_str(&format!("[{i}].value"));
+                validate(value, target.value, path, true)?;
+                path.truncate(len);
+            }
+            Ok(())
+        }
+        (DynamicValue::Option(inner), ValueReflection::Option(target)) => match inner {
+            Some(_) if target.skip => error(path, "skipped Option has to be None".to_string()),
+            Some(inner) => validate(inner, target.element, path, true),
+            None => Ok(()),
+        },
+        _ => error(
+            path,
+            format!("can't apply {} to {}", patch.kind(), kind(target)),
+        ),
+    }
+}
+
+fn kind<'a, A: Access>(target: &ValueReflection<'a, A>) -> &'a str {
+    match target {
+        ValueReflection::I32(_) => "i32",
+        ValueReflection::U32(_) => "u32",
+        ValueReflection::F32(_) => "f32",
+        ValueReflection::I64(_) => "i64",
+        ValueReflection::U64(_) => "u64",
+        ValueReflection::F64(_) => "f64",
+        ValueReflection::ISize(_) => "isize",
+        ValueReflection::USize(_) => "usize",
+        ValueReflection::Bool(_) => "bool",
+        ValueReflection::String(_) => "String",
+        ValueReflection::Struct(s) => s.name,
+        ValueReflection::CEnum(e) => e.name,
+        ValueReflection::RustEnum(e) => e.name,
+        ValueReflection::Vec(_) => "Vec",
+        ValueReflection::HashMap(_) => "HashMap",
+        ValueReflection::HashSet(_) => "HashSet",
+        ValueReflection::Option(_) => "Option",
+        ValueReflection::Box(b) => kind(&b.inner),
+    }
+}
+
+/// Applies an already checked patch.
+fn apply_checked(patch: &DynamicValue, target: &mut ValueMut, policy: CollectionPolicy) {
+    match (patch, target) {
+        (DynamicValue::I32(val), ValueReflection::I32(target)) => **target = *val,
+        (DynamicValue::U32(val), ValueReflection::U32(target)) => **target = *val,
+        (DynamicValue::F32(val), ValueReflection::F32(target)) => **target = *val,
+        (DynamicValue::I64(val), ValueReflection::I64(target)) => **target = *val,
+        (DynamicValue::U64(val), ValueReflection::U64(target)) => **target = *val,
+        (DynamicValue::F64(val), ValueReflection::F64(target)) => **target = *val,
+        (DynamicValue::ISize(val), ValueReflection::ISize(target)) => **target = *val,
+        (DynamicValue::USize(val), ValueReflection::USize(target)) => **target = *val,
+        (DynamicValue::Bool(val), ValueReflection::Bool(target)) => **target = *val,
+        (DynamicValue::String(val), ValueReflection::String(target)) => val.clone_into(target),
+        (_, ValueReflection::Box(b)) => apply_checked(patch, &mut b.inner, policy),
+        (DynamicValue::Struct(s), ValueReflection::Struct(target)) => {
+            for field in &mut target.fields {
+                if let Some(patch) = find_field(&s.fields, field.name) {
+                    apply_checked(patch, &mut field.value, policy);
+                }
+            }
+        }
+        (DynamicValue::Enum(e), ValueReflection::CEnum(target)) => {
+            let (val, _) = target
+                .variants
+                .iter()
+                .find(|(_, name)| *name == e.variant)
+                .unwrap();
+            *target.val = *val;
+        }
+        (DynamicValue::Enum(e), ValueReflection::RustEnum(target)) => {
+            if target.variant_name != e.variant {
+                let Type::RustEnum(mirror) = target.ty() else {
+                    unreachable!()
+                };
+                let idx = mirror
+                    .variants
+                    .iter()
+                    .position(|it| it.name == e.variant)
+                    .unwrap();
+                target.switch_variant(idx);
+            }
+            for field in &mut target.fields {
+                if let Some(patch) = find_field(&e.fields, field.name) {
+                    apply_checked(patch, &mut field.value, policy);
+                }
+            }
+        }
+        (DynamicValue::List(elements), ValueReflection::Vec(target)) => {
+            if target.skip {
+                return;
+            }
+            if policy == CollectionPolicy::Replace {
+                target.clear();
+            }
+            target.reserve(elements.len());
+            for element in elements {
+                target.push_default();
+                let last = target.len() - 1;
+                apply_checked(element, &mut target.get(last), policy);
+            }
+        }
+        (DynamicValue::List(elements), ValueReflection::HashSet(target)) => {
+            if target.skip {
+                return;
+            }
+            if policy == CollectionPolicy::Replace {
+                target.clear();
+            }
+            for element in elements {
+                unsafe { target.insert(box_value(element, target.element)) };
+            }
+        }
+        (DynamicValue::Map(entries), ValueReflection::HashMap(target)) => {
+            if target.skip {
+                return;
+            }
+            if policy == CollectionPolicy::Replace {
+                target.clear();
+            }
+            for (key, value) in entries {
+                unsafe {
+                    let key_ptr = box_value(key, target.key);
+                    if policy == CollectionPolicy::Merge
+                        && let Some(mut existing) = target.get_mut(key_ptr)
+                    {
+                        apply_checked(value, &mut existing, policy);
+                        drop_alloc(key_ptr, target.key);
+                        continue;
+                    }
+                    let value_ptr = box_default(target.value);
+                    apply_checked(value, &mut reflect_value(value_ptr, target.value), policy);
+                    target.insert(key_ptr, value_ptr);
+                }
+            }
+        }
+        (DynamicValue::Option(inner), ValueReflection::Option(target)) => match inner {
+            None => {
+                if !target.skip {
+                    target.set_none();
+                }
+            }
+            Some(inner) => {
+                if target.get().is_none() {
+                    target.set_some_default();
+                }
+                apply_checked(inner, &mut target.get().unwrap(), policy);
+            }
+        },
+        _ => unreachable!("patch was checked"),
+    }
+}
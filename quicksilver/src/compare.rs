@@ -0,0 +1,323 @@
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+use crate::Quicksilver;
+use crate::map::HMReflection;
+use crate::reflections::{FieldReflection, Ref, ValueRef, ValueReflection};
+use crate::reflections_ref::reflect_ref;
+use crate::set::HSReflection;
+
+/// Hashes a value by its structure.
+///
+/// The hash doesn't depend on the iteration order of `HashMap`s and `HashSet`s
+/// and is the same across runs. Values that [`reflect_cmp`] considers equal hash the same.
+pub fn reflect_hash<T: Quicksilver>(val: &T) -> u64 {
+    let mut hasher = StableHasher::default();
+    hash_value(&reflect_ref(val), &mut hasher);
+    hasher.finish()
+}
+
+/// Compares two values by their structure.
+///
+/// This is a total order: floats are compared with `total_cmp`,
+/// `HashMap`s and `HashSet`s are compared as if their contents were sorted.
+pub fn reflect_cmp<T: Quicksilver>(a: &T, b: &T) -> Ordering {
+    cmp_values(&reflect_ref(a), &reflect_ref(b))
+}
+
+/// Feeds a reflected value into `state`, see [`reflect_hash`].
+pub fn hash_value<H: Hasher>(value: &ValueRef, state: &mut H) {
+    let value = unbox(value);
+    state.write_u8(rank(value));
+    match value {
+        ValueReflection::I32(val) => val.hash(state),
+        ValueReflection::U32(val) => val.hash(state),
+        ValueReflection::F32(val) => val.to_bits().hash(state),
+        ValueReflection::I64(val) => val.hash(state),
+        ValueReflection::U64(val) => val.hash(state),
+        ValueReflection::F64(val) => val.to_bits().hash(state),
+        ValueReflection::ISize(val) => val.hash(state),
+        ValueReflection::USize(val) => val.hash(state),
+        ValueReflection::Bool(val) => val.hash(state),
+        ValueReflection::String(val) => hash_str(val, state),
+        ValueReflection::CEnum(cenum) => {
+            hash_str(cenum.name, state);
+            cenum.val.hash(state);
+        }
+        ValueReflection::Struct(s) => {
+            hash_str(s.name, state);
+            hash_fields(s.fields(), state);
+        }
+        ValueReflection::RustEnum(renum) => {
+            hash_str(renum.name, state);
+            renum.variant_idx.hash(state);
+            hash_fields(renum.fields(), state);
+        }
+        ValueReflection::Vec(v) => {
+            let len = if v.skip { 0 } else { v.len() };
+            len.hash(state);
+            for i in 0..len {
+                hash_value(&v.get_ref(i), state);
+            }
+        }
+        ValueReflection::HashSet(hs) => {
+            if hs.skip {
+                return hash_unordered(Vec::new(), state);
+            }
+            hash_unordered(hs.iter().map(|it| stable_hash(&it)).collect(), state);
+        }
+        ValueReflection::HashMap(hm) => {
+            if hm.skip {
+                return hash_unordered(Vec::new(), state);
+            }
+            let hashes = hm
+                .iter()
+                .map(|(key, value)| {
+                    let mut hasher = StableHasher::default();
+                    hash_value(&key, &mut hasher);
+                    hash_value(&value, &mut hasher);
+                    hasher.finish()
+                })
+                .collect();
+            hash_unordered(hashes, state);
+        }
+        ValueReflection::Option(o) => match if o.skip { None } else { o.get_ref() } {
+            Some(inner) => {
+                state.write_u8(1);
+                hash_value(&inner, state);
+            }
+            None => state.write_u8(0),
+        },
+        ValueReflection::Box(_) => unreachable!("boxes are looked through"),
+    }
+}
+
+/// Compares two reflected values, see [`reflect_cmp`].
+///
+/// Values of different kinds are ordered by their kind,
+/// different structs and enums by their name.
+/// Boxes are looked through, so a `Box<T>` compares like the `T`.
+pub fn cmp_values(a: &ValueRef, b: &ValueRef) -> Ordering {
+    match (unbox(a), unbox(b)) {
+        (ValueReflection::I32(a), ValueReflection::I32(b)) => a.cmp(b),
+        (ValueReflection::U32(a), ValueReflection::U32(b)) => a.cmp(b),
+        (ValueReflection::F32(a), ValueReflection::F32(b)) => a.total_cmp(b),
+        (ValueReflection::I64(a), ValueReflection::I64(b)) => a.cmp(b),
+        (ValueReflection::U64(a), ValueReflection::U64(b)) => a.cmp(b),
+        (ValueReflection::F64(a), ValueReflection::F64(b)) => a.total_cmp(b),
+        (ValueReflection::ISize(a), ValueReflection::ISize(b)) => a.cmp(b),
+        (ValueReflection::USize(a), ValueReflection::USize(b)) => a.cmp(b),
+        (ValueReflection::Bool(a), ValueReflection::Bool(b)) => a.cmp(b),
+        (ValueReflection::String(a), ValueReflection::String(b)) => a.cmp(b),
+        (ValueReflection::CEnum(a), ValueReflection::CEnum(b)) => {
+            a.name.cmp(b.name).then(a.val.cmp(b.val))
+        }
+        (ValueReflection::Struct(a), ValueReflection::Struct(b)) => a
+            .name
+            .cmp(b.name)
+            .then_with(|| cmp_fields(a.fields(), b.fields())),
+        (ValueReflection::RustEnum(a), ValueReflection::RustEnum(b)) => a
+            .name
+            .cmp(b.name)
+            .then(a.variant_idx.cmp(&b.variant_idx))
+            .then_with(|| cmp_fields(a.fields(), b.fields())),
+        (ValueReflection::Vec(a), ValueReflection::Vec(b)) => {
+            let a_len = if a.skip { 0 } else { a.len() };
+            let b_len = if b.skip { 0 } else { b.len() };
+            for i in 0..a_len.min(b_len) {
+                let ord = cmp_values(&a.get_ref(i), &b.get_ref(i));
+                if ord.is_ne() {
+                    return ord;
+                }
+            }
+            a_len.cmp(&b_len)
+        }
+        (ValueReflection::HashSet(a), ValueReflection::HashSet(b)) => {
+            cmp_slices(&sorted_elements(a), &sorted_elements(b), cmp_values)
+        }
+        (ValueReflection::HashMap(a), ValueReflection::HashMap(b)) => {
+            cmp_slices(&sorted_entries(a), &sorted_entries(b), cmp_entries)
+        }
+        (ValueReflection::Option(a), ValueReflection::Option(b)) => {
+            // skipped Options can't be read, they compare like `None`
+            let a = if a.skip { None } else { a.get_ref() };
+            let b = if b.skip { None } else { b.get_ref() };
+            match (a, b) {
+                (Some(a), Some(b)) => cmp_values(&a, &b),
+                (a, b) => a.is_some().cmp(&b.is_some()),
+            }
+        }
+        (a, b) => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// Wraps a reflected value, so it can be used in `HashSet`s, `BTreeSet`s or be sorted.
+/// Uses [`hash_value`] and [`cmp_values`].
+pub struct ByReflection<'a>(pub ValueRef<'a>);
+
+impl PartialEq for ByReflection<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        cmp_values(&self.0, &other.0).is_eq()
+    }
+}
+
+impl Eq for ByReflection<'_> {}
+
+impl PartialOrd for ByReflection<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ByReflection<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        cmp_values(&self.0, &other.0)
+    }
+}
+
+impl Hash for ByReflection<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_value(&self.0, state);
+    }
+}
+
+/// 64 bit FNV-1a, which gives the same hashes on every run and platform.
+#[derive(Debug, Clone)]
+pub struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    // integers are hashed as little endian u64, so the hash doesn't depend on the platform
+    fn write_usize(&mut self, i: usize) {
+        self.write(&(i as u64).to_le_bytes());
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write(&(i as i64).to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write(&i.to_le_bytes());
+    }
+}
+
+/// Orders the kinds of values, if two values of different kinds are compared.
+fn rank(value: &ValueRef) -> u8 {
+    match value {
+        ValueReflection::I32(_) => 0,
+        ValueReflection::U32(_) => 1,
+        ValueReflection::F32(_) => 2,
+        ValueReflection::I64(_) => 3,
+        ValueReflection::U64(_) => 4,
+        ValueReflection::F64(_) => 5,
+        ValueReflection::ISize(_) => 6,
+        ValueReflection::USize(_) => 7,
+        ValueReflection::Bool(_) => 8,
+        ValueReflection::String(_) => 9,
+        ValueReflection::Struct(_) => 10,
+        ValueReflection::CEnum(_) => 11,
+        ValueReflection::Vec(_) => 12,
+        ValueReflection::HashMap(_) => 13,
+        ValueReflection::HashSet(_) => 14,
+        ValueReflection::Option(_) => 15,
+        ValueReflection::RustEnum(_) => 16,
+        ValueReflection::Box(_) => unreachable!("boxes are looked through"),
+    }
+}
+
+fn unbox<'v, 'a>(mut value: &'v ValueRef<'a>) -> &'v ValueRef<'a> {
+    while let ValueReflection::Box(b) = value {
+        value = &b.inner;
+    }
+    value
+}
+
+fn stable_hash(value: &ValueRef) -> u64 {
+    let mut hasher = StableHasher::default();
+    hash_value(value, &mut hasher);
+    hasher.finish()
+}
+
+// `str::hash` may change between compiler versions
+fn hash_str<H: Hasher>(s: &str, state: &mut H) {
+    state.write_usize(s.len());
+    state.write(s.as_bytes());
+}
+
+fn hash_unordered<H: Hasher>(mut hashes: Vec<u64>, state: &mut H) {
+    hashes.sort_unstable();
+    hashes.len().hash(state);
+    for hash in hashes {
+        state.write_u64(hash);
+    }
+}
+
+fn hash_fields<H: Hasher>(fields: &[FieldReflection<'_, Ref>], state: &mut H) {
+    for field in fields {
+        hash_value(&field.value, state);
+    }
+}
+
+fn sorted_elements<'a>(hs: &'a HSReflection<'_, Ref>) -> Vec<ValueRef<'a>> {
+    let mut elements: Vec<ValueRef> = Vec::new();
+    if !hs.skip {
+        elements.extend(hs.iter());
+    }
+    elements.sort_by(cmp_values);
+    elements
+}
+
+fn sorted_entries<'a>(hm: &'a HMReflection<'_, Ref>) -> Vec<(ValueRef<'a>, ValueRef<'a>)> {
+    let mut entries: Vec<(ValueRef, ValueRef)> = Vec::new();
+    if !hm.skip {
+        entries.extend(hm.iter());
+    }
+    entries.sort_by(cmp_entries);
+    entries
+}
+
+fn cmp_fields(a: &[FieldReflection<'_, Ref>], b: &[FieldReflection<'_, Ref>]) -> Ordering {
+    cmp_slices(a, b, |a, b| cmp_values(&a.value, &b.value))
+}
+
+fn cmp_entries(a: &(ValueRef, ValueRef), b: &(ValueRef, ValueRef)) -> Ordering {
+    cmp_values(&a.0, &b.0).then_with(|| cmp_values(&a.1, &b.1))
+}
+
+fn cmp_slices<T, U>(a: &[T], b: &[U], cmp: impl Fn(&T, &U) -> Ordering) -> Ordering {
+    for (a, b) in a.iter().zip(b) {
+        let ord = cmp(a, b);
+        if ord.is_ne() {
+            return ord;
+        }
+    }
+    a.len().cmp(&b.len())
+}
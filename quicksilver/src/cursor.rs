@@ -0,0 +1,540 @@
+use crate::map::HMVtable;
+use crate::option::OptionVtable;
+use crate::reflections::{Access, ValueRef, ValueReflection};
+use crate::set::HSVtable;
+use crate::vec::VecVtable;
+use crate::{CEnum, Quicksilver, RustEnum, Struct, Type};
+
+/// Read only reflection of a value, which doesn't allocate.
+///
+/// Unlike [`ValueRef`](crate::reflections::ValueRef) nothing is reflected up front,
+/// fields and elements are computed on demand from the mirror.
+/// This makes walking large values, e.g. for serialization, cheap.
+#[derive(Clone, Copy)]
+pub struct Cursor<'a> {
+    ptr: *const u8,
+    ty: &'a Type,
+}
+
+/// What a [`Cursor`] points at. Boxes are looked through.
+#[derive(Clone, Copy)]
+pub enum CursorValue<'a> {
+    I32(&'a i32),
+    U32(&'a u32),
+    F32(&'a f32),
+    I64(&'a i64),
+    U64(&'a u64),
+    F64(&'a f64),
+    ISize(&'a isize),
+    USize(&'a usize),
+    Bool(&'a bool),
+    String(&'a String),
+    Struct(StructCursor<'a>),
+    CEnum(CEnumCursor<'a>),
+    Vec(VecCursor<'a>),
+    HashMap(HMCursor<'a>),
+    HashSet(HSCursor<'a>),
+    Option(OptionCursor<'a>),
+    RustEnum(RustEnumCursor<'a>),
+}
+
+#[derive(Clone, Copy)]
+pub struct StructCursor<'a> {
+    ptr: *const u8,
+    mirror: &'a Struct,
+}
+
+#[derive(Clone, Copy)]
+pub struct CEnumCursor<'a> {
+    pub val: i32,
+    pub mirror: &'a CEnum,
+}
+
+#[derive(Clone, Copy)]
+pub struct RustEnumCursor<'a> {
+    ptr: *const u8,
+    mirror: &'a RustEnum,
+    variant_idx: usize,
+}
+
+/// Skipped Vecs, HashMaps, HashSets and Options appear empty.
+#[derive(Clone, Copy)]
+pub struct VecCursor<'a> {
+    ptr: *const u8,
+    element: &'a Type,
+    vtable: &'a VecVtable,
+    skip: bool,
+}
+
+#[derive(Clone, Copy)]
+pub struct HMCursor<'a> {
+    ptr: *const u8,
+    key: &'a Type,
+    value: &'a Type,
+    vtable: &'a HMVtable,
+    skip: bool,
+}
+
+#[derive(Clone, Copy)]
+pub struct HSCursor<'a> {
+    ptr: *const u8,
+    element: &'a Type,
+    vtable: &'a HSVtable,
+    skip: bool,
+}
+
+#[derive(Clone, Copy)]
+pub struct OptionCursor<'a> {
+    ptr: *const u8,
+    element: &'a Type,
+    vtable: &'a OptionVtable,
+    skip: bool,
+}
+
+/// Creates a [`Cursor`] pointing at `val`.
+pub fn cursor<T: Quicksilver>(val: &T) -> Cursor<'_> {
+    unsafe { Cursor::from_raw(val as *const T as *const u8, &T::MIRROR) }
+}
+
+impl<'a> Cursor<'a> {
+    /// # Safety
+    /// `ptr` must point to a valid value described by `ty`, which lives for `'a`.
+    pub unsafe fn from_raw(ptr: *const u8, ty: &'a Type) -> Self {
+        Cursor { ptr, ty }
+    }
+
+    pub fn ty(&self) -> &'a Type {
+        self.ty
+    }
+
+    pub fn ptr(&self) -> *const u8 {
+        self.ptr
+    }
+
+    pub fn get(&self) -> CursorValue<'a> {
+        let ptr = self.ptr;
+        unsafe {
+            match self.ty {
+                Type::I32 => CursorValue::I32(&*(ptr as *const i32)),
+                Type::U32 => CursorValue::U32(&*(ptr as *const u32)),
+                Type::F32 => CursorValue::F32(&*(ptr as *const f32)),
+                Type::I64 => CursorValue::I64(&*(ptr as *const i64)),
+                Type::U64 => CursorValue::U64(&*(ptr as *const u64)),
+                Type::F64 => CursorValue::F64(&*(ptr as *const f64)),
+                Type::ISize => CursorValue::ISize(&*(ptr as *const isize)),
+                Type::USize => CursorValue::USize(&*(ptr as *const usize)),
+                Type::Bool => CursorValue::Bool(&*(ptr as *const bool)),
+                Type::String => CursorValue::String(&*(ptr as *const String)),
+                Type::Box(b) => Cursor::from_raw((b.get_ref)(ptr), b.inner).get(),
+                Type::Struct(mirror) => CursorValue::Struct(StructCursor::from_raw(ptr, mirror)),
+                Type::CEnum(mirror) => CursorValue::CEnum(CEnumCursor {
+                    val: *(ptr as *const i32),
+                    mirror,
+                }),
+                Type::RustEnum(mirror) => {
+                    CursorValue::RustEnum(RustEnumCursor::from_raw(ptr, mirror))
+                }
+                Type::Vec(v) => {
+                    CursorValue::Vec(VecCursor::from_raw(ptr, v.element, &v.vtable, v.skip))
+                }
+                Type::HashMap(hm) => CursorValue::HashMap(HMCursor::from_raw(
+                    ptr, hm.key, hm.value, &hm.vtable, hm.skip,
+                )),
+                Type::HashSet(hs) => {
+                    CursorValue::HashSet(HSCursor::from_raw(ptr, hs.element, &hs.vtable, hs.skip))
+                }
+                Type::Option(o) => {
+                    CursorValue::Option(OptionCursor::from_raw(ptr, o.element, &o.vtable, o.skip))
+                }
+            }
+        }
+    }
+}
+
+impl<'a> StructCursor<'a> {
+    /// # Safety
+    /// `ptr` must point to a valid struct described by `mirror`, which lives for `'a`.
+    pub unsafe fn from_raw(ptr: *const u8, mirror: &'a Struct) -> Self {
+        StructCursor { ptr, mirror }
+    }
+
+    pub fn mirror(&self) -> &'a Struct {
+        self.mirror
+    }
+
+    pub fn ptr(&self) -> *const u8 {
+        self.ptr
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.mirror.name
+    }
+
+    pub fn len(&self) -> usize {
+        self.mirror.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mirror.fields.is_empty()
+    }
+
+    /// Panics if there is no field at `index`.
+    pub fn field(&self, index: usize) -> Cursor<'a> {
+        let field = &self.mirror.fields[index];
+        unsafe { Cursor::from_raw(self.ptr.add(field.offset), &field.ty) }
+    }
+
+    pub fn field_by_name(&self, name: &str) -> Option<Cursor<'a>> {
+        (self.mirror.index_of)(name).map(|index| self.field(index))
+    }
+
+    /// Names and cursors of all fields.
+    pub fn fields(&self) -> impl Iterator<Item = (&'a str, Cursor<'a>)> + use<'a> {
+        let this = *self;
+        (0..this.len()).map(move |i| (this.mirror.fields[i].name, this.field(i)))
+    }
+}
+
+impl<'a> CEnumCursor<'a> {
+    pub fn name(&self) -> &'a str {
+        self.mirror.name
+    }
+
+    /// `None` if the value doesn't belong to a variant.
+    pub fn variant_name(&self) -> Option<&'a str> {
+        let (_, name) = self.mirror.variants.iter().find(|it| it.0 == self.val)?;
+        Some(name)
+    }
+}
+
+impl<'a> RustEnumCursor<'a> {
+    /// # Safety
+    /// `ptr` must point to a valid enum described by `mirror`, which lives for `'a`.
+    pub unsafe fn from_raw(ptr: *const u8, mirror: &'a RustEnum) -> Self {
+        let variant_idx = unsafe { (mirror.variant_index)(ptr) };
+        RustEnumCursor {
+            ptr,
+            mirror,
+            variant_idx,
+        }
+    }
+
+    pub fn mirror(&self) -> &'a RustEnum {
+        self.mirror
+    }
+
+    pub fn ptr(&self) -> *const u8 {
+        self.ptr
+    }
+
+    /// Index of the current variant in the variants of the mirror.
+    pub fn variant_idx(&self) -> usize {
+        self.variant_idx
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.mirror.name
+    }
+
+    pub fn variant_name(&self) -> &'a str {
+        self.mirror.variants[self.variant_idx].name
+    }
+
+    /// Number of fields of the current variant.
+    pub fn len(&self) -> usize {
+        self.mirror.variants[self.variant_idx].fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Panics if the current variant has no field at `index`.
+    pub fn field(&self, index: usize) -> Cursor<'a> {
+        let (_, ty) = &self.mirror.variants[self.variant_idx].fields[index];
+        unsafe { Cursor::from_raw((self.mirror.field_ptr)(self.ptr, index), ty) }
+    }
+
+    /// Names and cursors of the fields of the current variant.
+    pub fn fields(&self) -> impl Iterator<Item = (&'a str, Cursor<'a>)> + use<'a> {
+        let this = *self;
+        let fields = this.mirror.variants[this.variant_idx].fields;
+        (0..fields.len()).map(move |i| (fields[i].0, this.field(i)))
+    }
+}
+
+impl<'a> VecCursor<'a> {
+    /// # Safety
+    /// `ptr` must point to a valid Vec whose contents are described by the types and `vtable`,
+    /// which lives for `'a`. If `skip` is set, it is never read.
+    pub unsafe fn from_raw(
+        ptr: *const u8,
+        element: &'a Type,
+        vtable: &'a VecVtable,
+        skip: bool,
+    ) -> Self {
+        VecCursor {
+            ptr,
+            element,
+            vtable,
+            skip,
+        }
+    }
+
+    /// The type of the elements.
+    pub fn element_ty(&self) -> &'a Type {
+        self.element
+    }
+
+    pub fn ptr(&self) -> *const u8 {
+        self.ptr
+    }
+
+    pub fn len(&self) -> usize {
+        if self.skip {
+            return 0;
+        }
+        // the vtable only reads through the pointer
+        unsafe { (self.vtable.get_len)(self.ptr as *mut u8) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Panics if `index` is out of bounds.
+    pub fn get(&self, index: usize) -> Cursor<'a> {
+        assert!(index < self.len(), "index {index} out of bounds");
+        unsafe { Cursor::from_raw((self.vtable.get_elem_ref)(self.ptr, index), self.element) }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Cursor<'a>> + use<'a> {
+        let this = *self;
+        (0..this.len()).map(move |i| this.get(i))
+    }
+}
+
+impl<'a> HMCursor<'a> {
+    /// # Safety
+    /// `ptr` must point to a valid HashMap whose contents are described by the types and `vtable`,
+    /// which lives for `'a`. If `skip` is set, it is never read.
+    pub unsafe fn from_raw(
+        ptr: *const u8,
+        key: &'a Type,
+        value: &'a Type,
+        vtable: &'a HMVtable,
+        skip: bool,
+    ) -> Self {
+        HMCursor {
+            ptr,
+            key,
+            value,
+            vtable,
+            skip,
+        }
+    }
+
+    /// The type of the keys.
+    pub fn key_ty(&self) -> &'a Type {
+        self.key
+    }
+
+    /// The type of the values.
+    pub fn value_ty(&self) -> &'a Type {
+        self.value
+    }
+
+    pub fn ptr(&self) -> *const u8 {
+        self.ptr
+    }
+
+    pub fn len(&self) -> usize {
+        if self.skip {
+            return 0;
+        }
+        unsafe { (self.vtable.len)(self.ptr) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterates over all entries in whatever order the HashMap sees fit.
+    pub fn iter(&self) -> impl Iterator<Item = (Cursor<'a>, Cursor<'a>)> + use<'a> {
+        let entries = if self.skip {
+            Vec::new()
+        } else {
+            unsafe { (self.vtable.get_entries_ref)(self.ptr) }
+        };
+        let (key, value) = (self.key, self.value);
+        entries
+            .into_iter()
+            .map(move |(k, v)| unsafe { (Cursor::from_raw(k, key), Cursor::from_raw(v, value)) })
+    }
+}
+
+impl<'a> HSCursor<'a> {
+    /// # Safety
+    /// `ptr` must point to a valid HashSet whose contents are described by the types and `vtable`,
+    /// which lives for `'a`. If `skip` is set, it is never read.
+    pub unsafe fn from_raw(
+        ptr: *const u8,
+        element: &'a Type,
+        vtable: &'a HSVtable,
+        skip: bool,
+    ) -> Self {
+        HSCursor {
+            ptr,
+            element,
+            vtable,
+            skip,
+        }
+    }
+
+    /// The type of the elements.
+    pub fn element_ty(&self) -> &'a Type {
+        self.element
+    }
+
+    pub fn ptr(&self) -> *const u8 {
+        self.ptr
+    }
+
+    pub fn len(&self) -> usize {
+        if self.skip {
+            return 0;
+        }
+        unsafe { (self.vtable.len)(self.ptr) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterates over all elements in whatever order the HashSet sees fit.
+    pub fn iter(&self) -> impl Iterator<Item = Cursor<'a>> + use<'a> {
+        let elements = if self.skip {
+            Vec::new()
+        } else {
+            unsafe { (self.vtable.get_elements)(self.ptr) }
+        };
+        let element = self.element;
+        elements
+            .into_iter()
+            .map(move |it| unsafe { Cursor::from_raw(it, element) })
+    }
+}
+
+impl<'a> OptionCursor<'a> {
+    /// # Safety
+    /// `ptr` must point to a valid Option whose contents are described by the types and `vtable`,
+    /// which lives for `'a`. If `skip` is set, it is never read.
+    pub unsafe fn from_raw(
+        ptr: *const u8,
+        element: &'a Type,
+        vtable: &'a OptionVtable,
+        skip: bool,
+    ) -> Self {
+        OptionCursor {
+            ptr,
+            element,
+            vtable,
+            skip,
+        }
+    }
+
+    /// The type of the value, if there is one.
+    pub fn element_ty(&self) -> &'a Type {
+        self.element
+    }
+
+    pub fn ptr(&self) -> *const u8 {
+        self.ptr
+    }
+
+    pub fn get(&self) -> Option<Cursor<'a>> {
+        if self.skip {
+            return None;
+        }
+        let ptr = unsafe { (self.vtable.get_elem_ref)(self.ptr)? };
+        Some(unsafe { Cursor::from_raw(ptr, self.element) })
+    }
+}
+
+impl<'a, A: Access> ValueReflection<'a, A> {
+    /// A cursor of the reflected value, except for structs and Rust enums.
+    ///
+    /// Their field reflections may be mutable references into the value,
+    /// reading through the pointer to the whole value would invalidate them.
+    pub(crate) fn leaf_cursor(&self) -> Option<CursorValue<'_>> {
+        let value = match self {
+            ValueReflection::I32(val) => CursorValue::I32(val),
+            ValueReflection::U32(val) => CursorValue::U32(val),
+            ValueReflection::F32(val) => CursorValue::F32(val),
+            ValueReflection::I64(val) => CursorValue::I64(val),
+            ValueReflection::U64(val) => CursorValue::U64(val),
+            ValueReflection::F64(val) => CursorValue::F64(val),
+            ValueReflection::ISize(val) => CursorValue::ISize(val),
+            ValueReflection::USize(val) => CursorValue::USize(val),
+            ValueReflection::Bool(val) => CursorValue::Bool(val),
+            ValueReflection::String(val) => CursorValue::String(val),
+            ValueReflection::Box(b) => return b.inner.leaf_cursor(),
+            _ => return self.container_cursor(),
+        };
+        Some(value)
+    }
+
+    /// A cursor of a reflected CEnum, Vec, HashMap, HashSet or Option.
+    fn container_cursor(&self) -> Option<CursorValue<'a>> {
+        let value = match self {
+            ValueReflection::CEnum(cenum) => CursorValue::CEnum(CEnumCursor {
+                val: *cenum.val,
+                mirror: cenum.mirror,
+            }),
+            // the reflections point to valid values of their types
+            ValueReflection::Vec(v) => {
+                CursorValue::Vec(unsafe { VecCursor::from_raw(v.ptr, v.element, v.vtable, v.skip) })
+            }
+            ValueReflection::HashMap(hm) => CursorValue::HashMap(unsafe {
+                HMCursor::from_raw(hm.ptr, hm.key, hm.value, hm.vtable, hm.skip)
+            }),
+            ValueReflection::HashSet(hs) => CursorValue::HashSet(unsafe {
+                HSCursor::from_raw(hs.ptr, hs.element, hs.vtable, hs.skip)
+            }),
+            ValueReflection::Option(o) => CursorValue::Option(unsafe {
+                OptionCursor::from_raw(o.ptr, o.element, o.vtable, o.skip)
+            }),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+impl<'a> ValueRef<'a> {
+    /// A cursor of the reflected value.
+    pub fn cursor(&self) -> CursorValue<'a> {
+        match self {
+            ValueReflection::I32(val) => CursorValue::I32(val),
+            ValueReflection::U32(val) => CursorValue::U32(val),
+            ValueReflection::F32(val) => CursorValue::F32(val),
+            ValueReflection::I64(val) => CursorValue::I64(val),
+            ValueReflection::U64(val) => CursorValue::U64(val),
+            ValueReflection::F64(val) => CursorValue::F64(val),
+            ValueReflection::ISize(val) => CursorValue::ISize(val),
+            ValueReflection::USize(val) => CursorValue::USize(val),
+            ValueReflection::Bool(val) => CursorValue::Bool(val),
+            ValueReflection::String(val) => CursorValue::String(val),
+            ValueReflection::Struct(s) => {
+                CursorValue::Struct(unsafe { StructCursor::from_raw(s.as_ptr(), s.mirror()) })
+            }
+            ValueReflection::RustEnum(renum) => {
+                let Type::RustEnum(mirror) = renum.ty() else {
+                    unreachable!("RustEnumReflection of non enum type {}", renum.name)
+                };
+                CursorValue::RustEnum(unsafe { RustEnumCursor::from_raw(renum.as_ptr(), mirror) })
+            }
+            ValueReflection::Box(b) => b.inner.cursor(),
+            _ => self.container_cursor().unwrap(),
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use std::alloc::Layout;
+use std::mem::MaybeUninit;
+
+use crate::{Quicksilver, Type};
+
+/// Writes a default value of type `ty` to `dest`.
+///
+/// Types deriving Quicksilver with `#[quicksilver(default)]` use their `Default` impl.
+/// Everything else gets a structural default: `0`, `false`, `""`, empty containers, `None`,
+/// the first variant of an enum and a default value for every field of a struct.
+///
+/// # Safety
+/// `dest` must be valid for writes of `ty.layout()`.
+/// Whatever `dest` pointed to before is overwritten without being dropped.
+pub unsafe fn construct_default(ty: &Type, dest: *mut u8) {
+    unsafe {
+        match ty {
+            Type::I32 => (dest as *mut i32).write(0),
+            Type::U32 => (dest as *mut u32).write(0),
+            Type::F32 => (dest as *mut f32).write(0.),
+            Type::I64 => (dest as *mut i64).write(0),
+            Type::U64 => (dest as *mut u64).write(0),
+            Type::F64 => (dest as *mut f64).write(0.),
+            Type::ISize => (dest as *mut isize).write(0),
+            Type::USize => (dest as *mut usize).write(0),
+            Type::Bool => (dest as *mut bool).write(false),
+            Type::String => (dest as *mut String).write(String::new()),
+            Type::Box(b) => {
+                let inner = box_default(b.inner);
+                (b.box_up)(dest, inner);
+            }
+            Type::Vec(v) => {
+                (v.vtable.new_at)(dest, 0);
+            }
+            Type::HashMap(hm) => (hm.vtable.new_at)(dest),
+            Type::HashSet(hs) => (hs.vtable.new_at)(dest),
+            Type::Option(o) => (o.vtable.new_at)(dest),
+            Type::Struct(s) => {
+                if let Some(default) = s.default {
+                    default(dest);
+                } else {
+                    for field in s.fields {
+                        construct_default(&field.ty, dest.add(field.offset));
+                    }
+                }
+            }
+            Type::CEnum(cenum) => {
+                if let Some(default) = cenum.default {
+                    default(dest);
+                } else {
+                    let (first, _) = cenum
+                        .variants
+                        .first()
+                        .unwrap_or_else(|| panic!("{} has no variants", cenum.name));
+                    debug_assert_eq!(cenum.size, size_of::<i32>());
+                    (dest as *mut i32).write(*first);
+                }
+            }
+            Type::RustEnum(renum) => {
+                if let Some(default) = renum.default {
+                    default(dest);
+                } else {
+                    let variant = renum
+                        .variants
+                        .first()
+                        .unwrap_or_else(|| panic!("{} has no variants", renum.name));
+                    let fields: Vec<*mut u8> = variant
+                        .fields
+                        .iter()
+                        .map(|(_, ty)| box_default(ty))
+                        .collect();
+                    (renum.write)(dest, 0, &fields);
+                }
+            }
+        }
+    }
+}
+
+/// Allocates a default value of type `ty` on the heap.
+///
+/// The returned pointer behaves like one created by `Box::into_raw`,
+/// so it can be handed to vtable functions that take ownership of elements.
+pub fn box_default(ty: &Type) -> *mut u8 {
+    let layout = ty.layout();
+    let ptr = alloc(layout);
+    unsafe { construct_default(ty, ptr) };
+    ptr
+}
+
+/// Creates a default value of `T` from its mirror.
+pub fn default_of<T: Quicksilver>() -> T {
+    let mut result: MaybeUninit<T> = MaybeUninit::uninit();
+    unsafe {
+        construct_default(&T::MIRROR, result.as_mut_ptr() as *mut u8);
+        result.assume_init()
+    }
+}
+
+/// Allocates memory the same way `Box` would, including zero sized types.
+pub(crate) fn alloc(layout: Layout) -> *mut u8 {
+    if layout.size() == 0 {
+        return std::ptr::without_provenance_mut(layout.align());
+    }
+    let ptr = unsafe { std::alloc::alloc(layout) };
+    if ptr.is_null() {
+        std::alloc::handle_alloc_error(layout);
+    }
+    ptr
+}
+
+/// Frees memory from [`alloc`].
+///
+/// # Safety
+/// `ptr` must come from [`alloc`] with the same `layout`.
+pub(crate) unsafe fn dealloc(ptr: *mut u8, layout: Layout) {
+    if layout.size() != 0 {
+        unsafe { std::alloc::dealloc(ptr, layout) }
+    }
+}
+
+/// Drops and frees a value of type `ty` from [`alloc`].
+///
+/// # Safety
+/// `ptr` must come from [`alloc`] with the layout of `ty` and point to a valid value of it.
+pub(crate) unsafe fn drop_alloc(ptr: *mut u8, ty: &Type) {
+    unsafe {
+        ty.drop_in_place(ptr);
+        dealloc(ptr, ty.layout());
+    }
+}
@@ -0,0 +1,499 @@
+use std::fmt;
+use std::mem::MaybeUninit;
+
+use crate::default::alloc;
+use crate::reflections::{Access, FieldReflection, ValueReflection};
+use crate::reflections_ref::reflect_ref;
+use crate::{Quicksilver, Type};
+
+/// An owned value of a reflected type, which is not tied to a concrete Rust type.
+///
+/// Created from any reflected value with [`DynamicValue::from_value`] or [`DynamicValue::from_reflection`],
+/// or built by hand. It can be checked against a [`Type`] with [`DynamicValue::validate`]
+/// and turned back into a real value with [`DynamicValue::to_value`] or [`DynamicValue::write_to`].
+///
+/// `Box<T>` is transparent, it is represented by the value of `T`.
+/// `Vec` and `HashSet` are both represented as a [`DynamicValue::List`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum DynamicValue {
+    I32(i32),
+    U32(u32),
+    F32(f32),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    ISize(isize),
+    USize(usize),
+    Bool(bool),
+    String(String),
+    Struct(DynamicStruct),
+    /// C-style enums and Rust enums, C-style variants have no fields
+    Enum(DynamicEnum),
+    List(Vec<DynamicValue>),
+    Map(Vec<(DynamicValue, DynamicValue)>),
+    Option(Option<Box<DynamicValue>>),
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DynamicStruct {
+    pub fields: Vec<(String, DynamicValue)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamicEnum {
+    pub variant: String,
+    pub fields: Vec<(String, DynamicValue)>,
+}
+
+/// Why a [`DynamicValue`] doesn't fit a [`Type`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamicError {
+    /// where in the value the mismatch is, e.g. `.items[2].name`, empty for the root
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for DynamicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{} at {}", self.message, self.path)
+        }
+    }
+}
+
+impl std::error::Error for DynamicError {}
+
+impl DynamicStruct {
+    pub fn field(&self, name: &str) -> Option<&DynamicValue> {
+        find_field(&self.fields, name)
+    }
+
+    pub fn field_mut(&mut self, name: &str) -> Option<&mut DynamicValue> {
+        find_field_mut(&mut self.fields, name)
+    }
+}
+
+impl DynamicEnum {
+    pub fn field(&self, name: &str) -> Option<&DynamicValue> {
+        find_field(&self.fields, name)
+    }
+
+    pub fn field_mut(&mut self, name: &str) -> Option<&mut DynamicValue> {
+        find_field_mut(&mut self.fields, name)
+    }
+}
+
+pub(crate) fn find_field<'a>(
+    fields: &'a [(String, DynamicValue)],
+    name: &str,
+) -> Option<&'a DynamicValue> {
+    fields.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+}
+
+fn find_field_mut<'a>(
+    fields: &'a mut [(String, DynamicValue)],
+    name: &str,
+) -> Option<&'a mut DynamicValue> {
+    fields.iter_mut().find(|(n, _)| n == name).map(|(_, v)| v)
+}
+
+impl DynamicValue {
+    /// Copies a value into a dynamic value.
+    pub fn from_value<T: Quicksilver>(val: &T) -> Self {
+        Self::from_reflection(&reflect_ref(val))
+    }
+
+    /// Copies the reflected value into a dynamic value.
+    /// Skipped containers end up empty.
+    pub fn from_reflection<A: Access>(vr: &ValueReflection<'_, A>) -> Self {
+        match vr {
+            ValueReflection::I32(val) => DynamicValue::I32(**val),
+            ValueReflection::U32(val) => DynamicValue::U32(**val),
+            ValueReflection::F32(val) => DynamicValue::F32(**val),
+            ValueReflection::I64(val) => DynamicValue::I64(**val),
+            ValueReflection::U64(val) => DynamicValue::U64(**val),
+            ValueReflection::F64(val) => DynamicValue::F64(**val),
+            ValueReflection::ISize(val) => DynamicValue::ISize(**val),
+            ValueReflection::USize(val) => DynamicValue::USize(**val),
+            ValueReflection::Bool(val) => DynamicValue::Bool(**val),
+            ValueReflection::String(val) => DynamicValue::String(val.to_string()),
+            ValueReflection::Struct(s) => DynamicValue::Struct(DynamicStruct {
+                fields: from_fields(s.fields()),
+            }),
+            ValueReflection::CEnum(cenum) => {
+                let (_, name) = cenum
+                    .variants
+                    .iter()
+                    .find(|(val, _)| *val == *cenum.val)
+                    .unwrap_or_else(|| panic!("{} has no variant {}", cenum.name, *cenum.val));
+                DynamicValue::Enum(DynamicEnum {
+                    variant: name.to_string(),
+                    fields: Vec::new(),
+                })
+            }
+            ValueReflection::RustEnum(renum) => DynamicValue::Enum(DynamicEnum {
+                variant: renum.variant_name.to_string(),
+                fields: from_fields(renum.fields()),
+            }),
+            ValueReflection::Vec(v) => {
+                if v.skip {
+                    DynamicValue::List(Vec::new())
+                } else {
+                    DynamicValue::List(
+                        (0..v.len())
+                            .map(|i| Self::from_reflection(&v.get_ref(i)))
+                            .collect(),
+                    )
+                }
+            }
+            ValueReflection::HashSet(hs) => {
+                if hs.skip {
+                    DynamicValue::List(Vec::new())
+                } else {
+                    DynamicValue::List(hs.iter().map(|it| Self::from_reflection(&it)).collect())
+                }
+            }
+            ValueReflection::HashMap(hm) => {
+                if hm.skip {
+                    DynamicValue::Map(Vec::new())
+                } else {
+                    DynamicValue::Map(
+                        hm.iter()
+                            .map(|(k, v)| (Self::from_reflection(&k), Self::from_reflection(&v)))
+                            .collect(),
+                    )
+                }
+            }
+            ValueReflection::Option(o) => {
+                if o.skip {
+                    DynamicValue::Option(None)
+                } else {
+                    DynamicValue::Option(
+                        o.get_ref()
+                            .map(|inner| Box::new(Self::from_reflection(&inner))),
+                    )
+                }
+            }
+            ValueReflection::Box(b) => Self::from_reflection(&b.inner),
+        }
+    }
+
+    /// Checks if the value can be written as a value of type `ty`.
+    ///
+    /// Struct and enum fields are matched by name, in any order.
+    /// Every field has to be present exactly once.
+    /// Skipped containers have to be empty.
+    pub fn validate(&self, ty: &Type) -> Result<(), DynamicError> {
+        validate(self, ty, &mut String::new(), false)
+    }
+
+    /// Checks if the value can be applied as a patch onto a value of type `ty`,
+    /// see [`apply`](crate::apply::apply).
+    ///
+    /// Like [`validate`](DynamicValue::validate), but struct and enum fields may be missing.
+    /// Elements of a HashSet and keys of a HashMap still have to be complete.
+    pub fn validate_patch(&self, ty: &Type) -> Result<(), DynamicError> {
+        validate(self, ty, &mut String::new(), true)
+    }
+
+    /// Writes the value to `dest` as a value of type `ty`.
+    /// Nothing is written if the value doesn't [`validate`](DynamicValue::validate).
+    ///
+    /// # Safety
+    /// `dest` must be valid for writes of `ty.layout()`.
+    /// Whatever `dest` pointed to before is overwritten without being dropped.
+    pub unsafe fn write_to(&self, ty: &Type, dest: *mut u8) -> Result<(), DynamicError> {
+        self.validate(ty)?;
+        unsafe { write(self, ty, dest) };
+        Ok(())
+    }
+
+    /// Creates a `T` from the value.
+    pub fn to_value<T: Quicksilver>(&self) -> Result<T, DynamicError> {
+        let mut result: MaybeUninit<T> = MaybeUninit::uninit();
+        unsafe {
+            self.write_to(&T::MIRROR, result.as_mut_ptr() as *mut u8)?;
+            Ok(result.assume_init())
+        }
+    }
+
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            DynamicValue::I32(_) => "i32",
+            DynamicValue::U32(_) => "u32",
+            DynamicValue::F32(_) => "f32",
+            DynamicValue::I64(_) => "i64",
+            DynamicValue::U64(_) => "u64",
+            DynamicValue::F64(_) => "f64",
+            DynamicValue::ISize(_) => "isize",
+            DynamicValue::USize(_) => "usize",
+            DynamicValue::Bool(_) => "bool",
+            DynamicValue::String(_) => "String",
+            DynamicValue::Struct(_) => "struct",
+            DynamicValue::Enum(_) => "enum",
+            DynamicValue::List(_) => "list",
+            DynamicValue::Map(_) => "map",
+            DynamicValue::Option(_) => "option",
+        }
+    }
+}
+
+fn from_fields<A: Access>(fields: &[FieldReflection<'_, A>]) -> Vec<(String, DynamicValue)> {
+    fields
+        .iter()
+        .map(|field| {
+            (
+                field.name.to_string(),
+                DynamicValue::from_reflection(&field.value),
+            )
+        })
+        .collect()
+}
+
+fn type_name(ty: &Type) -> &'static str {
+    match ty {
+        Type::I32 => "i32",
+        Type::U32 => "u32",
+        Type::F32 => "f32",
+        Type::I64 => "i64",
+        Type::U64 => "u64",
+        Type::F64 => "f64",
+        Type::ISize => "isize",
+        Type::USize => "usize",
+        Type::Bool => "bool",
+        Type::String => "String",
+        Type::Box(b) => type_name(b.inner),
+        Type::Vec(_) => "Vec",
+        Type::HashMap(_) => "HashMap",
+        Type::HashSet(_) => "HashSet",
+        Type::Option(_) => "Option",
+        Type::Struct(s) => s.name,
+        Type::CEnum(e) => e.name,
+        Type::RustEnum(e) => e.name,
+    }
+}
+
+pub(crate) fn error(path: &str, message: String) -> Result<(), DynamicError> {
+    Err(DynamicError {
+        path: path.to_string(),
+        message,
+    })
+}
+
+/// Missing struct and enum fields are allowed if `partial` is set.
+pub(crate) fn validate(
+    value: &DynamicValue,
+    ty: &Type,
+    path: &mut String,
+    partial: bool,
+) -> Result<(), DynamicError> {
+    match (value, ty) {
+        (DynamicValue::I32(_), Type::I32)
+        | (DynamicValue::U32(_), Type::U32)
+        | (DynamicValue::F32(_), Type::F32)
+        | (DynamicValue::I64(_), Type::I64)
+        | (DynamicValue::U64(_), Type::U64)
+        | (DynamicValue::F64(_), Type::F64)
+        | (DynamicValue::ISize(_), Type::ISize)
+        | (DynamicValue::USize(_), Type::USize)
+        | (DynamicValue::Bool(_), Type::Bool)
+        | (DynamicValue::String(_), Type::String) => Ok(()),
+        (_, Type::Box(b)) => validate(value, b.inner, path, partial),
+        (DynamicValue::Struct(s), Type::Struct(mirror)) => {
+            let expected = mirror.fields.iter().map(|field| (field.name, &field.ty));
+            validate_fields(&s.fields, expected, path, partial)
+        }
+        (DynamicValue::Enum(e), Type::CEnum(mirror)) => {
+            if !mirror.variants.iter().any(|(_, name)| *name == e.variant) {
+                return error(
+                    path,
+                    format!("{} has no variant {}", mirror.name, e.variant),
+                );
+            }
+            if !e.fields.is_empty() {
+                return error(
+                    path,
+                    format!("{}::{} has no fields", mirror.name, e.variant),
+                );
+            }
+            Ok(())
+        }
+        (DynamicValue::Enum(e), Type::RustEnum(mirror)) => {
+            let Some(variant) = mirror.variants.iter().find(|it| it.name == e.variant) else {
+                return error(
+                    path,
+                    format!("{} has no variant {}", mirror.name, e.variant),
+                );
+            };
+            let expected = variant.fields.iter().map(|(name, ty)| (*name, ty));
+            validate_fields(&e.fields, expected, path, partial)
+        }
+        (DynamicValue::List(elements), Type::Vec(v)) => {
+            validate_list(elements, v.element, v.skip, path, partial)
+        }
+        (DynamicValue::List(elements), Type::HashSet(hs)) => {
+            validate_list(elements, hs.element, hs.skip, path, false)
+        }
+        (DynamicValue::Map(entries), Type::HashMap(hm)) => {
+            if hm.skip && !entries.is_empty() {
+                return error(path, "skipped HashMap has to be empty".to_string());
+            }
+            for (i, (key, value)) in entries.iter().enumerate() {
+                let len = path.len();
+                path.push_str(&format!("[{i}].key"));
+                validate(key, hm.key, path, false)?;
+                path.truncate(len);
+                path.push_str(&format!("[{i}].value"));
+                validate(value, hm.value, path, partial)?;
+                path.truncate(len);
+            }
+            Ok(())
+        }
+        (DynamicValue::Option(inner), Type::Option(o)) => match inner {
+            Some(_) if o.skip => error(path, "skipped Option has to be None".to_string()),
+            Some(inner) => validate(inner, o.element, path, partial),
+            None => Ok(()),
+        },
+        _ => error(
+            path,
+            format!("expected {}, found {}", type_name(ty), value.kind()),
+        ),
+    }
+}
+
+pub(crate) fn validate_fields<'a>(
+    fields: &[(String, DynamicValue)],
+    expected: impl Iterator<Item = (&'a str, &'a Type)> + Clone,
+    path: &mut String,
+    partial: bool,
+) -> Result<(), DynamicError> {
+    let len = path.len();
+    for (name, ty) in expected.clone() {
+        path.push('.');
+        path.push_str(name);
+        match fields.iter().filter(|(n, _)| n == name).count() {
+            0 if partial => {}
+            0 => return error(path, "missing field".to_string()),
+            1 => validate(find_field(fields, name).unwrap(), ty, path, partial)?,
+            _ => return error(path, "duplicate field".to_string()),
+        }
+        path.truncate(len);
+    }
+    let expected_names = expected.map(|(name, _)| name);
+    if let Some((name, _)) = fields
+        .iter()
+        .find(|(name, _)| !expected_names.clone().any(|it| it == name))
+    {
+        path.push('.');
+        path.push_str(name);
+        return error(path, "unknown field".to_string());
+    }
+    Ok(())
+}
+
+pub(crate) fn validate_list(
+    elements: &[DynamicValue],
+    ty: &Type,
+    skip: bool,
+    path: &mut String,
+    partial: bool,
+) -> Result<(), DynamicError> {
+    if skip && !elements.is_empty() {
+        return error(path, "skipped container has to be empty".to_string());
+    }
+    for (i, element) in elements.iter().enumerate() {
+        let len = path.len();
+        path.push_str(&format!("[{i}]"));
+        validate(element, ty, path, partial)?;
+        path.truncate(len);
+    }
+    Ok(())
+}
+
+/// Writes an already validated value.
+unsafe fn write(value: &DynamicValue, ty: &Type, dest: *mut u8) {
+    unsafe {
+        match (value, ty) {
+            (DynamicValue::I32(val), Type::I32) => (dest as *mut i32).write(*val),
+            (DynamicValue::U32(val), Type::U32) => (dest as *mut u32).write(*val),
+            (DynamicValue::F32(val), Type::F32) => (dest as *mut f32).write(*val),
+            (DynamicValue::I64(val), Type::I64) => (dest as *mut i64).write(*val),
+            (DynamicValue::U64(val), Type::U64) => (dest as *mut u64).write(*val),
+            (DynamicValue::F64(val), Type::F64) => (dest as *mut f64).write(*val),
+            (DynamicValue::ISize(val), Type::ISize) => (dest as *mut isize).write(*val),
+            (DynamicValue::USize(val), Type::USize) => (dest as *mut usize).write(*val),
+            (DynamicValue::Bool(val), Type::Bool) => (dest as *mut bool).write(*val),
+            (DynamicValue::String(val), Type::String) => (dest as *mut String).write(val.clone()),
+            (_, Type::Box(b)) => (b.box_up)(dest, box_value(value, b.inner)),
+            (DynamicValue::Struct(s), Type::Struct(mirror)) => {
+                for field in mirror.fields {
+                    let value = find_field(&s.fields, field.name).unwrap();
+                    write(value, &field.ty, dest.add(field.offset));
+                }
+            }
+            (DynamicValue::Enum(e), Type::CEnum(mirror)) => {
+                let (val, _) = mirror
+                    .variants
+                    .iter()
+                    .find(|(_, name)| *name == e.variant)
+                    .unwrap();
+                debug_assert_eq!(mirror.size, size_of::<i32>());
+                (dest as *mut i32).write(*val);
+            }
+            (DynamicValue::Enum(e), Type::RustEnum(mirror)) => {
+                let idx = mirror
+                    .variants
+                    .iter()
+                    .position(|it| it.name == e.variant)
+                    .unwrap();
+                let fields: Vec<*mut u8> = mirror.variants[idx]
+                    .fields
+                    .iter()
+                    .map(|(name, ty)| box_value(find_field(&e.fields, name).unwrap(), ty))
+                    .collect();
+                (mirror.write)(dest, idx, &fields);
+            }
+            (DynamicValue::List(elements), Type::Vec(v)) => {
+                let first = (v.vtable.new_at)(dest, elements.len());
+                if v.skip {
+                    return;
+                }
+                let stride = v.element.layout().size();
+                for (i, element) in elements.iter().enumerate() {
+                    write(element, v.element, first.add(stride * i));
+                }
+                (v.vtable.set_len)(dest, elements.len());
+            }
+            (DynamicValue::List(elements), Type::HashSet(hs)) => {
+                (hs.vtable.new_at)(dest);
+                for element in elements {
+                    (hs.vtable.fill_with)(dest, box_value(element, hs.element));
+                }
+            }
+            (DynamicValue::Map(entries), Type::HashMap(hm)) => {
+                (hm.vtable.new_at)(dest);
+                for (key, value) in entries {
+                    let key = box_value(key, hm.key);
+                    let value = box_value(value, hm.value);
+                    (hm.vtable.fill_with)(dest, key, value);
+                }
+            }
+            (DynamicValue::Option(inner), Type::Option(o)) => {
+                (o.vtable.new_at)(dest);
+                if let Some(inner) = inner {
+                    (o.vtable.set)(dest, Some(box_value(inner, o.element)));
+                }
+            }
+            _ => unreachable!("value was validated"),
+        }
+    }
+}
+
+/// Writes an already validated value to a new heap allocation, like `Box::into_raw` would return.
+pub(crate) unsafe fn box_value(value: &DynamicValue, ty: &Type) -> *mut u8 {
+    let ptr = alloc(ty.layout());
+    unsafe { write(value, ty, ptr) };
+    ptr
+}
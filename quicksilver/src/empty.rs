@@ -2,6 +2,7 @@ use crate::option::EmptyOptionVtableCreator;
 use crate::set::EmptyHSVtableCreator;
 use crate::{HMType, Struct, Type, VecType, map::EmptyHMVtableCreator, vec::EmptyVecVtableCreator};
 use crate::{HSType, OptionType};
+use std::any::TypeId;
 use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
 
@@ -9,11 +10,18 @@ pub trait EmptyContainer {
     const EMPTY: Type;
 }
 
+/// Stands in for the element type of skipped containers.
+struct Skipped;
+
 const EMPTY_STRUCT: Type = Type::Struct(&Struct {
     size: 0,
-    align: 0,
+    align: 1,
     name: "skipped",
+    type_id: TypeId::of::<Skipped>(),
     fields: &[],
+    index_of: |_| None,
+    drop: |_| {},
+    default: None,
 });
 
 impl<T> EmptyContainer for Vec<T> {
@@ -1,316 +1,1519 @@
+use std::cmp::Ordering;
+use std::fmt::{self, Write};
+use std::io;
 use std::mem::MaybeUninit;
+mod input;
 mod parser;
 
-use parser::{JsonWalker, peek};
+use input::Input;
+use parser::{JsonWalker, Position};
 
 use crate::{
-    Quicksilver, Struct, Type,
-    reflections::{StructReflection, ValueReflection},
+    CEnum, HMType, HSType, OptionType, Quicksilver, RustEnum, RustEnumVariant, Struct, Type,
+    VecType,
+    compare::cmp_values,
+    cursor::{Cursor, CursorValue, cursor},
+    default::{alloc, box_default, construct_default, dealloc, drop_alloc},
+    dynamic::{DynamicEnum, DynamicStruct, DynamicValue},
+    reflections::{
+        Access, FieldReflection, Mut, StructReflection, ValueMut, ValueReflection, reflect,
+    },
+    reflections_ref::reflect_value_ref,
 };
 
-impl<'a> StructReflection<'a> {
+impl<A: Access> StructReflection<'_, A> {
     pub fn to_json(&self) -> String {
-        let mut json_parts = Vec::new();
-        for field in &self.fields {
-            let field_name = format!("\"{}\"", field.name);
-            let field_value = value_to_json(&field.value);
-            json_parts.push(format!("{}:{}", field_name, field_value));
-        }
-        format!("{{{}}}", json_parts.join(","))
+        self.to_json_with(&JsonOptions::default())
+    }
+
+    pub fn to_json_with(&self, options: &JsonOptions) -> String {
+        into_string(|out| {
+            let mut writer = JsonWriter::new(out, options);
+            writer.write_reflected_fields(None, self.fields())?;
+            writer.finish()
+        })
     }
 }
 
-impl<'a> ValueReflection<'a> {
+impl<A: Access> ValueReflection<'_, A> {
     pub fn to_json(&self) -> String {
         value_to_json(self)
     }
 }
 
-pub fn value_to_json(vr: &ValueReflection) -> String {
-    match vr {
-        ValueReflection::I32(val) => format!("{}", **val),
-        ValueReflection::CEnum(cenum_reflection) => format!("{}", *cenum_reflection.val),
-        ValueReflection::U32(val) => format!("{}", **val),
-        ValueReflection::F32(val) => format!("{}", **val),
-        ValueReflection::I64(val) => format!("{}", **val),
-        ValueReflection::U64(val) => format!("{}", **val),
-        ValueReflection::F64(val) => format!("{}", **val),
-        ValueReflection::ISize(val) => format!("{}", **val),
-        ValueReflection::USize(val) => format!("{}", **val),
-        ValueReflection::Bool(val) => format!("{}", **val),
-        ValueReflection::String(val) => {
-            let escaped_val = val.replace(r"\", r"\\").replace(r#"""#, r#"\""#);
-            format!("\"{}\"", escaped_val)
-        }
-        ValueReflection::Struct(s_ref) => s_ref.to_json(),
-        ValueReflection::Vec(vec_reflection) => {
-            if vec_reflection.skip {
-                "[]".to_string()
-            } else {
-                let mut ret = "[".to_string();
-                let len = vec_reflection.len();
-                let mut first = true;
-                for i in 0..len {
-                    if !first {
-                        ret.push(',');
+/// Serializes `val` to json, without reflecting it up front.
+pub fn to_json<T: Quicksilver>(val: &T) -> String {
+    to_json_with(val, &JsonOptions::default())
+}
+
+/// Serializes `val` to json with the given options, see [`JsonOptions::pretty`].
+///
+/// # Panics
+/// If a float isn't finite and `options.non_finite` is [`NonFinite::Error`].
+pub fn to_json_with<T: Quicksilver>(val: &T, options: &JsonOptions) -> String {
+    into_string(|out| to_json_writer_with(val, out, options))
+}
+
+/// Serializes `val` to json and streams it to `out`, without building the whole json in memory.
+///
+/// `out` is written in chunks, so it doesn't need to be buffered.
+pub fn to_json_writer<T: Quicksilver>(val: &T, out: impl io::Write) -> io::Result<()> {
+    to_json_writer_with(val, out, &JsonOptions::default())
+}
+
+/// Like [`to_json_writer`] with the given options.
+pub fn to_json_writer_with<T: Quicksilver>(
+    val: &T,
+    out: impl io::Write,
+    options: &JsonOptions,
+) -> io::Result<()> {
+    let mut writer = JsonWriter::new(out, options);
+    writer.write_value(cursor(val).get())?;
+    writer.finish()
+}
+
+pub fn value_to_json<A: Access>(vr: &ValueReflection<'_, A>) -> String {
+    value_to_json_with(vr, &JsonOptions::default())
+}
+
+pub fn value_to_json_with<A: Access>(vr: &ValueReflection<'_, A>, options: &JsonOptions) -> String {
+    into_string(|out| value_to_json_writer(vr, out, options))
+}
+
+/// Like [`to_json_writer_with`] for a reflected value.
+pub fn value_to_json_writer<A: Access>(
+    vr: &ValueReflection<'_, A>,
+    out: impl io::Write,
+    options: &JsonOptions,
+) -> io::Result<()> {
+    let mut writer = JsonWriter::new(out, options);
+    writer.write_reflection(vr)?;
+    writer.finish()
+}
+
+/// Collects the output of a json writer.
+/// Writing to a `Vec` can't fail, only non-finite floats with [`NonFinite::Error`] can.
+fn into_string(write: impl FnOnce(&mut Vec<u8>) -> io::Result<()>) -> String {
+    let mut out = Vec::new();
+    write(&mut out).unwrap_or_else(|e| panic!("{e}"));
+    String::from_utf8(out).expect("json is written as utf-8")
+}
+
+/// The buffer is written to the output once it is this large.
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Writes json to `out` through a reused buffer, keeping track of the indentation.
+struct JsonWriter<'o, W: io::Write> {
+    out: W,
+    buf: String,
+    options: &'o JsonOptions,
+    level: usize,
+}
+
+impl<'o, W: io::Write> JsonWriter<'o, W> {
+    fn new(out: W, options: &'o JsonOptions) -> Self {
+        Self {
+            out,
+            buf: String::with_capacity(CHUNK_SIZE),
+            options,
+            level: 0,
+        }
+    }
+
+    /// Writes the rest of the buffer.
+    fn finish(mut self) -> io::Result<()> {
+        self.out.write_all(self.buf.as_bytes())?;
+        self.out.flush()
+    }
+
+    fn newline(&mut self) {
+        if let Some(indent) = self.options.indent {
+            self.buf.push('\n');
+            let width = indent * self.level;
+            write!(self.buf, "{:width$}", "").unwrap();
+        }
+    }
+
+    fn open(&mut self, c: char) {
+        self.buf.push(c);
+        self.level += 1;
+    }
+
+    /// Goes to the line of the next field or element, `i` counts them.
+    /// Full chunks are written to the output here.
+    fn next(&mut self, i: usize) -> io::Result<()> {
+        if i > 0 {
+            self.buf.push(',');
+        }
+        if self.buf.len() >= CHUNK_SIZE {
+            self.out.write_all(self.buf.as_bytes())?;
+            self.buf.clear();
+        }
+        self.newline();
+        Ok(())
+    }
+
+    /// `empty` containers are closed on the same line.
+    fn close(&mut self, c: char, empty: bool) {
+        self.level -= 1;
+        if !empty {
+            self.newline();
+        }
+        self.buf.push(c);
+    }
+
+    fn key(&mut self, name: &str) {
+        write_string(&mut self.buf, name);
+        self.buf.push(':');
+        if self.options.indent.is_some() {
+            self.buf.push(' ');
+        }
+    }
+
+    fn write_reflection<A: Access>(&mut self, vr: &ValueReflection<'_, A>) -> io::Result<()> {
+        match vr {
+            // fields are read through their reflections,
+            // reading through the struct pointer would invalidate mutable field reflections
+            ValueReflection::Struct(s) => self.write_reflected_fields(None, s.fields()),
+            ValueReflection::RustEnum(renum) => self.write_variant(
+                renum.variant_name,
+                renum.fields().is_empty(),
+                |this, tag| this.write_reflected_fields(tag, renum.fields()),
+            ),
+            ValueReflection::Box(b) => self.write_reflection(&b.inner),
+            _ => self.write_value(vr.leaf_cursor().unwrap()),
+        }
+    }
+
+    fn write_reflected_fields<A: Access>(
+        &mut self,
+        tag: Option<(&str, &str)>,
+        fields: &[FieldReflection<'_, A>],
+    ) -> io::Result<()> {
+        self.open('{');
+        let mut i = 0;
+        if let Some(tag) = tag {
+            self.write_tag(tag)?;
+            i += 1;
+        }
+        for field in fields {
+            self.next(i)?;
+            self.key(field.name);
+            self.write_reflection(&field.value)?;
+            i += 1;
+        }
+        self.close('}', i == 0);
+        Ok(())
+    }
+
+    fn write_value(&mut self, value: CursorValue) -> io::Result<()> {
+        match value {
+            CursorValue::I32(val) => write!(self.buf, "{val}").unwrap(),
+            CursorValue::U32(val) => write!(self.buf, "{val}").unwrap(),
+            CursorValue::F32(val) => return self.write_float(val, val.is_finite()),
+            CursorValue::I64(val) => write!(self.buf, "{val}").unwrap(),
+            CursorValue::U64(val) => write!(self.buf, "{val}").unwrap(),
+            CursorValue::F64(val) => return self.write_float(val, val.is_finite()),
+            CursorValue::ISize(val) => write!(self.buf, "{val}").unwrap(),
+            CursorValue::USize(val) => write!(self.buf, "{val}").unwrap(),
+            CursorValue::Bool(val) => write!(self.buf, "{val}").unwrap(),
+            CursorValue::String(val) => write_string(&mut self.buf, val),
+            CursorValue::CEnum(cenum) => {
+                let variant = cenum.mirror.variants.iter().find(|it| it.0 == cenum.val);
+                match variant {
+                    Some((_, name)) if self.options.cenum_names => {
+                        write_string(&mut self.buf, name)
                     }
-                    ret.push_str(&value_to_json(&vec_reflection.get_ref(i)));
-                    first = false;
+                    _ => write!(self.buf, "{}", cenum.val).unwrap(),
                 }
-                ret.push(']');
-                ret
+            }
+            CursorValue::Struct(s) => return self.write_fields(None, s.fields()),
+            CursorValue::RustEnum(renum) => {
+                let mut fields = renum.fields().peekable();
+                let unit = fields.peek().is_none();
+                return self.write_variant(renum.variant_name(), unit, |this, tag| {
+                    this.write_fields(tag, fields)
+                });
+            }
+            CursorValue::Vec(v) => return self.write_list(v.len(), v.element_ty(), v.iter()),
+            CursorValue::HashSet(hs) if self.options.sort_collections => {
+                let mut elements: Vec<_> = hs.iter().collect();
+                elements.sort_by(|a, b| cmp_cursors(a, b));
+                return self.write_list(elements.len(), hs.element_ty(), elements.into_iter());
+            }
+            CursorValue::HashSet(hs) => {
+                return self.write_list(hs.len(), hs.element_ty(), hs.iter());
+            }
+            CursorValue::HashMap(hm) if self.options.sort_collections => {
+                let mut entries: Vec<_> = hm.iter().collect();
+                entries.sort_by(|a, b| cmp_cursors(&a.0, &b.0));
+                return self.write_entries(hm.key_ty(), entries.into_iter());
+            }
+            CursorValue::HashMap(hm) => return self.write_entries(hm.key_ty(), hm.iter()),
+            CursorValue::Option(o) if self.options.null_options => match o.get() {
+                Some(element) => return self.write_value(element.get()),
+                None => self.buf.push_str("null"),
+            },
+            CursorValue::Option(o) => {
+                let element = o.get();
+                return self.write_list(element.iter().len(), o.element_ty(), element.into_iter());
             }
         }
-        ValueReflection::HashSet(hsreflection) => {
-            if hsreflection.skip {
-                "[]".to_string()
-            } else {
-                let vec_reflection = &mut hsreflection.get_elements_ref();
-                let mut ret = "[".to_string();
-                let mut first = true;
-                for elem in vec_reflection {
-                    if !first {
-                        ret.push(',');
-                    }
-                    ret.push_str(&elem.to_json());
-                    first = false;
+        Ok(())
+    }
+
+    /// Non-finite floats aren't valid json numbers, they are written as `options.non_finite` says.
+    fn write_float(&mut self, val: impl fmt::Display, finite: bool) -> io::Result<()> {
+        if finite {
+            write!(self.buf, "{val}").unwrap();
+            return Ok(());
+        }
+        match self.options.non_finite {
+            NonFinite::Null => self.buf.push_str("null"),
+            NonFinite::String => write!(self.buf, r#""{val}""#).unwrap(),
+            NonFinite::Error => {
+                let msg = format!("{val} can't be written as json");
+                return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes an enum variant tagged as `options.enum_tagging` says.
+    /// `fields` writes the fields as an object, starting with the tag field it is given.
+    fn write_variant(
+        &mut self,
+        variant: &str,
+        unit: bool,
+        fields: impl FnOnce(&mut Self, Option<(&str, &str)>) -> io::Result<()>,
+    ) -> io::Result<()> {
+        match self.options.enum_tagging {
+            EnumTagging::Internal { tag } => return fields(self, Some((tag, variant))),
+            EnumTagging::External if unit => write_string(&mut self.buf, variant),
+            EnumTagging::External => {
+                self.open('{');
+                self.next(0)?;
+                self.key(variant);
+                fields(self, None)?;
+                self.close('}', false);
+            }
+            EnumTagging::Adjacent { tag, content } => {
+                self.open('{');
+                self.write_tag((tag, variant))?;
+                if !unit {
+                    self.next(1)?;
+                    self.key(content);
+                    fields(self, None)?;
                 }
-                ret.push(']');
-                ret
+                self.close('}', false);
             }
         }
-        ValueReflection::HashMap(hmreflection) => {
-            if hmreflection.skip {
-                "[]".to_string()
+        Ok(())
+    }
+
+    /// Writes the first field of an object, naming the enum variant.
+    fn write_tag(&mut self, (tag, variant): (&str, &str)) -> io::Result<()> {
+        self.next(0)?;
+        self.key(tag);
+        write_string(&mut self.buf, variant);
+        Ok(())
+    }
+
+    fn write_fields<'a>(
+        &mut self,
+        tag: Option<(&str, &str)>,
+        fields: impl Iterator<Item = (&'a str, Cursor<'a>)>,
+    ) -> io::Result<()> {
+        self.open('{');
+        let mut i = 0;
+        if let Some(tag) = tag {
+            self.write_tag(tag)?;
+            i += 1;
+        }
+        for (name, value) in fields {
+            self.next(i)?;
+            self.key(name);
+            self.write_value(value.get())?;
+            i += 1;
+        }
+        self.close('}', i == 0);
+        Ok(())
+    }
+
+    fn write_list<'a>(
+        &mut self,
+        len: usize,
+        element: &Type,
+        elements: impl Iterator<Item = Cursor<'a>>,
+    ) -> io::Result<()> {
+        let compact = self.options.indent.is_none()
+            || self.options.compact_arrays.is_some_and(|max| len <= max) && is_scalar(element);
+        if compact && self.options.indent.is_some() {
+            // short, so this doesn't need to go through `next`
+            self.buf.push('[');
+            for (i, element) in elements.enumerate() {
+                if i > 0 {
+                    self.buf.push_str(", ");
+                }
+                self.write_value(element.get())?;
+            }
+            self.buf.push(']');
+            return Ok(());
+        }
+        self.open('[');
+        for (i, element) in elements.enumerate() {
+            self.next(i)?;
+            self.write_value(element.get())?;
+        }
+        self.close(']', len == 0);
+        Ok(())
+    }
+
+    fn write_entries<'a>(
+        &mut self,
+        key: &Type,
+        entries: impl Iterator<Item = (Cursor<'a>, Cursor<'a>)>,
+    ) -> io::Result<()> {
+        if is_object_map(self.options, key) {
+            self.open('{');
+            let mut i = 0;
+            for (key, value) in entries {
+                let CursorValue::String(key) = key.get() else {
+                    unreachable!()
+                };
+                self.next(i)?;
+                self.key(key);
+                self.write_value(value.get())?;
+                i += 1;
+            }
+            self.close('}', i == 0);
+            return Ok(());
+        }
+        self.open('[');
+        let mut i = 0;
+        for (key, value) in entries {
+            self.next(i)?;
+            self.open('{');
+            self.newline();
+            self.key("key");
+            self.write_value(key.get())?;
+            self.buf.push(',');
+            self.newline();
+            self.key("value");
+            self.write_value(value.get())?;
+            self.close('}', false);
+            i += 1;
+        }
+        self.close(']', i == 0);
+        Ok(())
+    }
+}
+
+/// Maps with string keys can be written as objects, see [`JsonOptions::object_maps`].
+fn is_object_map(options: &JsonOptions, key: &Type) -> bool {
+    options.object_maps && matches!(key, Type::String)
+}
+
+/// Numbers, bools, strings and C-like enums, which are written without nesting.
+fn is_scalar(ty: &Type) -> bool {
+    match ty {
+        Type::Box(b) => is_scalar(b.inner),
+        Type::Struct(_)
+        | Type::RustEnum(_)
+        | Type::Vec(_)
+        | Type::HashMap(_)
+        | Type::HashSet(_)
+        | Type::Option(_) => false,
+        _ => true,
+    }
+}
+
+/// Orders values like [`cmp_values`], for sorted output.
+fn cmp_cursors(a: &Cursor, b: &Cursor) -> Ordering {
+    unsafe {
+        cmp_values(
+            &reflect_value_ref(a.ptr(), a.ty()),
+            &reflect_value_ref(b.ptr(), b.ty()),
+        )
+    }
+}
+
+fn write_string(out: &mut String, val: &str) {
+    out.push('"');
+    // copy everything between escaped chars in one go
+    let mut unescaped = 0;
+    for (i, c) in val.char_indices() {
+        let escape = match c {
+            '\\' => r"\\",
+            '"' => r#"\""#,
+            '\n' => r"\n",
+            '\r' => r"\r",
+            '\t' => r"\t",
+            '\u{8}' => r"\b",
+            '\u{c}' => r"\f",
+            c if c < ' ' => "",
+            _ => continue,
+        };
+        out.push_str(&val[unescaped..i]);
+        match escape {
+            "" => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            escape => out.push_str(escape),
+        }
+        unescaped = i + c.len_utf8();
+    }
+    out.push_str(&val[unescaped..]);
+    out.push('"');
+}
+
+/// Why json couldn't be deserialized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonError {
+    /// starting at 1
+    pub line: usize,
+    /// in chars, starting at 1
+    pub column: usize,
+    /// path of the value being parsed, like `.tiles[3].kind`, empty for the root value
+    pub path: String,
+    pub expected: String,
+    pub found: String,
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            line,
+            column,
+            path,
+            expected,
+            found,
+        } = self;
+        write!(f, "expected {expected}, found {found}")?;
+        if !path.is_empty() {
+            write!(f, " at {path}")?;
+        }
+        write!(f, " (line {line}, column {column})")
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+/// Settings for reading and writing json.
+#[derive(Debug, Clone, Default)]
+pub struct JsonOptions {
+    /// Fields which don't exist on a struct or enum variant are an error, instead of being skipped.
+    pub deny_unknown_fields: bool,
+    /// Write every field and element on its own line, indented by this many spaces per level.
+    /// Everything is written on one line if `None`.
+    pub indent: Option<usize>,
+    /// With `indent`, arrays of at most this many numbers, bools or strings stay on one line,
+    /// like `[1, 2]`.
+    pub compact_arrays: Option<usize>,
+    /// Write map entries and set elements in the order of [`cmp_values`],
+    /// so the output doesn't change with the hash order.
+    pub sort_collections: bool,
+    /// How NaN and infinite floats are written and read, json numbers can't express them.
+    pub non_finite: NonFinite,
+    /// Integers can also be read from floats without a fractional part, like `3.0` or `1e3`.
+    pub integral_floats: bool,
+    /// Write options as `null` or their value instead of `[]` or `[value]`.
+    /// `Some` can't be told apart from `None` then if its value is written as `null` too,
+    /// like a nested `None` or a NaN with [`NonFinite::Null`].
+    pub null_options: bool,
+    /// Write maps with `String` keys as objects like `{"key": value}`,
+    /// instead of lists of `{"key": key, "value": value}` entries.
+    pub object_maps: bool,
+    /// How the variant of a Rust enum is written.
+    /// With internal and adjacent tagging the tag has to be the first key when reading,
+    /// objects aren't buffered to look for it.
+    pub enum_tagging: EnumTagging,
+    /// Write C-like enums as the name of their variant instead of its number,
+    /// so reordering or renumbering the variants doesn't change what is read.
+    /// Names and numbers are both read either way.
+    pub cenum_names: bool,
+}
+
+/// How the variant of a Rust enum is written, see [`JsonOptions::enum_tagging`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnumTagging {
+    /// `{"Variant": {"field": ..}}`, variants without fields as `"Variant"`.
+    External,
+    /// `{"tag": "Variant", "field": ..}`, the tag has to be the first key when reading.
+    Internal { tag: &'static str },
+    /// `{"tag": "Variant", "content": {"field": ..}}`,
+    /// variants without fields without content. When reading, the tag has to be the first key
+    /// and the content has to follow it directly.
+    Adjacent {
+        tag: &'static str,
+        content: &'static str,
+    },
+}
+
+impl Default for EnumTagging {
+    /// `{"__enum_variant": "Variant", "field": ..}`
+    fn default() -> Self {
+        EnumTagging::Internal {
+            tag: "__enum_variant",
+        }
+    }
+}
+
+/// Representation of NaN and infinite floats, see [`JsonOptions::non_finite`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NonFinite {
+    /// Written as `null`, which is read back as NaN. Infinities become NaN this way.
+    #[default]
+    Null,
+    /// Written as the strings `"NaN"`, `"inf"` and `"-inf"`, which round trip exactly.
+    String,
+    /// Writing them is an error, so is reading anything but a number.
+    Error,
+}
+
+impl JsonOptions {
+    /// Readable output with stable diffs, e.g. for files in version control.
+    pub fn pretty() -> Self {
+        Self {
+            indent: Some(4),
+            compact_arrays: Some(4),
+            sort_collections: true,
+            ..Self::default()
+        }
+    }
+}
+
+/// Deserializes json written by [`to_json`].
+///
+/// # Panics
+/// If the json is malformed, see [`try_from_json`].
+pub fn from_json<T: Quicksilver>(s: &str) -> T {
+    try_from_json(s).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Deserializes json written by [`to_json`] or by hand with default options.
+///
+/// On errors everything deserialized so far is dropped again.
+pub fn try_from_json<T: Quicksilver>(s: &str) -> Result<T, JsonError> {
+    try_from_json_with(s, JsonOptions::default())
+}
+
+/// Deserializes json with the given options.
+///
+/// Whitespace is allowed between tokens and fields can come in any order,
+/// but an enum's tag has to come before its fields, see [`EnumTagging`].
+/// All fields have to be present.
+pub fn try_from_json_with<T: Quicksilver>(s: &str, options: JsonOptions) -> Result<T, JsonError> {
+    deserialize_root(&mut JsonWalker::new(s, options))
+}
+
+/// Deserializes json from `reader` while reading it, without loading all of it into memory.
+///
+/// `reader` is read in chunks, so it doesn't need to be buffered.
+/// I/O errors and invalid utf-8 end the input, they are reported as what was `found`.
+pub fn from_json_reader<T: Quicksilver>(reader: impl io::Read) -> Result<T, JsonError> {
+    from_json_reader_with(reader, JsonOptions::default())
+}
+
+/// Like [`from_json_reader`] with the given options.
+pub fn from_json_reader_with<T: Quicksilver>(
+    reader: impl io::Read,
+    options: JsonOptions,
+) -> Result<T, JsonError> {
+    deserialize_root(&mut JsonWalker::with_input(Input::reader(reader), options))
+}
+
+fn deserialize_root<T: Quicksilver>(walker: &mut JsonWalker) -> Result<T, JsonError> {
+    let mut result: MaybeUninit<T> = MaybeUninit::uninit();
+    let result = unsafe {
+        deserialize_field(walker, result.as_mut_ptr() as *mut u8, &T::MIRROR)?;
+        result.assume_init()
+    };
+    walker.consume_end()?;
+    Ok(result)
+}
+
+/// Deserializes json into an existing value, overwriting it in place.
+///
+/// Skipped containers keep their contents, `Vec`s reuse their allocation
+/// and their elements are overwritten in place. Replaced values are dropped.
+///
+/// # Panics
+/// If the json is malformed, see [`try_from_json_into`].
+pub fn from_json_into<T: Quicksilver>(existing: &mut T, s: &str) {
+    try_from_json_into(existing, s).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Like [`from_json_into`], but returns an error on malformed json.
+///
+/// `existing` stays valid on errors, but may already be partially overwritten.
+pub fn try_from_json_into<T: Quicksilver>(existing: &mut T, s: &str) -> Result<(), JsonError> {
+    try_from_json_into_with(existing, s, JsonOptions::default())
+}
+
+/// Like [`try_from_json_into`] with the given options.
+///
+/// Fields missing from the json keep their values.
+pub fn try_from_json_into_with<T: Quicksilver>(
+    existing: &mut T,
+    s: &str,
+    options: JsonOptions,
+) -> Result<(), JsonError> {
+    let walker = &mut JsonWalker::new(s, options);
+    deserialize_in_place(walker, &mut reflect(existing))?;
+    walker.consume_end()
+}
+
+/// Returns `result`, calling `cleanup` first if it is an error.
+fn or_cleanup<T>(result: Result<T, JsonError>, cleanup: impl FnOnce()) -> Result<T, JsonError> {
+    if result.is_err() {
+        cleanup();
+    }
+    result
+}
+
+/// Reads a C-like enum by the name or number of its variant, which has to exist.
+fn consume_cenum(walker: &mut JsonWalker, cenum: &CEnum) -> Result<i32, JsonError> {
+    let expected = format!("a variant of {}", cenum.name);
+    let found = if walker.peek_some(&expected)? == '"' {
+        let start = walker.position();
+        let name = walker.consume_string()?;
+        if let Some((val, _)) = cenum.variants.iter().find(|it| it.1 == name) {
+            return Ok(*val);
+        }
+        (start, format!("\"{name}\""))
+    } else {
+        let start = walker.position();
+        let val: i32 = walker.consume_int()?;
+        if cenum.variants.iter().any(|it| it.0 == val) {
+            return Ok(val);
+        }
+        (start, val.to_string())
+    };
+    let variants: Vec<_> = cenum
+        .variants
+        .iter()
+        .map(|(val, name)| format!("{name} = {val}"))
+        .collect();
+    let expected = format!("{expected} ({})", variants.join(", "));
+    Err(walker.error_at(found.0, &expected, found.1))
+}
+
+/// Where the fields of an enum variant are, after [`consume_variant`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum VariantFields {
+    /// after the tag, in the object of the enum
+    Tagged,
+    /// in an object of their own, whose `{` is consumed, inside the object of the enum
+    Nested,
+    /// nowhere, the enum is consumed already
+    Unit,
+}
+
+impl VariantFields {
+    /// Whether the next key is the first in its object.
+    fn first(self) -> bool {
+        self == VariantFields::Nested
+    }
+
+    /// Consumes the rest of the enum, after its fields and their `}`.
+    fn consume_end(self, walker: &mut JsonWalker) -> Result<(), JsonError> {
+        match self {
+            VariantFields::Nested => walker.consume_char('}'),
+            VariantFields::Tagged | VariantFields::Unit => Ok(()),
+        }
+    }
+}
+
+/// Reads a Rust enum up to its fields, tagged as `options.enum_tagging` says.
+/// The variant has to exist.
+fn consume_variant(
+    walker: &mut JsonWalker,
+    mirror: &'static RustEnum,
+) -> Result<(usize, &'static RustEnumVariant, VariantFields), JsonError> {
+    let tagging = walker.options.enum_tagging;
+    let (start, name, fields) = match tagging {
+        EnumTagging::Internal { tag } | EnumTagging::Adjacent { tag, .. } => {
+            walker.consume_char('{')?;
+            // nothing is buffered, so the tag can't come after the fields
+            walker.consume_field(tag).map_err(|err| JsonError {
+                expected: format!("\"{tag}\" as the first key"),
+                ..err
+            })?;
+            (
+                walker.position(),
+                walker.consume_string()?,
+                VariantFields::Tagged,
+            )
+        }
+        EnumTagging::External if walker.peek_some("'{'")? == '"' => (
+            walker.position(),
+            walker.consume_string()?,
+            VariantFields::Unit,
+        ),
+        EnumTagging::External => {
+            walker.consume_char('{')?;
+            let expected = format!("a variant of {}", mirror.name);
+            let start = walker
+                .next_key(true)?
+                .ok_or_else(|| walker.error(&expected))?;
+            let name = walker.key().to_string();
+            (start, name, VariantFields::Nested)
+        }
+    };
+    let (index, variant) = mirror
+        .variants
+        .iter()
+        .enumerate()
+        .find(|(_, val)| val.name == name)
+        .ok_or_else(|| {
+            let expected = format!("a variant of {}", mirror.name);
+            walker.error_at(start, &expected, format!("\"{name}\""))
+        })?;
+    // the content of adjacently tagged variants comes after the tag
+    let fields = match tagging {
+        EnumTagging::Adjacent { .. } if walker.peek_some("'}'")? == '}' => {
+            walker.consume_char('}')?;
+            VariantFields::Unit
+        }
+        EnumTagging::Adjacent { tag, content } => {
+            walker.consume_char(',')?;
+            walker.consume_field(content).map_err(|err| JsonError {
+                expected: format!("\"{content}\" right after \"{tag}\""),
+                ..err
+            })?;
+            VariantFields::Nested
+        }
+        EnumTagging::Internal { .. } | EnumTagging::External => fields,
+    };
+    match fields {
+        VariantFields::Nested => walker.consume_char('{')?,
+        VariantFields::Unit if !variant.fields.is_empty() => {
+            let expected = format!("the fields of {}", variant.name);
+            return Err(walker.error_at(start, &expected, format!("\"{name}\"")));
+        }
+        _ => {}
+    }
+    Ok((index, variant, fields))
+}
+
+fn deserialize_in_place(walker: &mut JsonWalker, target: &mut ValueMut) -> Result<(), JsonError> {
+    match target {
+        ValueReflection::I32(val) => **val = walker.consume_int()?,
+        ValueReflection::U32(val) => **val = walker.consume_int()?,
+        ValueReflection::F32(val) => **val = walker.consume_float()?,
+        ValueReflection::I64(val) => **val = walker.consume_int()?,
+        ValueReflection::U64(val) => **val = walker.consume_int()?,
+        ValueReflection::F64(val) => **val = walker.consume_float()?,
+        ValueReflection::ISize(val) => **val = walker.consume_int()?,
+        ValueReflection::USize(val) => **val = walker.consume_int()?,
+        ValueReflection::Bool(val) => **val = walker.consume_bool()?,
+        ValueReflection::String(val) => **val = walker.consume_string()?,
+        ValueReflection::CEnum(cenum) => *cenum.val = consume_cenum(walker, cenum.mirror)?,
+        ValueReflection::Struct(s) => {
+            walker.consume_char('{')?;
+            deserialize_fields_in_place(walker, s.name, &mut s.fields, true)?;
+            walker.consume_char('}')?;
+        }
+        ValueReflection::Vec(v) if v.skip => walker.skip_value()?,
+        ValueReflection::Vec(v) => {
+            walker.consume_char('[')?;
+            let mut len = 0;
+            while walker.next_element(len == 0)? {
+                if len == v.len() {
+                    v.push_default();
+                }
+                let path_len = walker.path.len();
+                walker.path.push_str(&format!("[{len}]"));
+                deserialize_in_place(walker, &mut v.get(len))?;
+                walker.path.truncate(path_len);
+                len += 1;
+            }
+            v.truncate(len);
+            walker.consume_char(']')?;
+        }
+        ValueReflection::HashMap(hm) if hm.skip => walker.skip_value()?,
+        ValueReflection::HashMap(hm) if is_object_map(&walker.options, hm.key) => {
+            walker.consume_char('{')?;
+            hm.clear();
+            let mut first = true;
+            while walker.next_key(first)?.is_some() {
+                first = false;
+                let (key, value) = unsafe { deserialize_object_entry(walker, hm.key, hm.value)? };
+                unsafe { hm.insert(key, value) };
+            }
+            walker.consume_char('}')?;
+        }
+        ValueReflection::HashMap(hm) => {
+            walker.consume_char('[')?;
+            hm.clear();
+            let mut i = 0;
+            while walker.next_element(i == 0)? {
+                let path_len = walker.path.len();
+                walker.path.push_str(&format!("[{i}]"));
+                let (key, value) = unsafe { deserialize_entry(walker, hm.key, hm.value)? };
+                walker.path.truncate(path_len);
+                unsafe { hm.insert(key, value) };
+                i += 1;
+            }
+            walker.consume_char(']')?;
+        }
+        ValueReflection::HashSet(hs) if hs.skip => walker.skip_value()?,
+        ValueReflection::HashSet(hs) => {
+            walker.consume_char('[')?;
+            hs.clear();
+            let mut i = 0;
+            while walker.next_element(i == 0)? {
+                let path_len = walker.path.len();
+                walker.path.push_str(&format!("[{i}]"));
+                let element = unsafe { deserialize_alloc(walker, hs.element)? };
+                walker.path.truncate(path_len);
+                unsafe { hs.insert(element) };
+                i += 1;
+            }
+            walker.consume_char(']')?;
+        }
+        ValueReflection::Option(o) if o.skip => walker.skip_value()?,
+        ValueReflection::Option(o) if walker.options.null_options => {
+            if walker.consume_null()? {
+                o.set_none();
             } else {
-                let vec_reflection = &mut hmreflection.get_elements_ref();
-                let mut ret = "[".to_string();
-                let mut first = true;
-                for elem in vec_reflection {
-                    if !first {
-                        ret.push(',');
-                    }
-                    ret.push_str(&elem.to_json());
-                    first = false;
+                if o.get().is_none() {
+                    o.set_some_default();
                 }
-                ret.push(']');
-                ret
+                deserialize_in_place(walker, &mut o.get().unwrap())?;
             }
         }
-        ValueReflection::Option(o_reflection) => {
-            if o_reflection.skip {
-                "[]".to_string()
+        ValueReflection::Option(o) => {
+            walker.consume_char('[')?;
+            if !walker.next_element(true)? {
+                o.set_none();
             } else {
-                let mut ret = "[".to_string();
-                if let Some(inner) = o_reflection.get_ref() {
-                    ret.push_str(&inner.to_json());
+                if o.get().is_none() {
+                    o.set_some_default();
                 }
-                ret.push(']');
-                ret
+                deserialize_in_place(walker, &mut o.get().unwrap())?;
             }
+            walker.consume_char(']')?;
         }
         ValueReflection::RustEnum(renum) => {
-            let mut json_parts = Vec::new();
-            json_parts.push(format!(
-                r#""{}":"{}""#,
-                "__enum_variant", renum.variant_name
-            ));
-            for field in &renum.fields {
-                let field_name = format!("\"{}\"", field.name);
-                let field_value = value_to_json(&field.value);
-                json_parts.push(format!("{}:{}", field_name, field_value));
+            let Type::RustEnum(mirror) = renum.ty() else {
+                unreachable!()
+            };
+            let (index, variant, fields) = consume_variant(walker, mirror)?;
+            if variant.name != renum.variant_name {
+                renum.switch_variant(index);
+            }
+            if fields != VariantFields::Unit {
+                let first = fields.first();
+                deserialize_fields_in_place(walker, variant.name, &mut renum.fields, first)?;
+                walker.consume_char('}')?;
+                fields.consume_end(walker)?;
             }
-            format!("{{{}}}", json_parts.join(","))
         }
-        ValueReflection::Box(box_reflection) => value_to_json(&box_reflection.inner),
+        ValueReflection::Box(b) => deserialize_in_place(walker, &mut b.inner)?,
     }
+    Ok(())
 }
 
-pub fn from_json<T: Quicksilver>(s: &str) -> T {
-    let mut result: MaybeUninit<T> = MaybeUninit::uninit();
-    let ptr = result.as_mut_ptr();
-    let walker = &mut JsonWalker {
-        chars: s.chars(),
-        buffer: String::new(),
-    };
-    unsafe {
-        deserialize_field(walker, ptr as *mut u8, &T::MIRROR);
-        result.assume_init()
+/// Reads fields by name until the closing `}`, which is not consumed.
+/// `first` is false if there already was a field, i.e. the enum tag.
+fn deserialize_fields_in_place(
+    walker: &mut JsonWalker,
+    owner: &str,
+    fields: &mut [FieldReflection<'_, Mut>],
+    mut first: bool,
+) -> Result<(), JsonError> {
+    while let Some(start) = walker.next_key(first)? {
+        first = false;
+        let Some(field) = fields.iter_mut().find(|f| f.name == walker.key()) else {
+            walker.unknown_field(start, owner)?;
+            continue;
+        };
+        let path_len = walker.path.len();
+        walker.path.push_str(&format!(".{}", field.name));
+        deserialize_in_place(walker, &mut field.value)?;
+        walker.path.truncate(path_len);
+    }
+    Ok(())
+}
+
+/// Error for a field which was already read.
+fn duplicate_field(walker: &JsonWalker, start: Position) -> JsonError {
+    walker.error_at(
+        start,
+        "each field only once",
+        format!("\"{}\"", walker.key()),
+    )
+}
+
+/// Error at the closing `}` for a field which is missing.
+fn missing_field(walker: &JsonWalker, name: &str) -> JsonError {
+    walker.error(&format!("field \"{name}\""))
+}
+
+/// Skipped containers are always written empty, so they may also be left out
+/// and are default constructed then.
+fn is_skipped(ty: &Type) -> bool {
+    match ty {
+        Type::Vec(v) => v.skip,
+        Type::HashMap(hm) => hm.skip,
+        Type::HashSet(hs) => hs.skip,
+        Type::Option(o) => o.skip,
+        _ => false,
+    }
+}
+
+/// Deserializes a struct, on errors the fields deserialized so far are dropped again.
+unsafe fn deserialize_struct(
+    walker: &mut JsonWalker,
+    base: *mut u8,
+    mirror: &Struct,
+) -> Result<(), JsonError> {
+    let mut done = vec![false; mirror.fields.len()];
+    let result = unsafe { deserialize_struct_fields(walker, base, mirror, &mut done) };
+    or_cleanup(result, || {
+        for (field, _) in mirror.fields.iter().zip(done).filter(|(_, done)| *done) {
+            unsafe { field.ty.drop_in_place(base.add(field.offset)) };
+        }
+    })
+}
+
+/// `done` marks the fields which are fully deserialized.
+unsafe fn deserialize_struct_fields(
+    walker: &mut JsonWalker,
+    base: *mut u8,
+    mirror: &Struct,
+    done: &mut [bool],
+) -> Result<(), JsonError> {
+    walker.consume_char('{')?;
+    let mut first = true;
+    while let Some(start) = walker.next_key(first)? {
+        first = false;
+        let Some(index) = (mirror.index_of)(walker.key()) else {
+            walker.unknown_field(start, mirror.name)?;
+            continue;
+        };
+        if done[index] {
+            return Err(duplicate_field(walker, start));
+        }
+        let field = &mirror.fields[index];
+        let path_len = walker.path.len();
+        walker.path.push_str(&format!(".{}", field.name));
+        unsafe { deserialize_field(walker, base.add(field.offset), &field.ty)? };
+        walker.path.truncate(path_len);
+        done[index] = true;
+    }
+    for (field, done) in mirror.fields.iter().zip(done) {
+        if !*done {
+            if !is_skipped(&field.ty) {
+                return Err(missing_field(walker, field.name));
+            }
+            unsafe { construct_default(&field.ty, base.add(field.offset)) };
+            *done = true;
+        }
+    }
+    walker.consume_char('}')
+}
+
+/// Deserializes into a new allocation from [`alloc`], which is freed again on errors.
+unsafe fn deserialize_alloc(walker: &mut JsonWalker, ty: &Type) -> Result<*mut u8, JsonError> {
+    let layout = ty.layout();
+    let ptr = alloc(layout);
+    let result = unsafe { deserialize_field(walker, ptr, ty) };
+    or_cleanup(result, || unsafe { dealloc(ptr, layout) })?;
+    Ok(ptr)
+}
+
+/// Deserializes a `{"key":..,"value":..}` map entry into new allocations.
+unsafe fn deserialize_entry(
+    walker: &mut JsonWalker,
+    key_ty: &Type,
+    value_ty: &Type,
+) -> Result<(*mut u8, *mut u8), JsonError> {
+    walker.consume_char('{')?;
+    walker.consume_field("key")?;
+    let path_len = walker.path.len();
+    walker.path.push_str(".key");
+    let key = unsafe { deserialize_alloc(walker, key_ty)? };
+    walker.path.truncate(path_len);
+    let value = unsafe { deserialize_entry_value(walker, value_ty) };
+    let value = or_cleanup(value, || unsafe { drop_alloc(key, key_ty) })?;
+    Ok((key, value))
+}
+
+/// Deserializes a `"key": value` entry of a map written as object into new allocations.
+/// The key was read by [`JsonWalker::next_key`].
+unsafe fn deserialize_object_entry(
+    walker: &mut JsonWalker,
+    key_ty: &Type,
+    value_ty: &Type,
+) -> Result<(*mut u8, *mut u8), JsonError> {
+    debug_assert!(matches!(key_ty, Type::String));
+    let key = alloc(key_ty.layout());
+    unsafe { (key as *mut String).write(walker.key().to_string()) };
+    let path_len = walker.path.len();
+    walker.path.push_str(&format!(".{}", walker.key()));
+    let value = unsafe { deserialize_alloc(walker, value_ty) };
+    let value = or_cleanup(value, || unsafe { drop_alloc(key, key_ty) })?;
+    walker.path.truncate(path_len);
+    Ok((key, value))
+}
+
+unsafe fn deserialize_entry_value(
+    walker: &mut JsonWalker,
+    ty: &Type,
+) -> Result<*mut u8, JsonError> {
+    walker.consume_char(',')?;
+    walker.consume_field("value")?;
+    let path_len = walker.path.len();
+    walker.path.push_str(".value");
+    let value = unsafe { deserialize_alloc(walker, ty)? };
+    walker.path.truncate(path_len);
+    or_cleanup(walker.consume_char('}'), || unsafe {
+        drop_alloc(value, ty)
+    })?;
+    Ok(value)
+}
+
+/// `len` counts the elements which are fully deserialized.
+unsafe fn deserialize_vec_elements(
+    walker: &mut JsonWalker,
+    base: *mut u8,
+    v: &VecType,
+    mut first: *mut u8,
+    len: &mut usize,
+) -> Result<(), JsonError> {
+    if v.skip {
+        return walker.skip_value();
+    }
+    walker.consume_char('[')?;
+    let mut cap = VEC_START_CAPACITY;
+    let stride = v.element.layout().size();
+    while walker.next_element(*len == 0)? {
+        debug_assert!(*len <= cap); // sanity check
+        if *len == cap {
+            let extra = 8;
+            cap = *len + extra;
+            // because we don't set len beyond 0 until we are done
+            // reserve needs the capacity we want as parameter
+            first = unsafe { (v.vtable.reserve)(base, cap) };
+        }
+
+        let path_len = walker.path.len();
+        walker.path.push_str(&format!("[{len}]"));
+        unsafe { deserialize_field(walker, first.add(stride * *len), v.element)? };
+        walker.path.truncate(path_len);
+        *len += 1;
+    }
+    walker.consume_char(']')
+}
+
+const VEC_START_CAPACITY: usize = 4;
+
+unsafe fn deserialize_map_entries(
+    walker: &mut JsonWalker,
+    base: *mut u8,
+    hm: &HMType,
+) -> Result<(), JsonError> {
+    if hm.skip {
+        return walker.skip_value();
+    }
+    if is_object_map(&walker.options, hm.key) {
+        walker.consume_char('{')?;
+        let mut first = true;
+        while walker.next_key(first)?.is_some() {
+            first = false;
+            let (key, value) = unsafe { deserialize_object_entry(walker, hm.key, hm.value)? };
+            unsafe { (hm.vtable.fill_with)(base, key, value) };
+        }
+        return walker.consume_char('}');
+    }
+    walker.consume_char('[')?;
+    let mut i = 0;
+    while walker.next_element(i == 0)? {
+        let path_len = walker.path.len();
+        walker.path.push_str(&format!("[{i}]"));
+        let (key, value) = unsafe { deserialize_entry(walker, hm.key, hm.value)? };
+        walker.path.truncate(path_len);
+        unsafe { (hm.vtable.fill_with)(base, key, value) };
+        i += 1;
+    }
+    walker.consume_char(']')
+}
+
+unsafe fn deserialize_set_elements(
+    walker: &mut JsonWalker,
+    base: *mut u8,
+    hs: &HSType,
+) -> Result<(), JsonError> {
+    if hs.skip {
+        return walker.skip_value();
+    }
+    walker.consume_char('[')?;
+    let mut i = 0;
+    while walker.next_element(i == 0)? {
+        let path_len = walker.path.len();
+        walker.path.push_str(&format!("[{i}]"));
+        let element = unsafe { deserialize_alloc(walker, hs.element)? };
+        walker.path.truncate(path_len);
+        unsafe { (hs.vtable.fill_with)(base, element) };
+        i += 1;
+    }
+    walker.consume_char(']')
+}
+
+unsafe fn deserialize_option(
+    walker: &mut JsonWalker,
+    base: *mut u8,
+    o: &OptionType,
+) -> Result<(), JsonError> {
+    if o.skip {
+        return walker.skip_value();
+    }
+    if walker.options.null_options {
+        if walker.consume_null()? {
+            return Ok(());
+        }
+        let element = unsafe { deserialize_alloc(walker, o.element)? };
+        unsafe { (o.vtable.set)(base, Some(element)) };
+        return Ok(());
     }
+    walker.consume_char('[')?;
+    if walker.next_element(true)? {
+        let element = unsafe { deserialize_alloc(walker, o.element)? };
+        unsafe { (o.vtable.set)(base, Some(element)) };
+    }
+    walker.consume_char(']')
 }
 
-unsafe fn deserialize_struct(walker: &mut JsonWalker, base: *mut u8, mirror: &Struct) {
-    walker.consume_char('{');
-    for field in mirror.fields {
-        walker.consume_field(field.name);
-        unsafe { deserialize_field(walker, base.add(field.offset), &field.ty) };
-        walker.consume_maybe(',');
+/// Deserializes the fields of `variant` into new allocations and consumes the rest of the enum,
+/// `field_ptrs` starts out null for every field.
+unsafe fn deserialize_variant_fields(
+    walker: &mut JsonWalker,
+    variant: &RustEnumVariant,
+    fields: VariantFields,
+    field_ptrs: &mut [*mut u8],
+) -> Result<(), JsonError> {
+    if fields == VariantFields::Unit {
+        return Ok(());
+    }
+    let mut first = fields.first();
+    while let Some(start) = walker.next_key(first)? {
+        first = false;
+        let Some(index) = variant.fields.iter().position(|f| f.0 == walker.key()) else {
+            walker.unknown_field(start, variant.name)?;
+            continue;
+        };
+        if !field_ptrs[index].is_null() {
+            return Err(duplicate_field(walker, start));
+        }
+        let (field_name, ty) = &variant.fields[index];
+        let path_len = walker.path.len();
+        walker.path.push_str(&format!(".{field_name}"));
+        field_ptrs[index] = unsafe { deserialize_alloc(walker, ty)? };
+        walker.path.truncate(path_len);
+    }
+    for (ptr, (field_name, ty)) in field_ptrs.iter_mut().zip(variant.fields) {
+        if ptr.is_null() {
+            if !is_skipped(ty) {
+                return Err(missing_field(walker, field_name));
+            }
+            *ptr = box_default(ty);
+        }
     }
-    walker.consume_char('}');
+    walker.consume_char('}')?;
+    fields.consume_end(walker)
 }
 
-unsafe fn deserialize_field(walker: &mut JsonWalker, base: *mut u8, ty: &Type) {
+/// Deserializes a value of type `ty` to `base`.
+///
+/// On errors nothing is written to `base` and everything allocated so far is freed again.
+unsafe fn deserialize_field(
+    walker: &mut JsonWalker,
+    base: *mut u8,
+    ty: &Type,
+) -> Result<(), JsonError> {
     match ty {
         Type::I32 => unsafe {
             let ptr = base as *mut i32;
-            let val = walker.consume_int();
+            let val = walker.consume_int()?;
             ptr.write(val);
         },
         Type::CEnum(cenum) => unsafe {
             let ptr = base as *mut i32;
-            let val: i32 = walker.consume_int();
-            assert!(cenum.variants.iter().any(|it| it.0 == val));
+            let val = consume_cenum(walker, cenum)?;
             debug_assert_eq!(cenum.size, size_of::<i32>());
             ptr.write(val);
         },
         Type::U32 => unsafe {
             let ptr = base as *mut u32;
-            let val = walker.consume_int();
+            let val = walker.consume_int()?;
             ptr.write(val);
         },
         Type::F32 => unsafe {
             let ptr = base as *mut f32;
-            let val = walker.consume_float();
+            let val = walker.consume_float()?;
             ptr.write(val);
         },
         Type::I64 => unsafe {
             let ptr = base as *mut i64;
-            let val = walker.consume_int();
+            let val = walker.consume_int()?;
             ptr.write(val);
         },
         Type::U64 => unsafe {
             let ptr = base as *mut u64;
-            let val = walker.consume_int();
+            let val = walker.consume_int()?;
             ptr.write(val);
         },
         Type::F64 => unsafe {
             let ptr = base as *mut f64;
-            let val = walker.consume_int();
+            let val = walker.consume_float()?;
             ptr.write(val);
         },
         Type::ISize => unsafe {
             let ptr = base as *mut isize;
-            let val = walker.consume_int();
+            let val = walker.consume_int()?;
             ptr.write(val);
         },
         Type::USize => unsafe {
             let ptr = base as *mut usize;
-            let val = walker.consume_int();
+            let val = walker.consume_int()?;
             ptr.write(val);
         },
         Type::Bool => unsafe {
             let ptr = base as *mut bool;
-            let val = walker.consume_bool();
+            let val = walker.consume_bool()?;
             ptr.write(val);
         },
         Type::String => unsafe {
             let ptr = base as *mut String;
-            let val = walker.consume_string();
+            let val = walker.consume_string()?;
             ptr.write(val);
         },
         Type::Struct(inner_mirror) => unsafe {
-            deserialize_struct(walker, base, inner_mirror);
+            deserialize_struct(walker, base, inner_mirror)?;
         },
         Type::Vec(v) => unsafe {
-            walker.consume_char('[');
-            let mut cap = 4;
-            let mut first = (v.vtable.new_at)(base, cap);
+            let first = (v.vtable.new_at)(base, VEC_START_CAPACITY);
             let mut len = 0;
-            let stride = v.element.layout().size();
-
-            let mut is_first = true;
-            while peek(&walker.chars) != ']' {
-                if !is_first {
-                    walker.consume_char(',');
-                }
-                is_first = false;
-
-                debug_assert!(len <= cap); // sanity check
-                if len == cap {
-                    let extra = 8;
-                    cap = len + extra;
-                    // because we don't set len beyond 0 until we are done
-                    // reserve needs the capacity we want as parameter
-                    first = (v.vtable.reserve)(base, cap);
-                }
-
-                let ptr = first.add(stride * len);
-                deserialize_field(walker, ptr, v.element);
-                len += 1;
+            let result = deserialize_vec_elements(walker, base, v, first, &mut len);
+            if len > 0 {
+                // also on errors, so the elements read so far get dropped
+                (v.vtable.set_len)(base, len);
             }
-            (v.vtable.set_len)(base, len);
-            walker.consume_char(']');
+            or_cleanup(result, || (v.vtable.drop)(base))?;
         },
         Type::HashMap(hm) => unsafe {
             (hm.vtable.new_at)(base);
-            walker.consume_char('[');
-            while peek(&walker.chars) != ']' {
-                walker.consume_maybe(',');
-                let key = std::alloc::alloc(hm.key.layout());
-                let value = std::alloc::alloc(hm.value.layout());
-                walker.consume_char('{');
-                walker.consume_field("key");
-                deserialize_field(walker, key, hm.key);
-                walker.consume_char(',');
-                walker.consume_field("value");
-                deserialize_field(walker, value, hm.value);
-
-                walker.consume_char('}');
-                (hm.vtable.fill_with)(base, key, value);
-            }
-            walker.consume_char(']');
+            let result = deserialize_map_entries(walker, base, hm);
+            or_cleanup(result, || (hm.vtable.drop)(base))?;
         },
         Type::HashSet(hs) => unsafe {
             (hs.vtable.new_at)(base);
-            walker.consume_char('[');
-            while peek(&walker.chars) != ']' {
-                walker.consume_maybe(',');
-                let element = std::alloc::alloc(hs.element.layout());
-                deserialize_field(walker, element, hs.element);
-                (hs.vtable.fill_with)(base, element);
-            }
-            walker.consume_char(']');
+            let result = deserialize_set_elements(walker, base, hs);
+            or_cleanup(result, || (hs.vtable.drop)(base))?;
         },
         Type::Option(o) => unsafe {
             (o.vtable.new_at)(base);
-            walker.consume_char('[');
-
-            if peek(&walker.chars) != ']' {
-                let element = std::alloc::alloc(o.element.layout());
-                deserialize_field(walker, element, o.element);
-                (o.vtable.set)(base, Some(element));
-            }
-            walker.consume_char(']');
+            let result = deserialize_option(walker, base, o);
+            or_cleanup(result, || (o.vtable.drop)(base))?;
         },
         Type::RustEnum(mirror) => {
-            walker.consume_char('{');
             // figure out which variant we a derializing
-            walker.consume_field("__enum_variant");
-            let name = walker.consume_string();
-            let (index, variant) = mirror
-                .variants
-                .iter()
-                .enumerate()
-                .find(|(_, val)| val.name == name)
-                .unwrap_or_else(|| panic!("Can't find enum variant {name} in {}", mirror.name));
-            walker.consume_maybe(',');
-
-            let mut field_ptrs = Vec::new();
-            for (field_name, ty) in variant.fields {
-                walker.consume_field(field_name);
-                let ptr = unsafe { std::alloc::alloc(ty.layout()) };
-                field_ptrs.push(ptr); // TODO protect against memory leaks on panic/error
-                unsafe { deserialize_field(walker, ptr, ty) };
-                walker.consume_maybe(',');
-            }
-            walker.consume_char('}');
+            let (index, variant, fields) = consume_variant(walker, mirror)?;
+
+            let mut field_ptrs = vec![std::ptr::null_mut(); variant.fields.len()];
+            let result =
+                unsafe { deserialize_variant_fields(walker, variant, fields, &mut field_ptrs) };
+            or_cleanup(result, || {
+                for (ptr, (_, ty)) in field_ptrs.iter().zip(variant.fields) {
+                    if !ptr.is_null() {
+                        unsafe { drop_alloc(*ptr, ty) };
+                    }
+                }
+            })?;
 
             unsafe { (mirror.write)(base, index, &field_ptrs) }
         }
         Type::Box(box_type) => unsafe {
-            let inner_space = std::alloc::alloc(box_type.inner.layout());
-            deserialize_field(walker, inner_space, box_type.inner);
+            let inner_space = deserialize_alloc(walker, box_type.inner)?;
             (box_type.box_up)(base, inner_space)
         },
     }
+    Ok(())
+}
+
+impl DynamicValue {
+    /// Reads json in the format written by `to_json` as a value of type `ty`.
+    ///
+    /// Struct and enum fields may be missing,
+    /// so the result can be used as a patch, see [`apply`](crate::apply::apply).
+    ///
+    /// # Panics
+    /// If the json is malformed, see [`try_from_json`](Self::try_from_json).
+    pub fn from_json(s: &str, ty: &Type) -> Self {
+        Self::try_from_json(s, ty).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Like [`from_json`](Self::from_json), but returns an error on malformed json.
+    pub fn try_from_json(s: &str, ty: &Type) -> Result<Self, JsonError> {
+        Self::try_from_json_with(s, ty, JsonOptions::default())
+    }
+
+    /// Like [`try_from_json`](Self::try_from_json) with the given options.
+    pub fn try_from_json_with(s: &str, ty: &Type, options: JsonOptions) -> Result<Self, JsonError> {
+        let walker = &mut JsonWalker::new(s, options);
+        let result = read_dynamic(walker, ty)?;
+        walker.consume_end()?;
+        Ok(result)
+    }
+}
+
+fn read_dynamic(walker: &mut JsonWalker, ty: &Type) -> Result<DynamicValue, JsonError> {
+    Ok(match ty {
+        Type::I32 => DynamicValue::I32(walker.consume_int()?),
+        Type::U32 => DynamicValue::U32(walker.consume_int()?),
+        Type::F32 => DynamicValue::F32(walker.consume_float()?),
+        Type::I64 => DynamicValue::I64(walker.consume_int()?),
+        Type::U64 => DynamicValue::U64(walker.consume_int()?),
+        Type::F64 => DynamicValue::F64(walker.consume_float()?),
+        Type::ISize => DynamicValue::ISize(walker.consume_int()?),
+        Type::USize => DynamicValue::USize(walker.consume_int()?),
+        Type::Bool => DynamicValue::Bool(walker.consume_bool()?),
+        Type::String => DynamicValue::String(walker.consume_string()?),
+        Type::CEnum(cenum) => {
+            let val = consume_cenum(walker, cenum)?;
+            let (_, name) = cenum.variants.iter().find(|it| it.0 == val).unwrap();
+            DynamicValue::Enum(DynamicEnum {
+                variant: name.to_string(),
+                fields: Vec::new(),
+            })
+        }
+        Type::Struct(mirror) => {
+            walker.consume_char('{')?;
+            let fields = read_dynamic_fields(walker, mirror.name, true, |name| {
+                mirror.field(name).map(|f| &f.ty)
+            })?;
+            DynamicValue::Struct(DynamicStruct { fields })
+        }
+        Type::RustEnum(mirror) => {
+            let (_, variant, kind) = consume_variant(walker, mirror)?;
+            let mut fields = Vec::new();
+            if kind != VariantFields::Unit {
+                fields = read_dynamic_fields(walker, variant.name, kind.first(), |name| {
+                    variant.fields.iter().find(|f| f.0 == name).map(|f| &f.1)
+                })?;
+                kind.consume_end(walker)?;
+            }
+            DynamicValue::Enum(DynamicEnum {
+                variant: variant.name.to_string(),
+                fields,
+            })
+        }
+        // skipped containers are read as empty ones, like they are written
+        Type::Vec(VecType { skip: true, .. }) | Type::HashSet(HSType { skip: true, .. }) => {
+            walker.skip_value()?;
+            DynamicValue::List(Vec::new())
+        }
+        Type::HashMap(HMType { skip: true, .. }) => {
+            walker.skip_value()?;
+            DynamicValue::Map(Vec::new())
+        }
+        Type::Option(OptionType { skip: true, .. }) => {
+            walker.skip_value()?;
+            DynamicValue::Option(None)
+        }
+        Type::Vec(v) => DynamicValue::List(read_dynamic_list(walker, v.element)?),
+        Type::HashSet(hs) => DynamicValue::List(read_dynamic_list(walker, hs.element)?),
+        Type::HashMap(hm) if is_object_map(&walker.options, hm.key) => {
+            let mut entries = Vec::new();
+            walker.consume_char('{')?;
+            while walker.next_key(entries.is_empty())?.is_some() {
+                let key = walker.key().to_string();
+                let path_len = walker.path.len();
+                walker.path.push_str(&format!(".{key}"));
+                let value = read_dynamic(walker, hm.value)?;
+                walker.path.truncate(path_len);
+                entries.push((DynamicValue::String(key), value));
+            }
+            walker.consume_char('}')?;
+            DynamicValue::Map(entries)
+        }
+        Type::HashMap(hm) => {
+            let mut entries = Vec::new();
+            walker.consume_char('[')?;
+            while walker.next_element(entries.is_empty())? {
+                let path_len = walker.path.len();
+                walker.consume_char('{')?;
+                walker.consume_field("key")?;
+                walker.path.push_str(&format!("[{}].key", entries.len()));
+                let key = read_dynamic(walker, hm.key)?;
+                walker.path.truncate(path_len);
+                walker.consume_char(',')?;
+                walker.consume_field("value")?;
+                walker.path.push_str(&format!("[{}].value", entries.len()));
+                let value = read_dynamic(walker, hm.value)?;
+                walker.path.truncate(path_len);
+                walker.consume_char('}')?;
+                entries.push((key, value));
+            }
+            walker.consume_char(']')?;
+            DynamicValue::Map(entries)
+        }
+        Type::Option(o) if walker.options.null_options => match walker.consume_null()? {
+            true => DynamicValue::Option(None),
+            false => DynamicValue::Option(Some(Box::new(read_dynamic(walker, o.element)?))),
+        },
+        Type::Option(o) => {
+            walker.consume_char('[')?;
+            let mut inner = None;
+            if walker.next_element(true)? {
+                inner = Some(Box::new(read_dynamic(walker, o.element)?));
+            }
+            walker.consume_char(']')?;
+            DynamicValue::Option(inner)
+        }
+        Type::Box(b) => read_dynamic(walker, b.inner)?,
+    })
+}
+
+/// Reads fields until the closing `}` in any order, the opening `{` is already consumed.
+/// `first` is false if there already was a field, i.e. the enum tag.
+fn read_dynamic_fields<'a>(
+    walker: &mut JsonWalker,
+    owner: &str,
+    mut first: bool,
+    field_type: impl Fn(&str) -> Option<&'a Type>,
+) -> Result<Vec<(String, DynamicValue)>, JsonError> {
+    let mut fields: Vec<(String, DynamicValue)> = Vec::new();
+    while let Some(start) = walker.next_key(first)? {
+        first = false;
+        let Some(ty) = field_type(walker.key()) else {
+            walker.unknown_field(start, owner)?;
+            continue;
+        };
+        if fields.iter().any(|(name, _)| name == walker.key()) {
+            return Err(duplicate_field(walker, start));
+        }
+        let name = walker.key().to_string();
+        let path_len = walker.path.len();
+        walker.path.push_str(&format!(".{name}"));
+        let value = read_dynamic(walker, ty)?;
+        walker.path.truncate(path_len);
+        fields.push((name, value));
+    }
+    walker.consume_char('}')?;
+    Ok(fields)
+}
+
+fn read_dynamic_list(walker: &mut JsonWalker, ty: &Type) -> Result<Vec<DynamicValue>, JsonError> {
+    let mut elements = Vec::new();
+    walker.consume_char('[')?;
+    while walker.next_element(elements.is_empty())? {
+        let path_len = walker.path.len();
+        walker.path.push_str(&format!("[{}]", elements.len()));
+        elements.push(read_dynamic(walker, ty)?);
+        walker.path.truncate(path_len);
+    }
+    walker.consume_char(']')?;
+    Ok(elements)
 }
@@ -0,0 +1,101 @@
+use std::io;
+use std::str::Chars;
+
+/// Bytes read from an `io::Read` at once.
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Where the json is read from, one char at a time.
+pub enum Input<'a> {
+    Str(Chars<'a>),
+    Read(ReadInput<'a>),
+}
+
+/// Reads chunks from `reader` and decodes them as utf-8.
+pub struct ReadInput<'a> {
+    reader: Box<dyn io::Read + 'a>,
+    buf: Box<[u8]>,
+    pos: usize,
+    len: usize,
+    /// set if reading failed, the input ends there
+    error: Option<String>,
+}
+
+impl<'a> Input<'a> {
+    pub fn reader(reader: impl io::Read + 'a) -> Self {
+        Input::Read(ReadInput {
+            reader: Box::new(reader),
+            buf: vec![0; CHUNK_SIZE].into_boxed_slice(),
+            pos: 0,
+            len: 0,
+            error: None,
+        })
+    }
+
+    pub fn next_char(&mut self) -> Option<char> {
+        match self {
+            Input::Str(chars) => chars.next(),
+            Input::Read(read) => read.next_char(),
+        }
+    }
+
+    /// Why the input ended early, if it did.
+    pub fn error(&self) -> Option<&str> {
+        match self {
+            Input::Str(_) => None,
+            Input::Read(read) => read.error.as_deref(),
+        }
+    }
+}
+
+impl ReadInput<'_> {
+    fn next_byte(&mut self) -> Option<u8> {
+        while self.pos == self.len {
+            match self.reader.read(&mut self.buf) {
+                Ok(0) => return None,
+                Ok(len) => {
+                    self.pos = 0;
+                    self.len = len;
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    self.error = Some(format!("I/O error ({e})"));
+                    return None;
+                }
+            }
+        }
+        self.pos += 1;
+        Some(self.buf[self.pos - 1])
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        if self.error.is_some() {
+            return None;
+        }
+        let first = self.next_byte()?;
+        let width = match first {
+            0..0x80 => return Some(first as char),
+            0xC0..0xE0 => 2,
+            0xE0..0xF0 => 3,
+            0xF0..0xF8 => 4,
+            _ => return self.invalid(),
+        };
+        let mut bytes = [first, 0, 0, 0];
+        for byte in &mut bytes[1..width] {
+            match self.next_byte() {
+                Some(next) => *byte = next,
+                None => return self.invalid(),
+            }
+        }
+        match std::str::from_utf8(&bytes[..width]) {
+            Ok(s) => s.chars().next(),
+            Err(_) => self.invalid(),
+        }
+    }
+
+    fn invalid(&mut self) -> Option<char> {
+        // an I/O error in the middle of a char is reported as such
+        self.error
+            .get_or_insert_with(|| "invalid utf-8".to_string());
+        None
+    }
+}
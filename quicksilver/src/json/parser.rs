@@ -1,130 +1,502 @@
-use std::fmt::Debug;
-use std::str::{Chars, FromStr};
+use std::num::IntErrorKind;
+use std::str::FromStr;
+
+use super::input::Input;
+use super::{JsonError, JsonOptions, NonFinite};
 
 /// Helper struct for Deserializing
 pub struct JsonWalker<'a> {
-    pub chars: Chars<'a>,
+    input: Input<'a>,
+    /// the next char, read ahead so peeking doesn't need to read
+    peeked: Option<char>,
     /// buffer used to simplify parsing of numbers and stuff
-    pub buffer: String,
+    buffer: String,
+    line: usize,
+    column: usize,
+    /// path of the value currently being parsed, like `.tiles[3].kind`
+    pub path: String,
+    pub options: JsonOptions,
 }
 
-#[track_caller]
-pub fn peek(chars: &Chars) -> char {
-    chars
-        .as_str()
-        .chars()
-        .next()
-        .expect("String ended while parsing.")
+/// Line and column in the input, both starting at 1.
+#[derive(Clone, Copy)]
+pub struct Position {
+    line: usize,
+    column: usize,
 }
 
-/// Might be at the end of the string, in which case it returns None
-pub fn peek_maybe(chars: &Chars) -> Option<char> {
-    chars.as_str().chars().next()
-}
+impl<'a> JsonWalker<'a> {
+    pub fn new(s: &'a str, options: JsonOptions) -> Self {
+        Self::with_input(Input::Str(s.chars()), options)
+    }
 
-impl JsonWalker<'_> {
-    #[track_caller]
-    pub fn consume_char(&mut self, arg: char) {
-        let Self { chars, .. } = self;
-        assert_eq!(Some(arg), chars.next());
+    pub fn with_input(mut input: Input<'a>, options: JsonOptions) -> Self {
+        Self {
+            peeked: input.next_char(),
+            input,
+            buffer: String::new(),
+            line: 1,
+            column: 1,
+            path: String::new(),
+            options,
+        }
     }
 
-    #[track_caller]
-    pub fn consume_maybe(&mut self, c: char) {
-        let Self { chars, .. } = self;
-        if peek(chars) == c {
-            let _ = chars.next();
+    /// Might be at the end of the input, in which case it returns None
+    pub fn peek(&self) -> Option<char> {
+        self.peeked
+    }
+
+    /// Skips whitespace, then peeks. The end of the string is an error.
+    pub fn peek_some(&mut self, expected: &str) -> Result<char, JsonError> {
+        self.skip_whitespace();
+        self.peek().ok_or_else(|| self.error(expected))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.next();
         }
     }
 
-    pub fn consume_int<T>(&mut self) -> T
-    where
-        T: FromStr,
-        <T as FromStr>::Err: Debug,
-    {
-        let Self { chars, buffer } = self;
-        buffer.clear();
+    fn next(&mut self) -> Option<char> {
+        let c = self.peeked?;
+        self.peeked = self.input.next_char();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
 
-        let mut c = peek(chars);
-        while match c {
-            '0'..='9' | '+' | '-' => true,
-            _ => false,
-        } {
-            buffer.push(c);
-            let _ = chars.next();
-            let Some(next) = peek_maybe(chars) else { break };
-            c = next
+    pub fn position(&self) -> Position {
+        Position {
+            line: self.line,
+            column: self.column,
         }
-        buffer
-            .parse::<T>()
-            .unwrap_or_else(|_| panic!("Couldn't parse as int: '{buffer}'"))
-    }
-
-    pub fn consume_float<T>(&mut self) -> T
-    where
-        T: FromStr,
-        <T as FromStr>::Err: Debug,
-    {
-        let Self { chars, buffer } = self;
-        buffer.clear();
-        let mut c = peek(chars);
-        while match c {
-            '0'..='9' | '+' | '-' | '.' => true,
-            _ => false,
-        } {
-            buffer.push(c);
-            chars.next();
-            c = peek(chars);
-        }
-        buffer
+    }
+
+    /// Error at the current position, describing the next char as what was found.
+    pub fn error(&self, expected: &str) -> JsonError {
+        let found = match (self.peek(), self.input.error()) {
+            (Some(c), _) => format!("'{c}'"),
+            (None, Some(error)) => error.to_string(),
+            (None, None) => "end of input".to_string(),
+        };
+        self.error_at(self.position(), expected, found)
+    }
+
+    pub fn error_at(&self, pos: Position, expected: &str, found: String) -> JsonError {
+        JsonError {
+            line: pos.line,
+            column: pos.column,
+            path: self.path.clone(),
+            expected: expected.to_string(),
+            found,
+        }
+    }
+
+    pub fn consume_char(&mut self, arg: char) -> Result<(), JsonError> {
+        self.skip_whitespace();
+        if self.peek() != Some(arg) {
+            return Err(self.error(&format!("'{arg}'")));
+        }
+        self.next();
+        Ok(())
+    }
+
+    /// Consumes chars as long as `accept` returns true, into `buffer`.
+    fn consume_while(&mut self, accept: impl Fn(char) -> bool) {
+        self.skip_whitespace();
+        self.buffer.clear();
+        while let Some(c) = self.peek().filter(|&c| accept(c)) {
+            self.buffer.push(c);
+            self.next();
+        }
+    }
+
+    /// Reads a number token into `buffer`, which has to follow the grammar of RFC 8259.
+    fn consume_number(&mut self, start: Position, expected: &str) -> Result<(), JsonError> {
+        self.consume_while(|c| matches!(c, '0'..='9' | '+' | '-' | '.' | 'e' | 'E'));
+        match is_json_number(&self.buffer) {
+            true => Ok(()),
+            false => Err(self.token_error(start, expected)),
+        }
+    }
+
+    /// Parses the token in `buffer`, which started at `start`.
+    fn parse_buffer<T: FromStr>(&self, start: Position, expected: &str) -> Result<T, JsonError> {
+        self.buffer
             .parse::<T>()
-            .unwrap_or_else(|_| panic!("Couldn't parse as float: '{buffer}'"))
+            .map_err(|_| self.token_error(start, expected))
     }
 
-    #[track_caller]
-    pub fn consume_field(&mut self, name: &str) {
-        self.consume_char('"');
+    /// The token in `buffer` isn't what was expected.
+    fn token_error(&self, start: Position, expected: &str) -> JsonError {
+        if self.buffer.is_empty() {
+            self.error(expected)
+        } else {
+            self.error_at(start, expected, format!("'{}'", self.buffer))
+        }
+    }
+
+    /// Reads an integer, numbers outside of the range of `T` are an error.
+    /// Floats are only accepted if they are integral and `options.integral_floats` is set.
+    pub fn consume_int<T: Integer>(&mut self) -> Result<T, JsonError> {
+        self.skip_whitespace();
+        let start = self.position();
+        let expected = std::any::type_name::<T>();
+        self.consume_number(start, expected)?;
+        let val = if self.buffer.contains(['.', 'e', 'E']) {
+            self.integral_float(start, expected)?
+        } else {
+            match self.buffer.parse::<i128>() {
+                Ok(val) => val,
+                // still out of range for every integer type
+                Err(e) if *e.kind() == IntErrorKind::PosOverflow => i128::MAX,
+                Err(e) if *e.kind() == IntErrorKind::NegOverflow => i128::MIN,
+                Err(_) => return Err(self.token_error(start, expected)),
+            }
+        };
+        T::try_from(val).map_err(|_| {
+            let range = format!("{expected} in {}..={}", T::MIN, T::MAX);
+            let bound = if val < 0 { "too small" } else { "too large" };
+            self.error_at(start, &range, format!("'{}' ({bound})", self.buffer))
+        })
+    }
 
-        let Self { chars, buffer } = self;
-        buffer.clear();
-        while peek(chars) != '"' {
-            buffer.push(chars.next().unwrap());
+    /// The float in `buffer` as an integer, if that is allowed.
+    fn integral_float(&self, start: Position, expected: &str) -> Result<i128, JsonError> {
+        let Some(val) = self
+            .buffer
+            .parse::<f64>()
+            .ok()
+            .filter(|val| val.is_finite())
+        else {
+            return Err(self.token_error(start, expected));
+        };
+        if !self.options.integral_floats {
+            return Err(self.error_at(start, expected, format!("'{}' (a float)", self.buffer)));
         }
-        assert_eq!(name, buffer);
+        if val.fract() != 0.0 {
+            let found = format!("'{}' (not a whole number)", self.buffer);
+            return Err(self.error_at(start, expected, found));
+        }
+        // saturates, so values which are too large stay out of range
+        Ok(val as i128)
+    }
 
-        self.consume_char('"');
-        self.consume_char(':');
+    /// Reads a json number, like `-1.5e-7`.
+    /// NaN and infinity are accepted as written with `options.non_finite`.
+    pub fn consume_float<T: Float>(&mut self) -> Result<T, JsonError> {
+        self.skip_whitespace();
+        let start = self.position();
+        let expected = std::any::type_name::<T>();
+        match (self.peek(), self.options.non_finite) {
+            (Some('n'), NonFinite::Null) => {
+                self.consume_null()?;
+                return Ok(T::NAN);
+            }
+            (Some('"'), NonFinite::String) => {
+                self.consume_string_into_buffer()?;
+                // finite floats are written as numbers
+                return match self.buffer.parse::<T>() {
+                    Ok(val) if !val.is_finite() => Ok(val),
+                    _ => {
+                        let found = format!("\"{}\"", self.buffer);
+                        Err(self.error_at(start, r#""NaN", "inf" or "-inf""#, found))
+                    }
+                };
+            }
+            _ => {}
+        }
+        self.consume_number(start, expected)?;
+        let val: T = self.parse_buffer(start, expected)?;
+        if !val.is_finite() {
+            // too large, like `1e999`
+            let found = format!("'{}'", self.buffer);
+            return Err(self.error_at(start, &format!("a finite {expected}"), found));
+        }
+        Ok(val)
     }
 
-    pub fn consume_string(&mut self) -> String {
-        self.consume_char('"');
+    pub fn consume_field(&mut self, name: &str) -> Result<(), JsonError> {
+        self.skip_whitespace();
+        let start = self.position();
+        self.consume_string_into_buffer()?;
+        if self.buffer != name {
+            let found = format!("\"{}\"", self.buffer);
+            return Err(self.error_at(start, &format!("field \"{name}\""), found));
+        }
+        self.consume_char(':')
+    }
+
+    pub fn consume_string(&mut self) -> Result<String, JsonError> {
+        self.consume_string_into_buffer()?;
+        Ok(self.buffer.clone())
+    }
+
+    fn consume_string_into_buffer(&mut self) -> Result<(), JsonError> {
+        self.consume_char('"')?;
 
-        let Self { chars, buffer } = self;
-        buffer.clear();
-        let mut escaped = false;
-        while peek(chars) != '"' || escaped {
-            if peek(chars) == '\\' && !escaped {
-                // ignore escape char backslash
-                let _ = chars.next().unwrap();
-                escaped = true;
-                continue;
+        self.buffer.clear();
+        loop {
+            let start = self.position();
+            match self.next() {
+                Some('"') => break,
+                Some('\\') => {
+                    let c = self.consume_escape(start)?;
+                    self.buffer.push(c);
+                }
+                // RFC 8259 only allows them escaped
+                Some(c) if c < ' ' => {
+                    let found = format!("{c:?}");
+                    return Err(self.error_at(start, "an escaped control character", found));
+                }
+                Some(c) => self.buffer.push(c),
+                None => return Err(self.error("'\"'")),
             }
-            escaped = false;
-            buffer.push(chars.next().unwrap());
         }
+        Ok(())
+    }
 
-        self.consume_char('"'); // peek already consumed this
-        self.buffer.clone()
+    /// Decodes the escape sequence after the backslash at `start`.
+    fn consume_escape(&mut self, start: Position) -> Result<char, JsonError> {
+        let c = match self.peek() {
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('/') => '/',
+            Some('b') => '\u{8}',
+            Some('f') => '\u{c}',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('u') => {
+                self.next();
+                return self.consume_unicode_escape(start);
+            }
+            Some(c) => {
+                return Err(self.error_at(start, "an escape sequence", format!("'\\{c}'")));
+            }
+            None => return Err(self.error("an escape sequence")),
+        };
+        self.next();
+        Ok(c)
+    }
+
+    /// Decodes the hex digits of a `\uXXXX` escape, which started at `start`.
+    /// Chars outside the basic multilingual plane are escaped as a surrogate pair.
+    fn consume_unicode_escape(&mut self, start: Position) -> Result<char, JsonError> {
+        let high = self.consume_hex4()?;
+        let code = match high {
+            0xD800..0xDC00 => {
+                let low_start = self.position();
+                for c in ['\\', 'u'] {
+                    if self.peek() != Some(c) {
+                        return Err(self.error("a low surrogate escape"));
+                    }
+                    self.next();
+                }
+                let low = self.consume_hex4()?;
+                if !(0xDC00..0xE000).contains(&low) {
+                    let found = format!("'\\u{low:04x}'");
+                    return Err(self.error_at(low_start, "a low surrogate escape", found));
+                }
+                0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+            }
+            0xDC00..0xE000 => {
+                let found = format!("'\\u{high:04x}'");
+                return Err(self.error_at(start, "a high surrogate escape", found));
+            }
+            _ => high,
+        };
+        Ok(char::from_u32(code).expect("surrogates are handled above"))
     }
 
-    pub fn consume_bool(&mut self) -> bool {
-        let Self { chars, buffer } = self;
-        buffer.clear();
-        while peek(chars) != '}' && peek(chars) != ',' && peek(chars) != ']' {
-            buffer.push(chars.next().unwrap());
+    fn consume_hex4(&mut self) -> Result<u32, JsonError> {
+        let mut value = 0;
+        for _ in 0..4 {
+            let digit = self.peek().and_then(|c| c.to_digit(16));
+            value = value * 16 + digit.ok_or_else(|| self.error("a hex digit"))?;
+            self.next();
         }
-        buffer
-            .parse::<bool>()
-            .unwrap_or_else(|_| panic!("Couldn't parse as bool: '{buffer}'"))
+        Ok(value)
     }
+
+    /// Moves to the next key of an object and consumes it, it is then available via [`key`](Self::key).
+    /// Returns the position of the key, or `None` at the closing `}`, which is not consumed.
+    pub fn next_key(&mut self, first: bool) -> Result<Option<Position>, JsonError> {
+        if self.peek_some("'}'")? == '}' {
+            return Ok(None);
+        }
+        if !first {
+            self.consume_char(',')?;
+            self.skip_whitespace();
+        }
+        let start = self.position();
+        self.consume_string_into_buffer()?;
+        self.consume_char(':')?;
+        Ok(Some(start))
+    }
+
+    /// The key read by the last call to [`next_key`](Self::next_key).
+    pub fn key(&self) -> &str {
+        &self.buffer
+    }
+
+    /// Moves to the next element of an array.
+    /// Returns false at the closing `]`, which is not consumed.
+    pub fn next_element(&mut self, first: bool) -> Result<bool, JsonError> {
+        if self.peek_some("']'")? == ']' {
+            return Ok(false);
+        }
+        if !first {
+            self.consume_char(',')?;
+        }
+        Ok(true)
+    }
+
+    /// Handles the key read by [`next_key`](Self::next_key), which isn't a field of `owner`.
+    /// Its value is skipped, unless unknown fields are denied.
+    pub fn unknown_field(&mut self, start: Position, owner: &str) -> Result<(), JsonError> {
+        if self.options.deny_unknown_fields {
+            let found = format!("\"{}\"", self.key());
+            return Err(self.error_at(start, &format!("a field of {owner}"), found));
+        }
+        self.skip_value()
+    }
+
+    /// Skips over any json value.
+    pub fn skip_value(&mut self) -> Result<(), JsonError> {
+        match self.peek_some("a value")? {
+            '{' => {
+                self.next();
+                let mut first = true;
+                while self.next_key(first)?.is_some() {
+                    first = false;
+                    self.skip_value()?;
+                }
+                self.consume_char('}')
+            }
+            '[' => {
+                self.next();
+                let mut first = true;
+                while self.next_element(first)? {
+                    first = false;
+                    self.skip_value()?;
+                }
+                self.consume_char(']')
+            }
+            '"' => self.consume_string_into_buffer(),
+            '-' | '0'..='9' => {
+                let start = self.position();
+                self.consume_number(start, "a number")
+            }
+            _ => {
+                self.consume_while(|c| c.is_ascii_alphanumeric());
+                match self.buffer.is_empty() {
+                    true => Err(self.error("a value")),
+                    false => Ok(()),
+                }
+            }
+        }
+    }
+
+    /// Consumes `null` if it is next, returns false if something else is.
+    pub fn consume_null(&mut self) -> Result<bool, JsonError> {
+        if self.peek_some("a value")? != 'n' {
+            return Ok(false);
+        }
+        let start = self.position();
+        self.consume_while(|c| c.is_ascii_alphabetic());
+        match self.buffer == "null" {
+            true => Ok(true),
+            false => Err(self.token_error(start, "null")),
+        }
+    }
+
+    pub fn consume_bool(&mut self) -> Result<bool, JsonError> {
+        self.skip_whitespace();
+        let start = self.position();
+        self.consume_while(|c| c.is_ascii_alphabetic());
+        self.parse_buffer(start, "bool")
+    }
+
+    /// Errors if there is anything left after the parsed value.
+    pub fn consume_end(&mut self) -> Result<(), JsonError> {
+        self.skip_whitespace();
+        match (self.peek(), self.input.error()) {
+            (None, None) => Ok(()),
+            _ => Err(self.error("end of input")),
+        }
+    }
+}
+
+/// Integers which [`JsonWalker::consume_int`] can read.
+pub trait Integer: TryFrom<i128> {
+    const MIN: i128;
+    const MAX: i128;
+}
+
+macro_rules! impl_integer {
+    ($($ty:ty),*) => {
+        $(impl Integer for $ty {
+            const MIN: i128 = <$ty>::MIN as i128;
+            const MAX: i128 = <$ty>::MAX as i128;
+        })*
+    };
+}
+
+impl_integer!(i32, u32, i64, u64, isize, usize);
+
+/// Floats which [`JsonWalker::consume_float`] can read.
+pub trait Float: FromStr {
+    const NAN: Self;
+    fn is_finite(&self) -> bool;
+}
+
+impl Float for f32 {
+    const NAN: Self = f32::NAN;
+    fn is_finite(&self) -> bool {
+        f32::is_finite(*self)
+    }
+}
+
+impl Float for f64 {
+    const NAN: Self = f64::NAN;
+    fn is_finite(&self) -> bool {
+        f64::is_finite(*self)
+    }
+}
+
+/// `-? (0 | [1-9][0-9]*) (. [0-9]+)? ([eE] [+-]? [0-9]+)?` from RFC 8259,
+/// so no leading zeros or `+` and digits on both sides of the `.`.
+fn is_json_number(s: &str) -> bool {
+    let s = s.strip_prefix('-').unwrap_or(s);
+    let (int, rest) = split_digits(s);
+    if int.is_empty() || (int.len() > 1 && int.starts_with('0')) {
+        return false;
+    }
+    let rest = match rest.strip_prefix('.') {
+        Some(fraction) => match split_digits(fraction) {
+            ("", _) => return false,
+            (_, rest) => rest,
+        },
+        None => rest,
+    };
+    match rest.strip_prefix(['e', 'E']) {
+        Some(exponent) => {
+            let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+            matches!(split_digits(exponent), (digits, "") if !digits.is_empty())
+        }
+        None => rest.is_empty(),
+    }
+}
+
+/// Splits off the leading ascii digits.
+fn split_digits(s: &str) -> (&str, &str) {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    s.split_at(end)
 }
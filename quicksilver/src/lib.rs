@@ -1,3 +1,4 @@
+use std::any::TypeId;
 use std::collections::HashMap;
 use std::hash::Hash;
 use std::{alloc::Layout, collections::HashSet};
@@ -5,14 +6,21 @@ use std::{alloc::Layout, collections::HashSet};
 use map::{HMVtable, HMVtableCreator};
 use option::{OptionVtable, OptionVtableCreator};
 pub use quicksilver_derive::Quicksilver;
-use reflections::RustEnumReflection;
+use reflections::{Mut, Ref, RustEnumReflection};
 use set::{HSVtable, HSVtableCreator};
 use vec::{VecVtable, VecVtableCreator};
 
+pub mod apply;
+pub mod compare;
+pub mod cursor;
+pub mod default;
+pub mod dynamic;
 pub mod empty;
 pub mod json;
 pub mod map;
+pub mod numeric;
 pub mod option;
+pub mod pretty;
 pub mod reflections;
 pub mod reflections_ref;
 pub mod set;
@@ -63,6 +71,63 @@ impl Type {
             Type::Option(o) => unsafe { Layout::from_size_align_unchecked(o.size, o.align) },
         }
     }
+
+    /// Drops the value at `ptr` in place.
+    ///
+    /// # Safety
+    /// `ptr` must point to a valid value described by `self`, which is not used afterwards.
+    pub unsafe fn drop_in_place(&self, ptr: *mut u8) {
+        unsafe {
+            match self {
+                Type::I32
+                | Type::U32
+                | Type::F32
+                | Type::I64
+                | Type::U64
+                | Type::F64
+                | Type::ISize
+                | Type::USize
+                | Type::Bool
+                | Type::CEnum(_) => {}
+                Type::String => std::ptr::drop_in_place(ptr as *mut String),
+                Type::Box(b) => (b.drop)(ptr),
+                Type::Vec(v) => (v.vtable.drop)(ptr),
+                Type::HashMap(hm) => (hm.vtable.drop)(ptr),
+                Type::HashSet(hs) => (hs.vtable.drop)(ptr),
+                Type::Option(o) => (o.vtable.drop)(ptr),
+                Type::Struct(s) => (s.drop)(ptr),
+                Type::RustEnum(re) => (re.drop)(ptr),
+            }
+        }
+    }
+
+    /// Checks if both mirrors describe the same Rust type.
+    /// Named types are compared by their `TypeId`, everything else structurally.
+    pub fn same_as(&self, other: &Type) -> bool {
+        match (self, other) {
+            (Type::I32, Type::I32)
+            | (Type::U32, Type::U32)
+            | (Type::F32, Type::F32)
+            | (Type::I64, Type::I64)
+            | (Type::U64, Type::U64)
+            | (Type::F64, Type::F64)
+            | (Type::ISize, Type::ISize)
+            | (Type::USize, Type::USize)
+            | (Type::Bool, Type::Bool)
+            | (Type::String, Type::String) => true,
+            (Type::Box(a), Type::Box(b)) => a.inner.same_as(b.inner),
+            (Type::Vec(a), Type::Vec(b)) => a.element.same_as(b.element),
+            (Type::HashMap(a), Type::HashMap(b)) => {
+                a.key.same_as(b.key) && a.value.same_as(b.value)
+            }
+            (Type::HashSet(a), Type::HashSet(b)) => a.element.same_as(b.element),
+            (Type::Option(a), Type::Option(b)) => a.element.same_as(b.element),
+            (Type::Struct(a), Type::Struct(b)) => a.type_id == b.type_id,
+            (Type::CEnum(a), Type::CEnum(b)) => a.type_id == b.type_id,
+            (Type::RustEnum(a), Type::RustEnum(b)) => a.type_id == b.type_id,
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -73,6 +138,8 @@ pub struct BoxType {
     pub box_up: unsafe fn(dest: *mut u8, inner: *mut u8),
     pub get: unsafe fn(ptr: *mut u8) -> *mut u8,
     pub get_ref: unsafe fn(ptr: *const u8) -> *const u8,
+    /// drops the Box and its contents in place
+    pub drop: unsafe fn(ptr: *mut u8),
 }
 
 #[derive(Debug)]
@@ -126,7 +193,22 @@ pub struct Struct {
     pub size: usize,
     pub align: usize,
     pub name: &'static str,
+    /// `TypeId` of the reflected type, used to tell named types apart
+    pub type_id: TypeId,
     pub fields: &'static [Field],
+    /// position of the field with the given name in `fields`, generated as a `match`
+    pub index_of: fn(name: &str) -> Option<usize>,
+    /// drops the struct in place
+    pub drop: unsafe fn(this: *mut u8),
+    /// writes `Default::default()` to the pointer, set by `#[quicksilver(default)]`
+    pub default: Option<unsafe fn(ptr: *mut u8)>,
+}
+
+impl Struct {
+    /// Looks up a field by name, without going through all fields.
+    pub fn field(&self, name: &str) -> Option<&'static Field> {
+        (self.index_of)(name).map(|i| &self.fields[i])
+    }
 }
 
 #[derive(Debug)]
@@ -134,7 +216,11 @@ pub struct CEnum {
     pub size: usize,
     pub align: usize,
     pub name: &'static str,
+    /// `TypeId` of the reflected type, used to tell named types apart
+    pub type_id: TypeId,
     pub variants: &'static [(i32, &'static str)],
+    /// writes `Default::default()` to the pointer, set by `#[quicksilver(default)]`
+    pub default: Option<unsafe fn(ptr: *mut u8)>,
 }
 
 #[derive(Debug)]
@@ -148,10 +234,20 @@ pub struct RustEnum {
     pub size: usize,
     pub align: usize,
     pub name: &'static str,
+    /// `TypeId` of the reflected type, used to tell named types apart
+    pub type_id: TypeId,
     pub variants: &'static [RustEnumVariant],
-    pub reflect: unsafe fn(*mut u8) -> RustEnumReflection<'static>,
-    pub reflect_ref: unsafe fn(*const u8) -> RustEnumReflection<'static>,
+    pub reflect: unsafe fn(*mut u8) -> RustEnumReflection<'static, Mut>,
+    pub reflect_ref: unsafe fn(*const u8) -> RustEnumReflection<'static, Ref>,
     pub write: unsafe fn(this: *mut u8, variant: usize, fields: &[*mut u8]),
+    /// index of the current variant in `variants`
+    pub variant_index: unsafe fn(this: *const u8) -> usize,
+    /// points to the field with the given index of the current variant
+    pub field_ptr: unsafe fn(this: *const u8, field: usize) -> *const u8,
+    /// drops the enum in place
+    pub drop: unsafe fn(this: *mut u8),
+    /// writes `Default::default()` to the pointer, set by `#[quicksilver(default)]`
+    pub default: Option<unsafe fn(ptr: *mut u8)>,
 }
 
 pub trait Quicksilver {
@@ -200,6 +296,7 @@ where
             let inner: *const T = &**this;
             inner as *const u8
         },
+        drop: |this| unsafe { std::ptr::drop_in_place(this as *mut Self) },
     });
 }
 
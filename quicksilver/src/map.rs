@@ -2,7 +2,7 @@ use std::{collections::HashMap, marker::PhantomData};
 
 use std::hash::Hash;
 
-use crate::reflections::{FieldReflection, StructReflection, reflect_value};
+use crate::reflections::{Access, Mut, ValueMut, ValueRef, reflect_value};
 use crate::reflections_ref::reflect_value_ref;
 use crate::{Quicksilver, Type};
 
@@ -13,58 +13,26 @@ pub struct HMVtable {
     /// adds element to hashmap
     /// element pointers need to be created with Box::into_raw
     pub fill_with: unsafe fn(ptr: *mut u8, key_ptr: *mut u8, value_ptr: *mut u8),
-    /// returns all elements in HashMap in whatever iteration order it sees fit
-    pub get_elements: unsafe fn(ptr: *mut u8) -> Vec<StructReflection<'static>>,
-    /// returns all elements in HashMap in whatever iteration order it sees fit
-    pub get_elements_ref: unsafe fn(ptr: *const u8) -> Vec<StructReflection<'static>>,
-}
-
-/// pointers to Keys and Values inside the HashMap
-pub struct HMEntryView {
-    /// never ever mutate the key itself
-    pub key: *mut u8,
-    pub value: *mut u8,
-    pub key_t: &'static Type,
-    pub value_t: &'static Type,
-}
-
-impl HMEntryView {
-    /// unsafe because caller needs to uphold lifetime invariant
-    unsafe fn reflect(self) -> StructReflection<'static> {
-        StructReflection {
-            name: "HMEntry",
-            fields: unsafe {
-                vec![
-                    FieldReflection {
-                        name: "key",
-                        value: reflect_value_ref(self.key, self.key_t),
-                    },
-                    FieldReflection {
-                        name: "value",
-                        value: reflect_value(self.value, self.value_t),
-                    },
-                ]
-            },
-        }
-    }
-
-    unsafe fn reflect_ref(self) -> StructReflection<'static> {
-        StructReflection {
-            name: "HMEntry",
-            fields: unsafe {
-                vec![
-                    FieldReflection {
-                        name: "key",
-                        value: reflect_value_ref(self.key, self.key_t),
-                    },
-                    FieldReflection {
-                        name: "value",
-                        value: reflect_value_ref(self.value, self.value_t),
-                    },
-                ]
-            },
-        }
-    }
+    /// returns pointers to all keys and values in HashMap in whatever iteration order it sees fit
+    /// never ever mutate the keys
+    pub get_entries: unsafe fn(ptr: *mut u8) -> Vec<(*const u8, *mut u8)>,
+    /// returns pointers to all keys and values in HashMap in whatever iteration order it sees fit
+    pub get_entries_ref: unsafe fn(ptr: *const u8) -> Vec<(*const u8, *const u8)>,
+    /// number of entries
+    pub len: unsafe fn(ptr: *const u8) -> usize,
+    /// get value for key
+    pub get: unsafe fn(ptr: *mut u8, key_ptr: *const u8) -> Option<*mut u8>,
+    /// get value for key immutably
+    pub get_ref: unsafe fn(ptr: *const u8, key_ptr: *const u8) -> Option<*const u8>,
+    /// removes and drops the entry for key, returns if there was one
+    pub remove: unsafe fn(ptr: *mut u8, key_ptr: *const u8) -> bool,
+    /// removes the entry for key and hands ownership of it to the caller
+    /// returned pointers are created with Box::into_raw
+    pub remove_entry: unsafe fn(ptr: *mut u8, key_ptr: *const u8) -> Option<(*mut u8, *mut u8)>,
+    /// removes and drops all entries
+    pub clear: unsafe fn(ptr: *mut u8),
+    /// drops the HashMap in place
+    pub drop: unsafe fn(ptr: *mut u8),
 }
 
 pub struct HMVtableCreator<Key, Value> {
@@ -81,8 +49,15 @@ where
     pub const VTABLE: HMVtable = HMVtable {
         new_at: Self::new_at,
         fill_with: Self::fill_with,
-        get_elements: Self::get_elements,
-        get_elements_ref: Self::get_elements_ref,
+        get_entries: Self::get_entries,
+        get_entries_ref: Self::get_entries_ref,
+        len: Self::len,
+        get: Self::get,
+        get_ref: Self::get_ref,
+        remove: Self::remove,
+        remove_entry: Self::remove_entry,
+        clear: Self::clear,
+        drop: Self::drop,
     };
 
     unsafe fn new_at(ptr: *mut u8) {
@@ -93,6 +68,10 @@ where
         }
     }
 
+    unsafe fn drop(ptr: *mut u8) {
+        unsafe { std::ptr::drop_in_place(ptr as *mut HashMap<Key, Value>) }
+    }
+
     unsafe fn fill_with(ptr: *mut u8, key_ptr: *mut u8, value_ptr: *mut u8) {
         let ptr = ptr as *mut HashMap<Key, Value>;
         let key_ptr = key_ptr as *mut Key;
@@ -105,59 +84,334 @@ where
         }
     }
 
-    unsafe fn get_elements(ptr: *mut u8) -> Vec<StructReflection<'static>> {
+    unsafe fn get_entries(ptr: *mut u8) -> Vec<(*const u8, *mut u8)> {
         let ptr = ptr as *mut HashMap<Key, Value>;
-        let mut result = Vec::new();
         unsafe {
             let val = &mut *ptr;
-            for (key, value) in val.iter_mut() {
-                let el = HMEntryView {
-                    key: key as *const Key as *mut u8,
-                    value: value as *mut Value as *mut u8,
-                    key_t: &Key::MIRROR,
-                    value_t: &Value::MIRROR,
-                };
-                result.push(el.reflect());
-            }
+            val.iter_mut()
+                .map(|(key, value)| {
+                    (
+                        key as *const Key as *const u8,
+                        value as *mut Value as *mut u8,
+                    )
+                })
+                .collect()
         }
-        result
     }
 
-    unsafe fn get_elements_ref(ptr: *const u8) -> Vec<StructReflection<'static>> {
+    unsafe fn get_entries_ref(ptr: *const u8) -> Vec<(*const u8, *const u8)> {
         let ptr = ptr as *const HashMap<Key, Value>;
-        let mut result = Vec::new();
         unsafe {
             let val = &*ptr;
-            for (key, value) in val.iter() {
-                let el = HMEntryView {
-                    key: key as *const Key as *mut u8,
-                    value: value as *const Value as *mut u8,
-                    key_t: &Key::MIRROR,
-                    value_t: &Value::MIRROR,
-                };
-                result.push(el.reflect_ref());
-            }
+            val.iter()
+                .map(|(key, value)| {
+                    (
+                        key as *const Key as *const u8,
+                        value as *const Value as *const u8,
+                    )
+                })
+                .collect()
+        }
+    }
+
+    unsafe fn len(ptr: *const u8) -> usize {
+        let ptr = ptr as *const HashMap<Key, Value>;
+        unsafe { (*ptr).len() }
+    }
+
+    unsafe fn get(ptr: *mut u8, key_ptr: *const u8) -> Option<*mut u8> {
+        let ptr = ptr as *mut HashMap<Key, Value>;
+        unsafe {
+            let hm = &mut *ptr;
+            let key = &*(key_ptr as *const Key);
+            hm.get_mut(key).map(|value| value as *mut Value as *mut u8)
+        }
+    }
+
+    unsafe fn get_ref(ptr: *const u8, key_ptr: *const u8) -> Option<*const u8> {
+        let ptr = ptr as *const HashMap<Key, Value>;
+        unsafe {
+            let hm = &*ptr;
+            let key = &*(key_ptr as *const Key);
+            hm.get(key).map(|value| value as *const Value as *const u8)
+        }
+    }
+
+    unsafe fn remove(ptr: *mut u8, key_ptr: *const u8) -> bool {
+        let ptr = ptr as *mut HashMap<Key, Value>;
+        unsafe {
+            let hm = &mut *ptr;
+            let key = &*(key_ptr as *const Key);
+            hm.remove(key).is_some()
+        }
+    }
+
+    unsafe fn remove_entry(ptr: *mut u8, key_ptr: *const u8) -> Option<(*mut u8, *mut u8)> {
+        let ptr = ptr as *mut HashMap<Key, Value>;
+        unsafe {
+            let hm = &mut *ptr;
+            let key = &*(key_ptr as *const Key);
+            hm.remove_entry(key).map(|(key, value)| {
+                (
+                    Box::into_raw(Box::new(key)) as *mut u8,
+                    Box::into_raw(Box::new(value)) as *mut u8,
+                )
+            })
         }
-        result
+    }
+
+    unsafe fn clear(ptr: *mut u8) {
+        let ptr = ptr as *mut HashMap<Key, Value>;
+        unsafe { (*ptr).clear() }
     }
 }
 
+/// Reflection of a HashMap.
+///
+/// A read only reflection can't be turned into a mutable one:
+/// ```compile_fail
+/// # use std::collections::HashMap;
+/// # use std::marker::PhantomData;
+/// # use quicksilver::map::HMReflection;
+/// # use quicksilver::reflections::{Mut, ValueReflection};
+/// # use quicksilver::reflections_ref::reflect_ref;
+/// let val: HashMap<i32, i32> = HashMap::new();
+/// let ValueReflection::HashMap(r) = reflect_ref(&val) else { panic!() };
+/// let mut hm = HMReflection::<Mut> {
+///     key: r.key,
+///     value: r.value,
+///     ptr: r.ptr,
+///     vtable: r.vtable,
+///     skip: r.skip,
+///     _access: PhantomData,
+/// };
+/// hm.clear();
+/// ```
 #[repr(C)]
-pub struct HMReflection<'a> {
-    pub key: &'a Type,
-    pub value: &'a Type,
-    pub ptr: *mut u8,
-    pub vtable: &'a HMVtable,
+pub struct HMReflection<'a, A: Access = Mut> {
+    pub(crate) key: &'a Type,
+    pub(crate) value: &'a Type,
+    /// points to the HashMap, only written through if `A` is [`Mut`]
+    pub(crate) ptr: *mut u8,
+    pub(crate) vtable: &'a HMVtable,
     pub skip: bool,
+    pub(crate) _access: PhantomData<A>,
 }
 
-impl HMReflection<'_> {
-    pub fn get_elements(&self) -> Vec<StructReflection<'_>> {
-        unsafe { (self.vtable.get_elements)(self.ptr) }
+impl<'a, A: Access> HMReflection<'a, A> {
+    /// The type of the keys.
+    pub fn key_ty(&self) -> &'a Type {
+        self.key
+    }
+
+    /// The type of the values.
+    pub fn value_ty(&self) -> &'a Type {
+        self.value
+    }
+
+    /// Points to the HashMap, only written through if `A` is [`Mut`].
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.ptr
     }
 
-    pub fn get_elements_ref(&self) -> Vec<StructReflection<'_>> {
-        unsafe { (self.vtable.get_elements_ref)(self.ptr) }
+    /// Iterates over all entries in whatever order the HashMap sees fit.
+    ///
+    /// The returned reflections borrow the HashMap, so they can't outlive it:
+    /// ```compile_fail
+    /// # use std::collections::HashMap;
+    /// # use quicksilver::reflections::{ValueReflection, reflect};
+    /// let entries = {
+    ///     let mut map: HashMap<i32, String> = HashMap::new();
+    ///     let ValueReflection::HashMap(hm) = reflect(&mut map) else { panic!() };
+    ///     hm.iter().collect::<Vec<_>>()
+    /// };
+    /// ```
+    pub fn iter(&self) -> HMIter<'_> {
+        HMIter {
+            entries: unsafe { (self.vtable.get_entries_ref)(self.ptr) }.into_iter(),
+            key: self.key,
+            value: self.value,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        unsafe { (self.vtable.len)(self.ptr) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// # Safety
+    /// `key` must point to a valid value of the key type.
+    pub unsafe fn contains(&self, key: *const u8) -> bool {
+        unsafe { (self.vtable.get_ref)(self.ptr, key).is_some() }
+    }
+
+    /// # Safety
+    /// `key` must point to a valid value of the key type.
+    pub unsafe fn get(&self, key: *const u8) -> Option<ValueRef<'_>> {
+        unsafe {
+            let ptr = (self.vtable.get_ref)(self.ptr, key);
+            ptr.map(|it| reflect_value_ref(it, self.value))
+        }
+    }
+}
+
+impl HMReflection<'_, Mut> {
+    /// Iterates over all entries in whatever order the HashMap sees fit.
+    /// The values can be mutated, the keys can't.
+    ///
+    /// Mutable access requires a mutable borrow:
+    /// ```compile_fail
+    /// # use std::collections::HashMap;
+    /// # use quicksilver::map::HMReflection;
+    /// fn mutate_shared(hm: &HMReflection) {
+    ///     for (_key, _value) in hm.iter_mut() {}
+    /// }
+    /// ```
+    ///
+    /// Read only reflections can't be mutated:
+    /// ```compile_fail
+    /// # use std::collections::HashMap;
+    /// # use quicksilver::reflections::ValueReflection;
+    /// # use quicksilver::reflections_ref::reflect_ref;
+    /// let map: HashMap<i32, String> = HashMap::new();
+    /// let ValueReflection::HashMap(mut hm) = reflect_ref(&map) else { panic!() };
+    /// for (_key, _value) in hm.iter_mut() {}
+    /// ```
+    ///
+    /// And the values can't be aliased:
+    /// ```compile_fail
+    /// # use std::collections::HashMap;
+    /// # use quicksilver::reflections::{ValueReflection, reflect};
+    /// let mut map: HashMap<i32, String> = HashMap::new();
+    /// let ValueReflection::HashMap(mut hm) = reflect(&mut map) else { panic!() };
+    /// let first = hm.iter_mut().next();
+    /// let second = hm.iter_mut().next();
+    /// drop((first, second));
+    /// ```
+    pub fn iter_mut(&mut self) -> HMIterMut<'_> {
+        HMIterMut {
+            entries: unsafe { (self.vtable.get_entries)(self.ptr) }.into_iter(),
+            key: self.key,
+            value: self.value,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// # Safety
+    /// `key` must point to a valid value of the key type.
+    pub unsafe fn get_mut(&mut self, key: *const u8) -> Option<ValueMut<'_>> {
+        unsafe {
+            let ptr = (self.vtable.get)(self.ptr, key);
+            ptr.map(|it| reflect_value(it, self.value))
+        }
+    }
+
+    /// Inserts an entry, an existing value for the same key is dropped.
+    ///
+    /// # Safety
+    /// `key` and `value` must be created with `Box::into_raw` and be of the key and value type.
+    /// The HashMap takes ownership of them.
+    pub unsafe fn insert(&mut self, key: *mut u8, value: *mut u8) {
+        unsafe { (self.vtable.fill_with)(self.ptr, key, value) }
+    }
+
+    /// Removes and drops the entry for `key`, returns if there was one.
+    ///
+    /// # Safety
+    /// `key` must point to a valid value of the key type, which is not stored in the HashMap.
+    pub unsafe fn remove(&mut self, key: *const u8) -> bool {
+        unsafe { (self.vtable.remove)(self.ptr, key) }
+    }
+
+    pub fn clear(&mut self) {
+        unsafe { (self.vtable.clear)(self.ptr) }
+    }
+
+    /// Edits the key of an entry.
+    ///
+    /// Keys can't be mutated inside of a HashMap, so the entry is removed,
+    /// the key is handed to `edit` and then the entry is inserted again.
+    /// If the edited key collides with another entry that entry's value is dropped.
+    /// If `edit` panics, the entry is still inserted again with the key as `edit` left it.
+    /// Returns `false` if there is no entry for `key`.
+    ///
+    /// # Safety
+    /// `key` must point to a valid value of the key type, which is not stored in the HashMap.
+    pub unsafe fn edit_key(&mut self, key: *const u8, edit: impl FnOnce(ValueMut)) -> bool {
+        unsafe {
+            let Some((key, value)) = (self.vtable.remove_entry)(self.ptr, key) else {
+                return false;
+            };
+            let entry = Reinsert {
+                ptr: self.ptr,
+                vtable: self.vtable,
+                key,
+                value,
+            };
+            edit(reflect_value(entry.key, self.key));
+        }
+        true
+    }
+}
+
+/// Inserts an entry removed by [`HMReflection::edit_key`] again when dropped,
+/// so it isn't lost if the edit panics.
+struct Reinsert<'a> {
+    ptr: *mut u8,
+    vtable: &'a HMVtable,
+    key: *mut u8,
+    value: *mut u8,
+}
+
+impl Drop for Reinsert<'_> {
+    fn drop(&mut self) {
+        unsafe { (self.vtable.fill_with)(self.ptr, self.key, self.value) }
+    }
+}
+
+/// Iterator over the entries of a HashMap, see [`HMReflection::iter`]
+pub struct HMIter<'a> {
+    entries: std::vec::IntoIter<(*const u8, *const u8)>,
+    key: &'a Type,
+    value: &'a Type,
+}
+
+impl<'a> Iterator for HMIter<'a> {
+    type Item = (ValueRef<'a>, ValueRef<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, value) = self.entries.next()?;
+        unsafe {
+            Some((
+                reflect_value_ref(key, self.key),
+                reflect_value_ref(value, self.value),
+            ))
+        }
+    }
+}
+
+/// Iterator over the entries of a HashMap with mutable values, see [`HMReflection::iter_mut`]
+pub struct HMIterMut<'a> {
+    entries: std::vec::IntoIter<(*const u8, *mut u8)>,
+    key: &'a Type,
+    value: &'a Type,
+    _phantom: PhantomData<&'a mut ()>,
+}
+
+impl<'a> Iterator for HMIterMut<'a> {
+    type Item = (ValueRef<'a>, ValueMut<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, value) = self.entries.next()?;
+        unsafe {
+            Some((
+                reflect_value_ref(key, self.key),
+                reflect_value(value, self.value),
+            ))
+        }
     }
 }
 
@@ -173,8 +427,15 @@ where
     pub const VTABLE: HMVtable = HMVtable {
         new_at: Self::new_at,
         fill_with: empty_fill_with,
-        get_elements: empty_get_elements,
-        get_elements_ref: empty_get_elements_ref,
+        get_entries: empty_get_entries,
+        get_entries_ref: empty_get_entries_ref,
+        len: empty_len,
+        get: empty_get,
+        get_ref: empty_get_ref,
+        remove: empty_remove,
+        remove_entry: empty_remove_entry,
+        clear: empty_clear,
+        drop: Self::drop,
     };
 
     unsafe fn new_at(ptr: *mut u8) {
@@ -184,16 +445,44 @@ where
             ptr.write(v);
         }
     }
+
+    unsafe fn drop(ptr: *mut u8) {
+        unsafe { std::ptr::drop_in_place(ptr as *mut HashMap<Key, Value>) }
+    }
 }
 
 unsafe fn empty_fill_with(_ptr: *mut u8, _key_ptr: *mut u8, _value_ptr: *mut u8) {
     panic!("Not supported on skipped fields");
 }
 
-unsafe fn empty_get_elements(_ptr: *mut u8) -> Vec<StructReflection<'static>> {
+unsafe fn empty_get_entries(_ptr: *mut u8) -> Vec<(*const u8, *mut u8)> {
+    panic!("Not supported on skipped fields");
+}
+
+unsafe fn empty_get_entries_ref(_ptr: *const u8) -> Vec<(*const u8, *const u8)> {
+    panic!("Not supported on skipped fields");
+}
+
+unsafe fn empty_len(_ptr: *const u8) -> usize {
+    panic!("Not supported on skipped fields");
+}
+
+unsafe fn empty_get(_ptr: *mut u8, _key_ptr: *const u8) -> Option<*mut u8> {
+    panic!("Not supported on skipped fields");
+}
+
+unsafe fn empty_get_ref(_ptr: *const u8, _key_ptr: *const u8) -> Option<*const u8> {
+    panic!("Not supported on skipped fields");
+}
+
+unsafe fn empty_remove(_ptr: *mut u8, _key_ptr: *const u8) -> bool {
+    panic!("Not supported on skipped fields");
+}
+
+unsafe fn empty_remove_entry(_ptr: *mut u8, _key_ptr: *const u8) -> Option<(*mut u8, *mut u8)> {
     panic!("Not supported on skipped fields");
 }
 
-unsafe fn empty_get_elements_ref(_ptr: *const u8) -> Vec<StructReflection<'static>> {
+unsafe fn empty_clear(_ptr: *mut u8) {
     panic!("Not supported on skipped fields");
 }
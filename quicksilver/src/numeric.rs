@@ -0,0 +1,260 @@
+use std::fmt;
+
+use crate::Type;
+use crate::reflections::{Access, ValueMut, ValueReflection};
+
+/// The numeric primitives supported by quicksilver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NumericKind {
+    I32,
+    U32,
+    F32,
+    I64,
+    U64,
+    F64,
+    ISize,
+    USize,
+}
+
+/// A number of any [`NumericKind`], without loss of precision.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Number {
+    Int(i128),
+    Float(f64),
+}
+
+/// Why a number couldn't be written to a reflected value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NumericError {
+    /// The reflected value isn't a number.
+    NotNumeric,
+    /// A float with a fractional part, infinity or NaN was written to an integer.
+    NotAnInteger(f64),
+    /// The number is too small or too large for the reflected value.
+    OutOfRange { value: Number, kind: NumericKind },
+}
+
+impl NumericKind {
+    /// The kind of `ty`, if it is a (boxed) number.
+    pub fn of(ty: &Type) -> Option<NumericKind> {
+        match ty {
+            Type::I32 => Some(NumericKind::I32),
+            Type::U32 => Some(NumericKind::U32),
+            Type::F32 => Some(NumericKind::F32),
+            Type::I64 => Some(NumericKind::I64),
+            Type::U64 => Some(NumericKind::U64),
+            Type::F64 => Some(NumericKind::F64),
+            Type::ISize => Some(NumericKind::ISize),
+            Type::USize => Some(NumericKind::USize),
+            Type::Box(b) => NumericKind::of(b.inner),
+            _ => None,
+        }
+    }
+
+    pub const fn bits(self) -> u32 {
+        match self {
+            NumericKind::I32 | NumericKind::U32 | NumericKind::F32 => 32,
+            NumericKind::I64 | NumericKind::U64 | NumericKind::F64 => 64,
+            NumericKind::ISize | NumericKind::USize => usize::BITS,
+        }
+    }
+
+    /// Floats count as signed.
+    pub const fn is_signed(self) -> bool {
+        !matches!(
+            self,
+            NumericKind::U32 | NumericKind::U64 | NumericKind::USize
+        )
+    }
+
+    pub const fn is_float(self) -> bool {
+        matches!(self, NumericKind::F32 | NumericKind::F64)
+    }
+
+    /// Smallest value, for floats the smallest finite value.
+    pub const fn min(self) -> Number {
+        match self {
+            NumericKind::I32 => Number::Int(i32::MIN as i128),
+            NumericKind::U32 => Number::Int(0),
+            NumericKind::F32 => Number::Float(f32::MIN as f64),
+            NumericKind::I64 => Number::Int(i64::MIN as i128),
+            NumericKind::U64 => Number::Int(0),
+            NumericKind::F64 => Number::Float(f64::MIN),
+            NumericKind::ISize => Number::Int(isize::MIN as i128),
+            NumericKind::USize => Number::Int(0),
+        }
+    }
+
+    /// Largest value, for floats the largest finite value.
+    pub const fn max(self) -> Number {
+        match self {
+            NumericKind::I32 => Number::Int(i32::MAX as i128),
+            NumericKind::U32 => Number::Int(u32::MAX as i128),
+            NumericKind::F32 => Number::Float(f32::MAX as f64),
+            NumericKind::I64 => Number::Int(i64::MAX as i128),
+            NumericKind::U64 => Number::Int(u64::MAX as i128),
+            NumericKind::F64 => Number::Float(f64::MAX),
+            NumericKind::ISize => Number::Int(isize::MAX as i128),
+            NumericKind::USize => Number::Int(usize::MAX as i128),
+        }
+    }
+}
+
+impl fmt::Display for NumericKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            NumericKind::I32 => "i32",
+            NumericKind::U32 => "u32",
+            NumericKind::F32 => "f32",
+            NumericKind::I64 => "i64",
+            NumericKind::U64 => "u64",
+            NumericKind::F64 => "f64",
+            NumericKind::ISize => "isize",
+            NumericKind::USize => "usize",
+        };
+        f.write_str(name)
+    }
+}
+
+impl Number {
+    /// Large integers lose precision.
+    pub fn as_f64(self) -> f64 {
+        match self {
+            Number::Int(val) => val as f64,
+            Number::Float(val) => val,
+        }
+    }
+
+    /// Floats are only converted if they are whole numbers.
+    pub fn as_i128(self) -> Option<i128> {
+        match self {
+            Number::Int(val) => Some(val),
+            Number::Float(val) => float_to_i128(val),
+        }
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Number::Int(val) => write!(f, "{val}"),
+            Number::Float(val) => write!(f, "{val:?}"),
+        }
+    }
+}
+
+impl fmt::Display for NumericError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NumericError::NotNumeric => write!(f, "value is not a number"),
+            NumericError::NotAnInteger(val) => write!(f, "{val:?} is not an integer"),
+            NumericError::OutOfRange { value, kind } => {
+                write!(f, "{value} is out of range for {kind}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for NumericError {}
+
+impl<A: Access> ValueReflection<'_, A> {
+    /// The kind of the reflected value, if it is a (boxed) number.
+    pub fn numeric_kind(&self) -> Option<NumericKind> {
+        match self {
+            ValueReflection::I32(_) => Some(NumericKind::I32),
+            ValueReflection::U32(_) => Some(NumericKind::U32),
+            ValueReflection::F32(_) => Some(NumericKind::F32),
+            ValueReflection::I64(_) => Some(NumericKind::I64),
+            ValueReflection::U64(_) => Some(NumericKind::U64),
+            ValueReflection::F64(_) => Some(NumericKind::F64),
+            ValueReflection::ISize(_) => Some(NumericKind::ISize),
+            ValueReflection::USize(_) => Some(NumericKind::USize),
+            ValueReflection::Box(b) => b.inner.numeric_kind(),
+            _ => None,
+        }
+    }
+
+    /// The reflected value, if it is a (boxed) number.
+    pub fn as_number(&self) -> Option<Number> {
+        match self {
+            ValueReflection::I32(val) => Some(Number::Int(**val as i128)),
+            ValueReflection::U32(val) => Some(Number::Int(**val as i128)),
+            ValueReflection::F32(val) => Some(Number::Float(**val as f64)),
+            ValueReflection::I64(val) => Some(Number::Int(**val as i128)),
+            ValueReflection::U64(val) => Some(Number::Int(**val as i128)),
+            ValueReflection::F64(val) => Some(Number::Float(**val)),
+            ValueReflection::ISize(val) => Some(Number::Int(**val as i128)),
+            ValueReflection::USize(val) => Some(Number::Int(**val as i128)),
+            ValueReflection::Box(b) => b.inner.as_number(),
+            _ => None,
+        }
+    }
+
+    /// See [`Number::as_f64`].
+    pub fn as_f64(&self) -> Option<f64> {
+        self.as_number().map(Number::as_f64)
+    }
+
+    /// See [`Number::as_i128`].
+    pub fn as_i128(&self) -> Option<i128> {
+        self.as_number().and_then(Number::as_i128)
+    }
+}
+
+impl ValueMut<'_> {
+    /// Writes `val` to a (boxed) number, nothing is written if it doesn't fit.
+    /// Floats lose precision if needed, integers have to be in range.
+    pub fn set_from_i128(&mut self, val: i128) -> Result<(), NumericError> {
+        self.set_number(Number::Int(val))
+    }
+
+    /// Writes `val` to a (boxed) number, nothing is written if it doesn't fit.
+    /// Floats lose precision if needed, but can't overflow to infinity.
+    /// Integers only accept whole numbers in range.
+    pub fn set_from_f64(&mut self, val: f64) -> Result<(), NumericError> {
+        self.set_number(Number::Float(val))
+    }
+
+    /// Writes `val` to a (boxed) number, see [`set_from_i128`](Self::set_from_i128)
+    /// and [`set_from_f64`](Self::set_from_f64).
+    pub fn set_number(&mut self, val: Number) -> Result<(), NumericError> {
+        let kind = self.numeric_kind().ok_or(NumericError::NotNumeric)?;
+        let out_of_range = NumericError::OutOfRange { value: val, kind };
+        let int = match (val, kind.is_float()) {
+            (Number::Int(int), false) => int,
+            (Number::Float(float), false) => {
+                if float.fract() != 0. || !float.is_finite() {
+                    return Err(NumericError::NotAnInteger(float));
+                }
+                float_to_i128(float).ok_or_else(|| out_of_range.clone())?
+            }
+            (_, true) => 0,
+        };
+        match self {
+            ValueReflection::I32(target) => **target = int.try_into().or(Err(out_of_range))?,
+            ValueReflection::U32(target) => **target = int.try_into().or(Err(out_of_range))?,
+            ValueReflection::I64(target) => **target = int.try_into().or(Err(out_of_range))?,
+            ValueReflection::U64(target) => **target = int.try_into().or(Err(out_of_range))?,
+            ValueReflection::ISize(target) => **target = int.try_into().or(Err(out_of_range))?,
+            ValueReflection::USize(target) => **target = int.try_into().or(Err(out_of_range))?,
+            ValueReflection::F32(target) => {
+                let float = val.as_f64() as f32;
+                if float.is_infinite() && val.as_f64().is_finite() {
+                    return Err(out_of_range);
+                }
+                **target = float;
+            }
+            ValueReflection::F64(target) => **target = val.as_f64(),
+            ValueReflection::Box(b) => return b.inner.set_number(val),
+            _ => unreachable!("checked by numeric_kind"),
+        }
+        Ok(())
+    }
+}
+
+/// Exact conversion of whole numbers that fit into an i128.
+fn float_to_i128(val: f64) -> Option<i128> {
+    // 2^127
+    const LIMIT: f64 = 170141183460469231731687303715884105728.0;
+    (val.fract() == 0. && (-LIMIT..LIMIT).contains(&val)).then_some(val as i128)
+}
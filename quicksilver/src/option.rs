@@ -2,7 +2,8 @@ use std::marker::PhantomData;
 
 use crate::{
     Quicksilver, Type,
-    reflections::{ValueReflection, reflect_value},
+    default::box_default,
+    reflections::{Access, Mut, ValueMut, ValueRef, reflect_value},
     reflections_ref::reflect_value_ref,
 };
 
@@ -17,6 +18,8 @@ pub struct OptionVtable {
     pub get_elem: unsafe fn(ptr: *mut u8) -> Option<*mut u8>,
     /// get element immutably
     pub get_elem_ref: unsafe fn(ptr: *const u8) -> Option<*const u8>,
+    /// drops the Option in place
+    pub drop: unsafe fn(ptr: *mut u8),
 }
 
 pub struct OptionVtableCreator<T> {
@@ -32,6 +35,7 @@ where
         set: Self::set,
         get_elem: Self::get_elem,
         get_elem_ref: Self::get_elem_ref,
+        drop: Self::drop,
     };
 
     unsafe fn new_at(ptr: *mut u8) {
@@ -42,6 +46,10 @@ where
         }
     }
 
+    unsafe fn drop(ptr: *mut u8) {
+        unsafe { std::ptr::drop_in_place(ptr as *mut Option<T>) }
+    }
+
     unsafe fn set(ptr: *mut u8, val_ptr: Option<*mut u8>) {
         let ptr = ptr as *mut Option<T>;
         if let Some(val_ptr) = val_ptr {
@@ -63,11 +71,7 @@ where
         let ptr = ptr as *mut Option<T>;
         unsafe {
             let o: &mut Option<T> = &mut *ptr;
-            if let Some(inner) = o {
-                Some(inner as *mut T as *mut u8)
-            } else {
-                None
-            }
+            o.as_mut().map(|inner| inner as *mut T as *mut u8)
         }
     }
 
@@ -75,38 +79,86 @@ where
         let ptr = ptr as *const Option<T>;
         unsafe {
             let o: &Option<T> = &*ptr;
-            if let Some(inner) = o {
-                Some(inner as *const T as *const u8)
-            } else {
-                None
-            }
+            o.as_ref().map(|inner| inner as *const T as *const u8)
         }
     }
 }
 
+/// Reflection of an Option.
+///
+/// A read only reflection can't be turned into a mutable one:
+/// ```compile_fail
+/// # use std::marker::PhantomData;
+/// # use quicksilver::option::OptionReflection;
+/// # use quicksilver::reflections::{Mut, ValueReflection};
+/// # use quicksilver::reflections_ref::reflect_ref;
+/// let val: Option<i32> = None;
+/// let ValueReflection::Option(r) = reflect_ref(&val) else { panic!() };
+/// let mut o = OptionReflection::<Mut> {
+///     element: r.element,
+///     ptr: r.ptr,
+///     vtable: r.vtable,
+///     skip: r.skip,
+///     _access: PhantomData,
+/// };
+/// o.set_some_default();
+/// ```
 #[repr(C)]
-pub struct OptionReflection<'a> {
-    pub element: &'a Type,
-    /// points to the option
-    pub ptr: *mut u8,
-    pub vtable: &'a OptionVtable,
+pub struct OptionReflection<'a, A: Access = Mut> {
+    pub(crate) element: &'a Type,
+    /// points to the option, only written through if `A` is [`Mut`]
+    pub(crate) ptr: *mut u8,
+    pub(crate) vtable: &'a OptionVtable,
     pub skip: bool,
+    pub(crate) _access: PhantomData<A>,
 }
 
-impl OptionReflection<'_> {
-    pub fn get(&mut self) -> Option<ValueReflection<'_>> {
+impl<'a, A: Access> OptionReflection<'a, A> {
+    /// The type of the value, if there is one.
+    pub fn element_ty(&self) -> &'a Type {
+        self.element
+    }
+
+    /// Points to the Option, only written through if `A` is [`Mut`].
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.ptr
+    }
+
+    pub fn get_ref(&self) -> Option<ValueRef<'_>> {
         unsafe {
-            let ptr = (self.vtable.get_elem)(self.ptr);
-            ptr.map(|it| reflect_value(it, &self.element))
+            let ptr = (self.vtable.get_elem_ref)(self.ptr);
+            ptr.map(|it| reflect_value_ref(it, self.element))
         }
     }
+}
 
-    pub fn get_ref(&self) -> Option<ValueReflection<'_>> {
+impl OptionReflection<'_, Mut> {
+    pub fn get(&mut self) -> Option<ValueMut<'_>> {
         unsafe {
-            let ptr = (self.vtable.get_elem_ref)(self.ptr);
-            ptr.map(|it| reflect_value_ref(it, &self.element))
+            let ptr = (self.vtable.get_elem)(self.ptr);
+            ptr.map(|it| reflect_value(it, self.element))
         }
     }
+
+    /// Sets the Option to `None`, dropping the old value.
+    pub fn set_none(&mut self) {
+        unsafe { (self.vtable.set)(self.ptr, None) }
+    }
+
+    /// Sets the Option to `Some` default constructed value, dropping the old value.
+    /// See [`construct_default`](crate::default::construct_default).
+    pub fn set_some_default(&mut self) {
+        unsafe { self.set_some(box_default(self.element)) }
+    }
+
+    /// Sets the Option to `Some(value)`, dropping the old value.
+    ///
+    /// # Safety
+    /// `value` must be created with `Box::into_raw` and be of the element type.
+    /// The Option takes ownership of it.
+    pub unsafe fn set_some(&mut self, value: *mut u8) {
+        unsafe { (self.vtable.set)(self.ptr, Some(value)) }
+    }
 }
 
 pub struct EmptyOptionVtableCreator<T> {
@@ -119,6 +171,7 @@ impl<T> EmptyOptionVtableCreator<T> {
         set: empty_set,
         get_elem: empty_get_elem,
         get_elem_ref: empty_get_elem_ref,
+        drop: Self::drop,
     };
 
     unsafe fn new_at(ptr: *mut u8) {
@@ -128,6 +181,10 @@ impl<T> EmptyOptionVtableCreator<T> {
             ptr.write(o);
         }
     }
+
+    unsafe fn drop(ptr: *mut u8) {
+        unsafe { std::ptr::drop_in_place(ptr as *mut Option<T>) }
+    }
 }
 
 unsafe fn empty_set(_ptr: *mut u8, _val_ptr: Option<*mut u8>) {
@@ -0,0 +1,368 @@
+use std::fmt::{self, Write};
+
+use crate::Quicksilver;
+use crate::reflections::{FieldReflection, Ref, ValueRef, ValueReflection};
+use crate::reflections_ref::reflect_ref;
+
+/// Output format of [`Pretty`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PrettyStyle {
+    /// Looks like `{:#?}`
+    #[default]
+    Rust,
+    /// Indented `key: value` lines
+    Yaml,
+}
+
+/// Limits and filters for [`Pretty`].
+#[derive(Debug, Clone, Default)]
+pub struct PrettyOptions {
+    pub style: PrettyStyle,
+    /// Structs, enums and collections nested deeper than this are shown as `..`.
+    pub max_depth: Option<usize>,
+    /// Collections show at most this many elements.
+    pub max_elements: Option<usize>,
+    /// Strings are cut after this many chars.
+    pub max_string_len: Option<usize>,
+    /// Only struct and enum fields for which this returns `true` are shown.
+    pub field_filter: Option<fn(&str) -> bool>,
+    /// Sort map entries by their printed key.
+    pub sort_map_keys: bool,
+}
+
+/// Formats a reflected value via `Display`, e.g. `println!("{}", pretty(&state))`.
+pub struct Pretty<'a> {
+    pub value: ValueRef<'a>,
+    pub options: PrettyOptions,
+}
+
+/// Pretty prints `val` with default options.
+pub fn pretty<T: Quicksilver>(val: &T) -> Pretty<'_> {
+    Pretty {
+        value: reflect_ref(val),
+        options: PrettyOptions::default(),
+    }
+}
+
+/// Pretty prints `val` with the given options.
+pub fn pretty_with<T: Quicksilver>(val: &T, options: PrettyOptions) -> Pretty<'_> {
+    Pretty {
+        value: reflect_ref(val),
+        options,
+    }
+}
+
+impl fmt::Display for Pretty<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let node = build(&self.value, &self.options, 0);
+        match self.options.style {
+            PrettyStyle::Rust => write_rust(&node, 0, false, f),
+            PrettyStyle::Yaml => write_yaml(&node, 0, true, f),
+        }
+    }
+}
+
+/// What is going to be printed, after limits and filters are applied.
+enum Node {
+    Leaf(String),
+    None,
+    Some(Box<Node>),
+    Composite(Composite),
+}
+
+struct Composite {
+    /// struct or variant name
+    name: Option<String>,
+    /// enum variants are printed as a mapping with a single key in yaml
+    variant: bool,
+    kind: Kind,
+    /// label is empty for lists and tuples
+    entries: Vec<(String, Node)>,
+    /// number of elements left out due to `max_elements`
+    more: usize,
+    /// left out due to `max_depth`
+    elided: bool,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum Kind {
+    Fields,
+    Tuple,
+    List,
+    Map,
+}
+
+fn build(value: &ValueRef, options: &PrettyOptions, depth: usize) -> Node {
+    match value {
+        ValueReflection::I32(val) => Node::Leaf(val.to_string()),
+        ValueReflection::U32(val) => Node::Leaf(val.to_string()),
+        ValueReflection::F32(val) => Node::Leaf(format!("{:?}", val)),
+        ValueReflection::I64(val) => Node::Leaf(val.to_string()),
+        ValueReflection::U64(val) => Node::Leaf(val.to_string()),
+        ValueReflection::F64(val) => Node::Leaf(format!("{:?}", val)),
+        ValueReflection::ISize(val) => Node::Leaf(val.to_string()),
+        ValueReflection::USize(val) => Node::Leaf(val.to_string()),
+        ValueReflection::Bool(val) => Node::Leaf(val.to_string()),
+        ValueReflection::String(val) => match options.max_string_len {
+            Some(max) if val.chars().count() > max => {
+                let cut: String = val.chars().take(max).collect();
+                Node::Leaf(format!("{cut:?}..."))
+            }
+            _ => Node::Leaf(format!("{val:?}")),
+        },
+        ValueReflection::CEnum(cenum) => {
+            match cenum.variants.iter().find(|it| it.0 == *cenum.val) {
+                Some((_, name)) => Node::Leaf(name.to_string()),
+                None => Node::Leaf(cenum.val.to_string()),
+            }
+        }
+        ValueReflection::Struct(s) => fields_node(s.name, false, s.fields(), options, depth),
+        ValueReflection::RustEnum(renum) => {
+            if renum.fields().is_empty() {
+                Node::Leaf(renum.variant_name.to_string())
+            } else {
+                fields_node(renum.variant_name, true, renum.fields(), options, depth)
+            }
+        }
+        ValueReflection::Vec(v) => {
+            if v.skip {
+                return Node::Leaf("<skipped>".to_string());
+            }
+            let elements = (0..v.len()).map(|i| (String::new(), v.get_ref(i)));
+            list_node(Kind::List, v.len(), elements, options, depth)
+        }
+        ValueReflection::HashSet(hs) => {
+            if hs.skip {
+                return Node::Leaf("<skipped>".to_string());
+            }
+            let elements = hs.iter().map(|it| (String::new(), it));
+            list_node(Kind::List, hs.len(), elements, options, depth)
+        }
+        ValueReflection::HashMap(hm) => {
+            if hm.skip {
+                return Node::Leaf("<skipped>".to_string());
+            }
+            let mut entries: Vec<(String, ValueRef)> = hm
+                .iter()
+                .map(|(key, value)| {
+                    let mut label = String::new();
+                    let key = build(&key, options, depth + 1);
+                    write_rust(&key, 0, true, &mut label).unwrap();
+                    (label, value)
+                })
+                .collect();
+            if options.sort_map_keys {
+                entries.sort_by(|a, b| a.0.cmp(&b.0));
+            }
+            list_node(
+                Kind::Map,
+                entries.len(),
+                entries.into_iter(),
+                options,
+                depth,
+            )
+        }
+        ValueReflection::Option(o) => {
+            if o.skip {
+                return Node::Leaf("<skipped>".to_string());
+            }
+            match o.get_ref() {
+                Some(inner) => Node::Some(Box::new(build(&inner, options, depth))),
+                None => Node::None,
+            }
+        }
+        ValueReflection::Box(b) => build(&b.inner, options, depth),
+    }
+}
+
+fn fields_node(
+    name: &str,
+    variant: bool,
+    fields: &[FieldReflection<'_, Ref>],
+    options: &PrettyOptions,
+    depth: usize,
+) -> Node {
+    let is_tuple = !fields.is_empty()
+        && fields
+            .iter()
+            .all(|field| field.name.chars().all(|c| c.is_ascii_digit()));
+    let mut node = Composite {
+        name: Some(name.to_string()),
+        variant,
+        kind: if is_tuple { Kind::Tuple } else { Kind::Fields },
+        entries: Vec::new(),
+        more: 0,
+        elided: false,
+    };
+    if options.max_depth.is_some_and(|max| depth >= max) {
+        node.elided = true;
+        return Node::Composite(node);
+    }
+    for field in fields {
+        if options
+            .field_filter
+            .is_some_and(|filter| !filter(field.name))
+        {
+            continue;
+        }
+        let label = if is_tuple {
+            String::new()
+        } else {
+            field.name.to_string()
+        };
+        node.entries
+            .push((label, build(&field.value, options, depth + 1)));
+    }
+    Node::Composite(node)
+}
+
+fn list_node<'a>(
+    kind: Kind,
+    len: usize,
+    elements: impl Iterator<Item = (String, ValueRef<'a>)>,
+    options: &PrettyOptions,
+    depth: usize,
+) -> Node {
+    let mut node = Composite {
+        name: None,
+        variant: false,
+        kind,
+        entries: Vec::new(),
+        more: 0,
+        elided: false,
+    };
+    if len > 0 && options.max_depth.is_some_and(|max| depth >= max) {
+        node.elided = true;
+        return Node::Composite(node);
+    }
+    let shown = options.max_elements.unwrap_or(len).min(len);
+    node.more = len - shown;
+    node.entries = elements
+        .take(shown)
+        .map(|(label, value)| (label, build(&value, options, depth + 1)))
+        .collect();
+    Node::Composite(node)
+}
+
+fn indent(out: &mut impl Write, amount: usize) -> fmt::Result {
+    write!(out, "{:amount$}", "")
+}
+
+fn write_rust(node: &Node, level: usize, compact: bool, out: &mut impl Write) -> fmt::Result {
+    let c = match node {
+        Node::Leaf(s) => return out.write_str(s),
+        Node::None => return out.write_str("None"),
+        Node::Some(inner) => {
+            out.write_str("Some(")?;
+            write_rust(inner, level, compact, out)?;
+            return out.write_str(")");
+        }
+        Node::Composite(c) => c,
+    };
+    let (open, close, pad) = match c.kind {
+        Kind::Fields => ("{", "}", true),
+        Kind::Tuple => ("(", ")", false),
+        Kind::List => ("[", "]", false),
+        Kind::Map => ("{", "}", false),
+    };
+    if let Some(name) = &c.name {
+        out.write_str(name)?;
+        if c.kind == Kind::Fields {
+            if c.entries.is_empty() && !c.elided {
+                return Ok(());
+            }
+            out.write_char(' ')?;
+        }
+    }
+    let pad = if pad { " " } else { "" };
+    if c.elided {
+        return write!(out, "{open}{pad}..{pad}{close}");
+    }
+    if c.entries.is_empty() && c.more == 0 {
+        return write!(out, "{open}{close}");
+    }
+    if compact {
+        write!(out, "{open}{pad}")?;
+        for (i, (label, value)) in c.entries.iter().enumerate() {
+            if i > 0 {
+                out.write_str(", ")?;
+            }
+            if !label.is_empty() {
+                write!(out, "{label}: ")?;
+            }
+            write_rust(value, level, true, out)?;
+        }
+        if c.more > 0 {
+            if !c.entries.is_empty() {
+                out.write_str(", ")?;
+            }
+            write!(out, ".. {} more", c.more)?;
+        }
+        return write!(out, "{pad}{close}");
+    }
+    writeln!(out, "{open}")?;
+    for (label, value) in &c.entries {
+        indent(out, level + 4)?;
+        if !label.is_empty() {
+            write!(out, "{label}: ")?;
+        }
+        write_rust(value, level + 4, false, out)?;
+        writeln!(out, ",")?;
+    }
+    if c.more > 0 {
+        indent(out, level + 4)?;
+        writeln!(out, ".. {} more", c.more)?;
+    }
+    indent(out, level)?;
+    out.write_str(close)
+}
+
+/// Writes the value after a `key:` or `-`, or at the start of the output if `top` is set.
+fn write_yaml(node: &Node, level: usize, top: bool, out: &mut impl Write) -> fmt::Result {
+    let space = if top { "" } else { " " };
+    let c = match node {
+        Node::Leaf(s) => return write!(out, "{space}{s}"),
+        Node::None => return write!(out, "{space}~"),
+        Node::Some(inner) => return write_yaml(inner, level, top, out),
+        Node::Composite(c) => c,
+    };
+    if c.variant {
+        if !top {
+            writeln!(out)?;
+            indent(out, level)?;
+        }
+        write!(out, "{}:", c.name.as_deref().unwrap_or_default())?;
+        return write_yaml_entries(c, level + 2, false, out);
+    }
+    write_yaml_entries(c, level, top, out)
+}
+
+fn write_yaml_entries(c: &Composite, level: usize, top: bool, out: &mut impl Write) -> fmt::Result {
+    let space = if top { "" } else { " " };
+    if c.elided {
+        return write!(out, "{space}...");
+    }
+    if c.entries.is_empty() && c.more == 0 {
+        let empty = if c.kind == Kind::List { "[]" } else { "{}" };
+        return write!(out, "{space}{empty}");
+    }
+    for (i, (label, value)) in c.entries.iter().enumerate() {
+        if !top || i > 0 {
+            writeln!(out)?;
+        }
+        indent(out, level)?;
+        match c.kind {
+            Kind::List | Kind::Tuple => out.write_char('-')?,
+            Kind::Fields | Kind::Map => write!(out, "{label}:")?,
+        }
+        write_yaml(value, level + 2, false, out)?;
+    }
+    if c.more > 0 {
+        if !top || !c.entries.is_empty() {
+            writeln!(out)?;
+        }
+        indent(out, level)?;
+        write!(out, "# ... {} more", c.more)?;
+    }
+    Ok(())
+}
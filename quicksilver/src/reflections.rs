@@ -1,72 +1,836 @@
+use crate::default::box_default;
 use crate::option::OptionReflection;
 use crate::set::HSReflection;
-use crate::{Quicksilver, Struct, Type, map::HMReflection, vec::VecReflection};
-use std::fmt::Debug;
-
+use crate::{CEnum, Quicksilver, Struct, Type, map::HMReflection, vec::VecReflection};
+use std::marker::PhantomData;
 use std::ops::Deref;
-use std::ops::DerefMut;
+
+/// How a reflection accesses the reflected value, either [`Ref`] or [`Mut`].
+pub trait Access {
+    type Ref<'a, T: 'a>: Deref<Target = T>;
+}
+
+/// Read only access, see [`ValueRef`]
+pub enum Ref {}
+
+/// Mutable access, see [`ValueMut`]
+pub enum Mut {}
+
+impl Access for Ref {
+    type Ref<'a, T: 'a> = &'a T;
+}
+
+impl Access for Mut {
+    type Ref<'a, T: 'a> = &'a mut T;
+}
+
+/// Reflection of a value, which can only be read. Created by [`reflect_ref`](crate::reflections_ref::reflect_ref).
+pub type ValueRef<'a> = ValueReflection<'a, Ref>;
+
+/// Reflection of a value, which can be mutated. Created by [`reflect`].
+pub type ValueMut<'a> = ValueReflection<'a, Mut>;
 
 #[repr(C)]
-pub enum ValueReflection<'a> {
-    I32(RefOrMut<'a, i32>),
-    U32(RefOrMut<'a, u32>),
-    F32(RefOrMut<'a, f32>),
-    I64(RefOrMut<'a, i64>),
-    U64(RefOrMut<'a, u64>),
-    F64(RefOrMut<'a, f64>),
-    ISize(RefOrMut<'a, isize>),
-    USize(RefOrMut<'a, usize>),
-    Bool(RefOrMut<'a, bool>),
-    String(RefOrMut<'a, String>),
-    Struct(Box<StructReflection<'a>>),
-    CEnum(Box<CEnumReflection<'a>>),
-    Vec(Box<VecReflection<'a>>),
-    HashMap(Box<HMReflection<'a>>),
-    HashSet(Box<HSReflection<'a>>),
-    Option(Box<OptionReflection<'a>>),
-    RustEnum(RustEnumReflection<'a>),
-    Box(Box<BoxReflection<'a>>),
+pub enum ValueReflection<'a, A: Access = Mut> {
+    I32(A::Ref<'a, i32>),
+    U32(A::Ref<'a, u32>),
+    F32(A::Ref<'a, f32>),
+    I64(A::Ref<'a, i64>),
+    U64(A::Ref<'a, u64>),
+    F64(A::Ref<'a, f64>),
+    ISize(A::Ref<'a, isize>),
+    USize(A::Ref<'a, usize>),
+    Bool(A::Ref<'a, bool>),
+    String(A::Ref<'a, String>),
+    Struct(Box<StructReflection<'a, A>>),
+    CEnum(Box<CEnumReflection<'a, A>>),
+    Vec(Box<VecReflection<'a, A>>),
+    HashMap(Box<HMReflection<'a, A>>),
+    HashSet(Box<HSReflection<'a, A>>),
+    Option(Box<OptionReflection<'a, A>>),
+    RustEnum(RustEnumReflection<'a, A>),
+    Box(Box<BoxReflection<'a, A>>),
 }
 
 #[repr(C)]
-pub struct FieldReflection<'a> {
-    pub name: &'a str,
-    pub value: ValueReflection<'a>,
+pub struct FieldReflection<'a, A: Access = Mut> {
+    pub(crate) name: &'a str,
+    pub(crate) value: ValueReflection<'a, A>,
 }
 
+/// Reflection of a struct and its fields.
+///
+/// The fields can't be moved out, because they would alias the reference
+/// returned by [`downcast_mut`](StructReflection::downcast_mut):
+/// ```compile_fail
+/// # use quicksilver::Quicksilver;
+/// # use quicksilver::reflections::{reflect, ValueReflection};
+/// #[derive(Quicksilver)]
+/// struct Pos {
+///     x: i32,
+/// }
+/// let mut pos = Pos { x: 1 };
+/// let ValueReflection::Struct(mut s) = reflect(&mut pos) else { panic!() };
+/// let fields = std::mem::take(&mut s.fields);
+/// let pos = s.downcast_mut::<Pos>().ok().unwrap();
+/// ```
+/// Neither can a reborrowed field be swapped out of [`fields_mut`](StructReflection::fields_mut):
+/// ```compile_fail
+/// # use quicksilver::Quicksilver;
+/// # use quicksilver::reflections::{reflect, ValueReflection};
+/// # #[derive(Quicksilver)]
+/// # struct Pos {
+/// #     x: i32,
+/// # }
+/// let mut pos = Pos { x: 1 };
+/// let ValueReflection::Struct(mut s) = reflect(&mut pos) else { panic!() };
+/// let mut dummy = 0;
+/// let (_, mut x) = s.fields_mut().next().unwrap();
+/// let old = std::mem::replace(&mut x, ValueReflection::I32(&mut dummy));
+/// let pos = s.downcast_mut::<Pos>().ok().unwrap();
+/// drop(old);
+/// ```
 #[repr(C)]
-pub struct StructReflection<'a> {
+pub struct StructReflection<'a, A: Access = Mut> {
     pub name: &'a str,
-    pub fields: Vec<FieldReflection<'a>>,
+    pub(crate) fields: Vec<FieldReflection<'a, A>>,
+    /// points to the struct, only written through if `A` is [`Mut`]
+    pub(crate) ptr: *mut u8,
+    pub(crate) mirror: &'a Struct,
 }
 
+/// Reflection of a Box and the boxed value.
+///
+/// The boxed value is only handed out reborrowed, it would alias the reference
+/// returned by [`downcast_mut`](ValueReflection::downcast_mut):
+/// ```compile_fail
+/// # use quicksilver::reflections::{reflect, ValueReflection};
+/// let mut val = Box::new(1);
+/// let ValueReflection::Box(mut b) = reflect(&mut val) else { panic!() };
+/// let mut dummy = 0;
+/// let mut inner = b.inner_mut();
+/// let old = std::mem::replace(&mut inner, ValueReflection::I32(&mut dummy));
+/// let val = ValueReflection::Box(b).downcast_mut::<Box<i32>>().ok().unwrap();
+/// drop(old);
+/// ```
 #[repr(C)]
-pub struct BoxReflection<'a> {
-    pub inner: ValueReflection<'a>,
+pub struct BoxReflection<'a, A: Access = Mut> {
+    pub(crate) inner: ValueReflection<'a, A>,
+    /// points to the Box, only written through if `A` is [`Mut`]
+    pub(crate) ptr: *mut u8,
 }
 
 #[repr(C)]
-pub struct CEnumReflection<'a> {
+pub struct CEnumReflection<'a, A: Access = Mut> {
     pub name: &'a str,
-    pub val: RefOrMut<'a, i32>,
+    pub val: A::Ref<'a, i32>,
     pub variants: &'a [(i32, &'a str)],
+    pub mirror: &'a CEnum,
 }
 
+/// Reflection of the current variant of an enum with fields.
+///
+/// The fields can't be moved out, because
+/// [`switch_variant`](RustEnumReflection::switch_variant) drops the values they point to:
+/// ```compile_fail
+/// # use quicksilver::Quicksilver;
+/// # use quicksilver::reflections::{reflect, ValueReflection};
+/// #[derive(Quicksilver)]
+/// enum Shape {
+///     Circle { radius: String },
+///     Square { side: u32 },
+/// }
+/// let mut shape = Shape::Circle { radius: "big".to_string() };
+/// let ValueReflection::RustEnum(mut e) = reflect(&mut shape) else { panic!() };
+/// let old = std::mem::take(&mut e.fields);
+/// e.switch_variant(1);
+/// ```
+/// Neither can a reborrowed field be swapped out of [`fields_mut`](RustEnumReflection::fields_mut):
+/// ```compile_fail
+/// # use quicksilver::Quicksilver;
+/// # use quicksilver::reflections::{reflect, ValueReflection};
+/// # #[derive(Quicksilver)]
+/// # enum Shape {
+/// #     Circle { radius: String },
+/// #     Square { side: u32 },
+/// # }
+/// let mut shape = Shape::Circle { radius: "big".to_string() };
+/// let ValueReflection::RustEnum(mut e) = reflect(&mut shape) else { panic!() };
+/// let mut dummy = String::new();
+/// let (_, mut radius) = e.fields_mut().next().unwrap();
+/// let old = std::mem::replace(&mut radius, ValueReflection::String(&mut dummy));
+/// e.switch_variant(1);
+/// drop(old);
+/// ```
+/// ```compile_fail
+/// # use quicksilver::Quicksilver;
+/// # use quicksilver::reflections::{reflect, ValueReflection};
+/// # #[derive(Quicksilver)]
+/// # enum Shape {
+/// #     Circle { radius: String },
+/// #     Square { side: u32 },
+/// # }
+/// let mut shape = Shape::Circle { radius: "big".to_string() };
+/// let ValueReflection::RustEnum(mut e) = reflect(&mut shape) else { panic!() };
+/// let mut dummy = String::new();
+/// let mut old = reflect(&mut dummy);
+/// if let Some((_, mut radius)) = e.fields_mut().next() {
+///     std::mem::swap(&mut radius, &mut old);
+/// }
+/// e.switch_variant(1);
+/// drop(old);
+/// ```
 #[repr(C)]
-pub struct RustEnumReflection<'a> {
+pub struct RustEnumReflection<'a, A: Access = Mut> {
     pub name: &'a str,
     pub variant_name: &'a str,
     pub variant_idx: usize,
-    pub ty: &'a Type,
-    pub fields: Vec<FieldReflection<'a>>,
+    ty: &'a Type,
+    /// points to the enum
+    ptr: *mut u8,
+    pub(crate) fields: Vec<FieldReflection<'a, A>>,
+}
+
+impl<A: Access> ValueReflection<'_, A> {
+    /// Reborrows the reflection as read only.
+    pub fn to_ref(&self) -> ValueRef<'_> {
+        match self {
+            ValueReflection::I32(val) => ValueReflection::I32(&**val),
+            ValueReflection::U32(val) => ValueReflection::U32(&**val),
+            ValueReflection::F32(val) => ValueReflection::F32(&**val),
+            ValueReflection::I64(val) => ValueReflection::I64(&**val),
+            ValueReflection::U64(val) => ValueReflection::U64(&**val),
+            ValueReflection::F64(val) => ValueReflection::F64(&**val),
+            ValueReflection::ISize(val) => ValueReflection::ISize(&**val),
+            ValueReflection::USize(val) => ValueReflection::USize(&**val),
+            ValueReflection::Bool(val) => ValueReflection::Bool(&**val),
+            ValueReflection::String(val) => ValueReflection::String(&**val),
+            ValueReflection::Struct(s) => ValueReflection::Struct(Box::new(s.to_ref())),
+            ValueReflection::CEnum(cenum) => ValueReflection::CEnum(Box::new(CEnumReflection {
+                name: cenum.name,
+                val: &*cenum.val,
+                variants: cenum.variants,
+                mirror: cenum.mirror,
+            })),
+            ValueReflection::Vec(v) => ValueReflection::Vec(Box::new(VecReflection {
+                element: v.element,
+                ptr: v.ptr,
+                vtable: v.vtable,
+                skip: v.skip,
+                _access: PhantomData,
+            })),
+            ValueReflection::HashMap(hm) => ValueReflection::HashMap(Box::new(HMReflection {
+                key: hm.key,
+                value: hm.value,
+                ptr: hm.ptr,
+                vtable: hm.vtable,
+                skip: hm.skip,
+                _access: PhantomData,
+            })),
+            ValueReflection::HashSet(hs) => ValueReflection::HashSet(Box::new(HSReflection {
+                element: hs.element,
+                ptr: hs.ptr,
+                vtable: hs.vtable,
+                skip: hs.skip,
+                _access: PhantomData,
+            })),
+            ValueReflection::Option(o) => ValueReflection::Option(Box::new(OptionReflection {
+                element: o.element,
+                ptr: o.ptr,
+                vtable: o.vtable,
+                skip: o.skip,
+                _access: PhantomData,
+            })),
+            ValueReflection::RustEnum(renum) => ValueReflection::RustEnum(RustEnumReflection {
+                name: renum.name,
+                variant_name: renum.variant_name,
+                variant_idx: renum.variant_idx,
+                ty: renum.ty,
+                ptr: renum.ptr,
+                fields: renum.fields().iter().map(FieldReflection::to_ref).collect(),
+            }),
+            ValueReflection::Box(b) => ValueReflection::Box(Box::new(BoxReflection {
+                inner: b.inner.to_ref(),
+                ptr: b.ptr,
+            })),
+        }
+    }
+}
+
+impl<A: Access> ValueReflection<'_, A> {
+    /// Checks if the reflected value is a `T`.
+    /// Values in skipped containers are never a `T`.
+    pub fn is<T: Quicksilver>(&self) -> bool {
+        self.is_type(&T::MIRROR)
+    }
+
+    fn is_type(&self, ty: &Type) -> bool {
+        match (self, ty) {
+            (ValueReflection::I32(_), Type::I32)
+            | (ValueReflection::U32(_), Type::U32)
+            | (ValueReflection::F32(_), Type::F32)
+            | (ValueReflection::I64(_), Type::I64)
+            | (ValueReflection::U64(_), Type::U64)
+            | (ValueReflection::F64(_), Type::F64)
+            | (ValueReflection::ISize(_), Type::ISize)
+            | (ValueReflection::USize(_), Type::USize)
+            | (ValueReflection::Bool(_), Type::Bool)
+            | (ValueReflection::String(_), Type::String) => true,
+            (ValueReflection::Struct(s), Type::Struct(mirror)) => {
+                s.mirror.type_id == mirror.type_id
+            }
+            (ValueReflection::CEnum(cenum), Type::CEnum(mirror)) => {
+                cenum.mirror.type_id == mirror.type_id
+            }
+            (ValueReflection::RustEnum(renum), ty) => renum.ty.same_as(ty),
+            (ValueReflection::Vec(v), Type::Vec(vt)) => !v.skip && v.element.same_as(vt.element),
+            (ValueReflection::HashMap(hm), Type::HashMap(hmt)) => {
+                !hm.skip && hm.key.same_as(hmt.key) && hm.value.same_as(hmt.value)
+            }
+            (ValueReflection::HashSet(hs), Type::HashSet(hst)) => {
+                !hs.skip && hs.element.same_as(hst.element)
+            }
+            (ValueReflection::Option(o), Type::Option(ot)) => {
+                !o.skip && o.element.same_as(ot.element)
+            }
+            (ValueReflection::Box(b), Type::Box(bt)) => b.inner.is_type(bt.inner),
+            _ => false,
+        }
+    }
+}
+
+impl<'a> ValueRef<'a> {
+    /// Returns the reflected value, if it is a `T`.
+    pub fn downcast_ref<T: Quicksilver>(&self) -> Option<&'a T> {
+        if !self.is::<T>() {
+            return None;
+        }
+        let ptr: *const u8 = match self {
+            ValueReflection::I32(val) => *val as *const i32 as *const u8,
+            ValueReflection::U32(val) => *val as *const u32 as *const u8,
+            ValueReflection::F32(val) => *val as *const f32 as *const u8,
+            ValueReflection::I64(val) => *val as *const i64 as *const u8,
+            ValueReflection::U64(val) => *val as *const u64 as *const u8,
+            ValueReflection::F64(val) => *val as *const f64 as *const u8,
+            ValueReflection::ISize(val) => *val as *const isize as *const u8,
+            ValueReflection::USize(val) => *val as *const usize as *const u8,
+            ValueReflection::Bool(val) => *val as *const bool as *const u8,
+            ValueReflection::String(val) => *val as *const String as *const u8,
+            ValueReflection::Struct(s) => s.ptr,
+            ValueReflection::CEnum(cenum) => cenum.val as *const i32 as *const u8,
+            ValueReflection::Vec(v) => v.ptr,
+            ValueReflection::HashMap(hm) => hm.ptr,
+            ValueReflection::HashSet(hs) => hs.ptr,
+            ValueReflection::Option(o) => o.ptr,
+            ValueReflection::RustEnum(renum) => renum.ptr,
+            ValueReflection::Box(b) => b.ptr,
+        };
+        Some(unsafe { &*(ptr as *const T) })
+    }
+}
+
+impl<'a> ValueMut<'a> {
+    /// Turns the reflection into the reflected value, if it is a `T`.
+    /// Otherwise the reflection is handed back.
+    ///
+    /// The reflection is consumed, because the reflections of its fields
+    /// would alias the returned reference.
+    pub fn downcast_mut<T: Quicksilver>(self) -> Result<&'a mut T, Self> {
+        if !self.is::<T>() {
+            return Err(self);
+        }
+        let ptr: *mut u8 = match self {
+            ValueReflection::I32(val) => val as *mut i32 as *mut u8,
+            ValueReflection::U32(val) => val as *mut u32 as *mut u8,
+            ValueReflection::F32(val) => val as *mut f32 as *mut u8,
+            ValueReflection::I64(val) => val as *mut i64 as *mut u8,
+            ValueReflection::U64(val) => val as *mut u64 as *mut u8,
+            ValueReflection::F64(val) => val as *mut f64 as *mut u8,
+            ValueReflection::ISize(val) => val as *mut isize as *mut u8,
+            ValueReflection::USize(val) => val as *mut usize as *mut u8,
+            ValueReflection::Bool(val) => val as *mut bool as *mut u8,
+            ValueReflection::String(val) => val as *mut String as *mut u8,
+            ValueReflection::Struct(s) => s.ptr,
+            ValueReflection::CEnum(cenum) => cenum.val as *mut i32 as *mut u8,
+            ValueReflection::Vec(v) => v.ptr,
+            ValueReflection::HashMap(hm) => hm.ptr,
+            ValueReflection::HashSet(hs) => hs.ptr,
+            ValueReflection::Option(o) => o.ptr,
+            ValueReflection::RustEnum(renum) => renum.ptr,
+            ValueReflection::Box(b) => b.ptr,
+        };
+        Ok(unsafe { &mut *(ptr as *mut T) })
+    }
+}
+
+impl<'a> ValueMut<'a> {
+    /// Gives up mutable access.
+    pub fn into_ref(self) -> ValueRef<'a> {
+        match self {
+            ValueReflection::I32(val) => ValueReflection::I32(&*val),
+            ValueReflection::U32(val) => ValueReflection::U32(&*val),
+            ValueReflection::F32(val) => ValueReflection::F32(&*val),
+            ValueReflection::I64(val) => ValueReflection::I64(&*val),
+            ValueReflection::U64(val) => ValueReflection::U64(&*val),
+            ValueReflection::F64(val) => ValueReflection::F64(&*val),
+            ValueReflection::ISize(val) => ValueReflection::ISize(&*val),
+            ValueReflection::USize(val) => ValueReflection::USize(&*val),
+            ValueReflection::Bool(val) => ValueReflection::Bool(&*val),
+            ValueReflection::String(val) => ValueReflection::String(&*val),
+            ValueReflection::Struct(s) => ValueReflection::Struct(Box::new(StructReflection {
+                name: s.name,
+                fields: s
+                    .fields
+                    .into_iter()
+                    .map(FieldReflection::into_ref)
+                    .collect(),
+                ptr: s.ptr,
+                mirror: s.mirror,
+            })),
+            ValueReflection::CEnum(cenum) => ValueReflection::CEnum(Box::new(CEnumReflection {
+                name: cenum.name,
+                val: &*cenum.val,
+                variants: cenum.variants,
+                mirror: cenum.mirror,
+            })),
+            ValueReflection::Vec(v) => ValueReflection::Vec(Box::new(VecReflection {
+                element: v.element,
+                ptr: v.ptr,
+                vtable: v.vtable,
+                skip: v.skip,
+                _access: PhantomData,
+            })),
+            ValueReflection::HashMap(hm) => ValueReflection::HashMap(Box::new(HMReflection {
+                key: hm.key,
+                value: hm.value,
+                ptr: hm.ptr,
+                vtable: hm.vtable,
+                skip: hm.skip,
+                _access: PhantomData,
+            })),
+            ValueReflection::HashSet(hs) => ValueReflection::HashSet(Box::new(HSReflection {
+                element: hs.element,
+                ptr: hs.ptr,
+                vtable: hs.vtable,
+                skip: hs.skip,
+                _access: PhantomData,
+            })),
+            ValueReflection::Option(o) => ValueReflection::Option(Box::new(OptionReflection {
+                element: o.element,
+                ptr: o.ptr,
+                vtable: o.vtable,
+                skip: o.skip,
+                _access: PhantomData,
+            })),
+            ValueReflection::RustEnum(renum) => ValueReflection::RustEnum(RustEnumReflection {
+                name: renum.name,
+                variant_name: renum.variant_name,
+                variant_idx: renum.variant_idx,
+                ty: renum.ty,
+                ptr: renum.ptr,
+                fields: renum
+                    .fields
+                    .into_iter()
+                    .map(FieldReflection::into_ref)
+                    .collect(),
+            }),
+            ValueReflection::Box(b) => ValueReflection::Box(Box::new(BoxReflection {
+                inner: b.inner.into_ref(),
+                ptr: b.ptr,
+            })),
+        }
+    }
+}
+
+impl ValueMut<'_> {
+    /// Reborrows the reflection, e.g. to hand out a field without giving up the parent.
+    pub fn reborrow(&mut self) -> ValueMut<'_> {
+        match self {
+            ValueReflection::I32(val) => ValueReflection::I32(&mut **val),
+            ValueReflection::U32(val) => ValueReflection::U32(&mut **val),
+            ValueReflection::F32(val) => ValueReflection::F32(&mut **val),
+            ValueReflection::I64(val) => ValueReflection::I64(&mut **val),
+            ValueReflection::U64(val) => ValueReflection::U64(&mut **val),
+            ValueReflection::F64(val) => ValueReflection::F64(&mut **val),
+            ValueReflection::ISize(val) => ValueReflection::ISize(&mut **val),
+            ValueReflection::USize(val) => ValueReflection::USize(&mut **val),
+            ValueReflection::Bool(val) => ValueReflection::Bool(&mut **val),
+            ValueReflection::String(val) => ValueReflection::String(&mut **val),
+            ValueReflection::Struct(s) => ValueReflection::Struct(Box::new(StructReflection {
+                name: s.name,
+                fields: reborrow_fields(&mut s.fields),
+                ptr: s.ptr,
+                mirror: s.mirror,
+            })),
+            ValueReflection::CEnum(cenum) => ValueReflection::CEnum(Box::new(CEnumReflection {
+                name: cenum.name,
+                val: &mut *cenum.val,
+                variants: cenum.variants,
+                mirror: cenum.mirror,
+            })),
+            ValueReflection::Vec(v) => ValueReflection::Vec(Box::new(VecReflection {
+                element: v.element,
+                ptr: v.ptr,
+                vtable: v.vtable,
+                skip: v.skip,
+                _access: PhantomData,
+            })),
+            ValueReflection::HashMap(hm) => ValueReflection::HashMap(Box::new(HMReflection {
+                key: hm.key,
+                value: hm.value,
+                ptr: hm.ptr,
+                vtable: hm.vtable,
+                skip: hm.skip,
+                _access: PhantomData,
+            })),
+            ValueReflection::HashSet(hs) => ValueReflection::HashSet(Box::new(HSReflection {
+                element: hs.element,
+                ptr: hs.ptr,
+                vtable: hs.vtable,
+                skip: hs.skip,
+                _access: PhantomData,
+            })),
+            ValueReflection::Option(o) => ValueReflection::Option(Box::new(OptionReflection {
+                element: o.element,
+                ptr: o.ptr,
+                vtable: o.vtable,
+                skip: o.skip,
+                _access: PhantomData,
+            })),
+            ValueReflection::RustEnum(renum) => ValueReflection::RustEnum(RustEnumReflection {
+                name: renum.name,
+                variant_name: renum.variant_name,
+                variant_idx: renum.variant_idx,
+                ty: renum.ty,
+                ptr: renum.ptr,
+                fields: reborrow_fields(&mut renum.fields),
+            }),
+            ValueReflection::Box(b) => ValueReflection::Box(Box::new(BoxReflection {
+                inner: b.inner.reborrow(),
+                ptr: b.ptr,
+            })),
+        }
+    }
+}
+
+/// Goes through the field reflections, the base pointer may not be read
+/// while they are alive.
+fn reborrow_fields<'s>(
+    fields: &'s mut [FieldReflection<'_, Mut>],
+) -> Vec<FieldReflection<'s, Mut>> {
+    fields
+        .iter_mut()
+        .map(|field| FieldReflection {
+            name: field.name,
+            value: field.value.reborrow(),
+        })
+        .collect()
+}
+
+impl<'a> From<ValueMut<'a>> for ValueRef<'a> {
+    fn from(value: ValueMut<'a>) -> Self {
+        value.into_ref()
+    }
+}
+
+impl<'a, A: Access> FieldReflection<'a, A> {
+    /// Used by the derive.
+    ///
+    /// # Safety
+    /// `value` must reflect the field called `name` of the value
+    /// the reflection it is handed to points to.
+    #[doc(hidden)]
+    pub unsafe fn from_raw(name: &'a str, value: ValueReflection<'a, A>) -> Self {
+        FieldReflection { name, value }
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn value(&self) -> &ValueReflection<'a, A> {
+        &self.value
+    }
+
+    /// Gives up the field, e.g. after [`StructReflection::into_fields`].
+    pub fn into_value(self) -> ValueReflection<'a, A> {
+        self.value
+    }
+}
+
+impl<A: Access> FieldReflection<'_, A> {
+    /// Reborrows the reflection as read only.
+    pub fn to_ref(&self) -> FieldReflection<'_, Ref> {
+        FieldReflection {
+            name: self.name,
+            value: self.value.to_ref(),
+        }
+    }
+}
+
+impl<'a> FieldReflection<'a, Mut> {
+    /// Gives up mutable access.
+    pub fn into_ref(self) -> FieldReflection<'a, Ref> {
+        FieldReflection {
+            name: self.name,
+            value: self.value.into_ref(),
+        }
+    }
+}
+
+impl<A: Access> StructReflection<'_, A> {
+    /// Reborrows the reflection as read only.
+    pub fn to_ref(&self) -> StructReflection<'_, Ref> {
+        StructReflection {
+            name: self.name,
+            fields: self.fields.iter().map(FieldReflection::to_ref).collect(),
+            ptr: self.ptr,
+            mirror: self.mirror,
+        }
+    }
+}
+
+impl<'a, A: Access> StructReflection<'a, A> {
+    pub fn mirror(&self) -> &'a Struct {
+        self.mirror
+    }
+
+    /// Points to the struct, only written through if `A` is [`Mut`].
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.ptr
+    }
+
+    /// The fields in declaration order.
+    pub fn fields(&self) -> &[FieldReflection<'a, A>] {
+        &self.fields
+    }
+
+    /// Gives up the struct, e.g. to take ownership of the field reflections.
+    pub fn into_fields(self) -> Vec<FieldReflection<'a, A>> {
+        self.fields
+    }
+
+    /// Position of the field with the given name in `fields`, see [`Struct::index_of`].
+    pub fn field_index(&self, name: &str) -> Option<usize> {
+        (self.mirror.index_of)(name)
+    }
+
+    pub fn field(&self, name: &str) -> Option<&ValueReflection<'a, A>> {
+        let index = self.field_index(name)?;
+        Some(&self.fields[index].value)
+    }
+}
+
+impl<'a, A: Access> RustEnumReflection<'a, A> {
+    /// Used by the derive.
+    ///
+    /// # Safety
+    /// `ptr` must point to a valid enum described by `ty`, whose current variant is `variant_idx`.
+    /// `fields` must reflect the fields of that variant in order.
+    #[doc(hidden)]
+    pub unsafe fn from_raw(
+        ptr: *mut u8,
+        ty: &'a Type,
+        variant_idx: usize,
+        fields: Vec<FieldReflection<'a, A>>,
+    ) -> Self {
+        let Type::RustEnum(mirror) = ty else {
+            unreachable!("RustEnumReflection of non enum type")
+        };
+        RustEnumReflection {
+            name: mirror.name,
+            variant_name: mirror.variants[variant_idx].name,
+            variant_idx,
+            ty,
+            ptr,
+            fields,
+        }
+    }
+
+    pub fn ty(&self) -> &'a Type {
+        self.ty
+    }
+
+    /// Points to the enum, only written through if `A` is [`Mut`].
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.ptr
+    }
+
+    /// The fields of the current variant.
+    pub fn fields(&self) -> &[FieldReflection<'a, A>] {
+        &self.fields
+    }
+
+    /// Gives up the enum, e.g. to take ownership of the field reflections.
+    pub fn into_fields(self) -> Vec<FieldReflection<'a, A>> {
+        self.fields
+    }
+
+    /// Position of the field of the current variant with the given name in `fields`.
+    pub fn field_index(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|field| field.name == name)
+    }
+
+    pub fn field(&self, name: &str) -> Option<&ValueReflection<'a, A>> {
+        let index = self.field_index(name)?;
+        Some(&self.fields[index].value)
+    }
+}
+
+impl<'a, A: Access> BoxReflection<'a, A> {
+    /// Points to the Box, only written through if `A` is [`Mut`].
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.ptr
+    }
+
+    /// The boxed value.
+    pub fn inner(&self) -> &ValueReflection<'a, A> {
+        &self.inner
+    }
+
+    /// Gives up the Box, keeping the reflection of the boxed value.
+    pub fn into_inner(self) -> ValueReflection<'a, A> {
+        self.inner
+    }
+}
+
+impl BoxReflection<'_, Mut> {
+    /// The boxed value, reborrowed so it can't outlive a
+    /// [`downcast_mut`](ValueReflection::downcast_mut) of the Box.
+    pub fn inner_mut(&mut self) -> ValueMut<'_> {
+        self.inner.reborrow()
+    }
+}
+
+impl<A: Access> StructReflection<'_, A> {
+    /// Checks if the reflected struct is a `T`.
+    pub fn is<T: Quicksilver>(&self) -> bool {
+        matches!(T::MIRROR, Type::Struct(mirror) if mirror.type_id == self.mirror.type_id)
+    }
+}
+
+impl<'a> StructReflection<'a, Ref> {
+    /// Returns the reflected struct, if it is a `T`.
+    pub fn downcast_ref<T: Quicksilver>(&self) -> Option<&'a T> {
+        self.is::<T>().then(|| unsafe { &*(self.ptr as *const T) })
+    }
 }
 
-pub fn reflect<T: Quicksilver>(val: &mut T) -> ValueReflection<'_> {
+impl<'a> StructReflection<'a, Mut> {
+    /// The fields in declaration order, reborrowed so they can't outlive
+    /// a [`downcast_mut`](StructReflection::downcast_mut).
+    pub fn fields_mut(&mut self) -> impl Iterator<Item = (&str, ValueMut<'_>)> {
+        self.fields
+            .iter_mut()
+            .map(|field| (field.name, field.value.reborrow()))
+    }
+
+    /// The field with the given name, reborrowed like [`fields_mut`](StructReflection::fields_mut).
+    /// ```compile_fail
+    /// # use quicksilver::Quicksilver;
+    /// # use quicksilver::reflections::{reflect, ValueReflection};
+    /// # #[derive(Quicksilver)]
+    /// # struct Pos {
+    /// #     x: i32,
+    /// # }
+    /// let mut pos = Pos { x: 1 };
+    /// let ValueReflection::Struct(mut s) = reflect(&mut pos) else { panic!() };
+    /// let mut dummy = 0;
+    /// let mut x = s.field_mut("x").unwrap();
+    /// let old = std::mem::replace(&mut x, ValueReflection::I32(&mut dummy));
+    /// let pos = s.downcast_mut::<Pos>().ok().unwrap();
+    /// drop(old);
+    /// ```
+    pub fn field_mut(&mut self, name: &str) -> Option<ValueMut<'_>> {
+        let index = self.field_index(name)?;
+        Some(self.fields[index].value.reborrow())
+    }
+
+    /// Turns the reflection into the reflected struct, if it is a `T`.
+    /// Otherwise the reflection is handed back.
+    ///
+    /// The reflection is consumed, because the reflections of its fields
+    /// would alias the returned reference.
+    pub fn downcast_mut<T: Quicksilver>(self) -> Result<&'a mut T, Self> {
+        if self.is::<T>() {
+            Ok(unsafe { &mut *(self.ptr as *mut T) })
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl RustEnumReflection<'_, Mut> {
+    /// The fields of the current variant, reborrowed so they can't outlive
+    /// a [`switch_variant`](RustEnumReflection::switch_variant).
+    pub fn fields_mut(&mut self) -> impl Iterator<Item = (&str, ValueMut<'_>)> {
+        self.fields
+            .iter_mut()
+            .map(|field| (field.name, field.value.reborrow()))
+    }
+
+    /// The field of the current variant with the given name, reborrowed like
+    /// [`fields_mut`](RustEnumReflection::fields_mut).
+    /// ```compile_fail
+    /// # use quicksilver::Quicksilver;
+    /// # use quicksilver::reflections::{reflect, ValueReflection};
+    /// # #[derive(Quicksilver)]
+    /// # enum Shape {
+    /// #     Circle { radius: i32 },
+    /// #     Square { side: u32 },
+    /// # }
+    /// let mut shape = Shape::Circle { radius: 1 };
+    /// let ValueReflection::RustEnum(mut e) = reflect(&mut shape) else { panic!() };
+    /// let mut dummy = 0;
+    /// let mut radius = e.field_mut("radius").unwrap();
+    /// let old = std::mem::replace(&mut radius, ValueReflection::I32(&mut dummy));
+    /// e.switch_variant(1);
+    /// drop(old);
+    /// ```
+    pub fn field_mut(&mut self, name: &str) -> Option<ValueMut<'_>> {
+        let index = self.field_index(name)?;
+        Some(self.fields[index].value.reborrow())
+    }
+
+    /// Replaces the enum with the variant at `variant_idx`.
+    ///
+    /// The fields of the new variant are default constructed,
+    /// see [`construct_default`](crate::default::construct_default).
+    /// The old value is dropped.
+    pub fn switch_variant(&mut self, variant_idx: usize) {
+        let Type::RustEnum(mirror) = self.ty else {
+            unreachable!("RustEnumReflection of non enum type {}", self.name)
+        };
+        let variant = mirror
+            .variants
+            .get(variant_idx)
+            .unwrap_or_else(|| panic!("{} has no variant with index {variant_idx}", mirror.name));
+        let fields: Vec<*mut u8> = variant
+            .fields
+            .iter()
+            .map(|(_, ty)| box_default(ty))
+            .collect();
+        // the field reflections point into the old value
+        self.fields.clear();
+        unsafe {
+            (mirror.drop)(self.ptr);
+            (mirror.write)(self.ptr, variant_idx, &fields);
+            // only the lifetime differs
+            *self = std::mem::transmute::<RustEnumReflection<'static, Mut>, Self>(
+                (mirror.reflect)(self.ptr),
+            );
+        }
+    }
+}
+
+pub fn reflect<T: Quicksilver>(val: &mut T) -> ValueMut<'_> {
     unsafe { reflect_value(val as *mut T as *mut u8, &T::MIRROR) }
 }
 
-pub unsafe fn reflect_struct(base: *mut u8, mirror: &Struct) -> StructReflection<'_> {
-    let mut fields: Vec<FieldReflection> = Vec::new();
+/// # Safety
+/// `base` must point to a valid value described by `mirror`.
+pub unsafe fn reflect_struct(base: *mut u8, mirror: &Struct) -> StructReflection<'_, Mut> {
+    let mut fields: Vec<FieldReflection<Mut>> = Vec::new();
     for field in mirror.fields {
         unsafe {
             let ptr = base.add(field.offset);
@@ -79,58 +843,63 @@ pub unsafe fn reflect_struct(base: *mut u8, mirror: &Struct) -> StructReflection
     StructReflection {
         name: mirror.name,
         fields,
+        ptr: base,
+        mirror,
     }
 }
 
-pub unsafe fn reflect_value(ptr: *mut u8, ty: &Type) -> ValueReflection<'_> {
+/// # Safety
+/// `ptr` must point to a valid value described by `ty`.
+pub unsafe fn reflect_value(ptr: *mut u8, ty: &Type) -> ValueMut<'_> {
     match ty {
         Type::I32 => {
             let value = unsafe { &mut *(ptr as *mut i32) };
-            ValueReflection::I32(value.into())
+            ValueReflection::I32(value)
         }
         Type::CEnum(cenum) => {
             let value = unsafe { &mut *(ptr as *mut i32) };
             ValueReflection::CEnum(Box::new(CEnumReflection {
                 name: cenum.name,
-                val: value.into(),
+                val: value,
                 variants: cenum.variants,
+                mirror: cenum,
             }))
         }
         Type::U32 => {
             let value = unsafe { &mut *(ptr as *mut u32) };
-            ValueReflection::U32(value.into())
+            ValueReflection::U32(value)
         }
         Type::F32 => {
             let value = unsafe { &mut *(ptr as *mut f32) };
-            ValueReflection::F32(value.into())
+            ValueReflection::F32(value)
         }
         Type::I64 => {
             let value = unsafe { &mut *(ptr as *mut i64) };
-            ValueReflection::I64(value.into())
+            ValueReflection::I64(value)
         }
         Type::U64 => {
             let value = unsafe { &mut *(ptr as *mut u64) };
-            ValueReflection::U64(value.into())
+            ValueReflection::U64(value)
         }
         Type::F64 => {
             let value = unsafe { &mut *(ptr as *mut f64) };
-            ValueReflection::F64(value.into())
+            ValueReflection::F64(value)
         }
         Type::ISize => {
             let value = unsafe { &mut *(ptr as *mut isize) };
-            ValueReflection::ISize(value.into())
+            ValueReflection::ISize(value)
         }
         Type::USize => {
             let value = unsafe { &mut *(ptr as *mut usize) };
-            ValueReflection::USize(value.into())
+            ValueReflection::USize(value)
         }
         Type::Bool => {
             let value = unsafe { &mut *(ptr as *mut bool) };
-            ValueReflection::Bool(value.into())
+            ValueReflection::Bool(value)
         }
         Type::String => {
             let value = unsafe { &mut *(ptr as *mut String) };
-            ValueReflection::String(value.into())
+            ValueReflection::String(value)
         }
         Type::Struct(s) => ValueReflection::Struct(Box::new(unsafe { reflect_struct(ptr, s) })),
         Type::Vec(v) => ValueReflection::Vec(Box::new(VecReflection {
@@ -138,6 +907,7 @@ pub unsafe fn reflect_value(ptr: *mut u8, ty: &Type) -> ValueReflection<'_> {
             ptr,
             vtable: &v.vtable,
             skip: v.skip,
+            _access: PhantomData,
         })),
         Type::HashMap(hm) => ValueReflection::HashMap(Box::new(HMReflection {
             key: hm.key,
@@ -145,68 +915,34 @@ pub unsafe fn reflect_value(ptr: *mut u8, ty: &Type) -> ValueReflection<'_> {
             ptr,
             vtable: &hm.vtable,
             skip: hm.skip,
+            _access: PhantomData,
         })),
         Type::HashSet(hs) => ValueReflection::HashSet(Box::new(HSReflection {
             element: hs.element,
             ptr,
             vtable: &hs.vtable,
             skip: hs.skip,
+            _access: PhantomData,
         })),
         Type::Option(o) => ValueReflection::Option(Box::new(OptionReflection {
             element: o.element,
-            ptr: ptr,
+            ptr,
             vtable: &o.vtable,
             skip: o.skip,
+            _access: PhantomData,
         })),
-        Type::RustEnum(re_mirror) => ValueReflection::RustEnum(unsafe { (re_mirror.reflect)(ptr) }),
+        Type::RustEnum(re_mirror) => ValueReflection::RustEnum(unsafe {
+            // only the lifetime differs
+            std::mem::transmute::<RustEnumReflection<'static, Mut>, RustEnumReflection<'_, Mut>>(
+                (re_mirror.reflect)(ptr),
+            )
+        }),
         Type::Box(box_mirror) => unsafe {
             let inner_ptr = (box_mirror.get)(ptr);
             ValueReflection::Box(Box::new(BoxReflection {
                 inner: reflect_value(inner_ptr, box_mirror.inner),
+                ptr,
             }))
         },
     }
 }
-
-pub enum RefOrMut<'a, T> {
-    Ref(&'a T),
-    Mut(&'a mut T),
-}
-
-impl<T: Debug> Debug for RefOrMut<'_, T> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
-        (**self).fmt(f)
-    }
-}
-
-impl<T> Deref for RefOrMut<'_, T> {
-    type Target = T;
-    fn deref(&self) -> &<Self as Deref>::Target {
-        match self {
-            RefOrMut::Ref(r) => r,
-            RefOrMut::Mut(r) => r,
-        }
-    }
-}
-
-impl<T> DerefMut for RefOrMut<'_, T> {
-    #[track_caller]
-    fn deref_mut(&mut self) -> &mut <Self as Deref>::Target {
-        match self {
-            RefOrMut::Ref(_) => panic!("Immutable reference can't be used as mutable."),
-            RefOrMut::Mut(r) => r,
-        }
-    }
-}
-
-impl<'a, T> From<&'a T> for RefOrMut<'a, T> {
-    fn from(r: &'a T) -> Self {
-        Self::Ref(r)
-    }
-}
-
-impl<'a, T> From<&'a mut T> for RefOrMut<'a, T> {
-    fn from(r: &'a mut T) -> Self {
-        Self::Mut(r)
-    }
-}
@@ -1,20 +1,35 @@
+use std::marker::PhantomData;
+
 use crate::{
     Quicksilver, Struct, Type,
     map::HMReflection,
     option::OptionReflection,
     reflections::{
-        BoxReflection, CEnumReflection, FieldReflection, StructReflection, ValueReflection,
+        BoxReflection, CEnumReflection, FieldReflection, Ref, RustEnumReflection, StructReflection,
+        ValueRef, ValueReflection,
     },
     set::HSReflection,
     vec::VecReflection,
 };
 
-pub fn reflect_ref<T: Quicksilver>(val: &T) -> ValueReflection<'_> {
+/// Reflects a value read only.
+///
+/// The returned reflection can't be used to mutate the value:
+/// ```compile_fail
+/// # use quicksilver::reflections::ValueReflection;
+/// # use quicksilver::reflections_ref::reflect_ref;
+/// let number = 5;
+/// let ValueReflection::I32(val) = reflect_ref(&number) else { panic!() };
+/// *val = 6;
+/// ```
+pub fn reflect_ref<T: Quicksilver>(val: &T) -> ValueRef<'_> {
     unsafe { reflect_value_ref(val as *const T as *const u8, &T::MIRROR) }
 }
 
-pub unsafe fn reflect_struct_ref(base: *const u8, mirror: &Struct) -> StructReflection<'_> {
-    let mut fields: Vec<FieldReflection> = Vec::new();
+/// # Safety
+/// `base` must point to a valid value described by `mirror`.
+pub unsafe fn reflect_struct_ref(base: *const u8, mirror: &Struct) -> StructReflection<'_, Ref> {
+    let mut fields: Vec<FieldReflection<Ref>> = Vec::new();
     for field in mirror.fields {
         unsafe {
             let ptr = base.add(field.offset);
@@ -27,58 +42,63 @@ pub unsafe fn reflect_struct_ref(base: *const u8, mirror: &Struct) -> StructRefl
     StructReflection {
         name: mirror.name,
         fields,
+        ptr: base as *mut u8,
+        mirror,
     }
 }
 
-pub unsafe fn reflect_value_ref(ptr: *const u8, ty: &Type) -> ValueReflection<'_> {
+/// # Safety
+/// `ptr` must point to a valid value described by `ty`.
+pub unsafe fn reflect_value_ref(ptr: *const u8, ty: &Type) -> ValueRef<'_> {
     match ty {
         Type::I32 => {
             let value = unsafe { &*(ptr as *const i32) };
-            ValueReflection::I32(value.into())
+            ValueReflection::I32(value)
         }
         Type::U32 => {
             let value = unsafe { &*(ptr as *const u32) };
-            ValueReflection::U32(value.into())
+            ValueReflection::U32(value)
         }
         Type::F32 => {
             let value = unsafe { &*(ptr as *const f32) };
-            ValueReflection::F32(value.into())
+            ValueReflection::F32(value)
         }
         Type::I64 => {
             let value = unsafe { &*(ptr as *const i64) };
-            ValueReflection::I64(value.into())
+            ValueReflection::I64(value)
         }
         Type::U64 => {
             let value = unsafe { &*(ptr as *const u64) };
-            ValueReflection::U64(value.into())
+            ValueReflection::U64(value)
         }
         Type::F64 => {
             let value = unsafe { &*(ptr as *const f64) };
-            ValueReflection::F64(value.into())
+            ValueReflection::F64(value)
         }
         Type::ISize => {
             let value = unsafe { &*(ptr as *const isize) };
-            ValueReflection::ISize(value.into())
+            ValueReflection::ISize(value)
         }
         Type::USize => {
             let value = unsafe { &*(ptr as *const usize) };
-            ValueReflection::USize(value.into())
+            ValueReflection::USize(value)
         }
         Type::Bool => {
             let value = unsafe { &*(ptr as *const bool) };
-            ValueReflection::Bool(value.into())
+            ValueReflection::Bool(value)
         }
         Type::String => {
             let value = unsafe { &*(ptr as *const String) };
-            ValueReflection::String(value.into())
+            ValueReflection::String(value)
         }
         Type::Struct(s) => ValueReflection::Struct(Box::new(unsafe { reflect_struct_ref(ptr, s) })),
         Type::CEnum(cenum) => {
             let value = unsafe { &*(ptr as *const i32) };
             ValueReflection::CEnum(Box::new(CEnumReflection {
                 name: cenum.name,
-                val: value.into(),
+                val: value,
                 variants: cenum.variants,
+                mirror: cenum,
             }))
         }
         Type::Vec(v) => ValueReflection::Vec(Box::new(VecReflection {
@@ -86,6 +106,7 @@ pub unsafe fn reflect_value_ref(ptr: *const u8, ty: &Type) -> ValueReflection<'_
             ptr: ptr as *mut u8,
             vtable: &v.vtable,
             skip: v.skip,
+            _access: PhantomData,
         })),
         Type::HashMap(hm) => ValueReflection::HashMap(Box::new(HMReflection {
             key: hm.key,
@@ -93,24 +114,33 @@ pub unsafe fn reflect_value_ref(ptr: *const u8, ty: &Type) -> ValueReflection<'_
             ptr: ptr as *mut u8,
             vtable: &hm.vtable,
             skip: hm.skip,
+            _access: PhantomData,
         })),
         Type::HashSet(hs) => ValueReflection::HashSet(Box::new(HSReflection {
             element: hs.element,
             ptr: ptr as *mut u8,
             vtable: &hs.vtable,
             skip: hs.skip,
+            _access: PhantomData,
         })),
         Type::Option(o) => ValueReflection::Option(Box::new(OptionReflection {
             element: o.element,
             ptr: ptr as *mut u8,
             vtable: &o.vtable,
             skip: o.skip,
+            _access: PhantomData,
         })),
-        Type::RustEnum(renum) => ValueReflection::RustEnum(unsafe { (renum.reflect_ref)(ptr) }),
+        Type::RustEnum(renum) => ValueReflection::RustEnum(unsafe {
+            // only the lifetime differs
+            std::mem::transmute::<RustEnumReflection<'static, Ref>, RustEnumReflection<'_, Ref>>(
+                (renum.reflect_ref)(ptr),
+            )
+        }),
         Type::Box(box_mirror) => unsafe {
             let inner_ptr = (box_mirror.get_ref)(ptr);
             ValueReflection::Box(Box::new(BoxReflection {
                 inner: reflect_value_ref(inner_ptr, box_mirror.inner),
+                ptr: ptr as *mut u8,
             }))
         },
     }
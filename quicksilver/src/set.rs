@@ -3,7 +3,7 @@ use std::marker::PhantomData;
 
 use std::hash::Hash;
 
-use crate::reflections::ValueReflection;
+use crate::reflections::{Access, Mut, ValueMut, ValueRef, reflect_value};
 use crate::reflections_ref::reflect_value_ref;
 use crate::{Quicksilver, Type};
 
@@ -14,8 +14,21 @@ pub struct HSVtable {
     /// adds element to hashmap
     /// element pointers need to be created with Box::into_raw
     pub fill_with: unsafe fn(ptr: *mut u8, element_ptr: *mut u8),
-    /// returns all elements in HashSet in whatever iteration order it sees fit
-    pub get_elements_ref: unsafe fn(ptr: *const u8) -> Vec<ValueReflection<'static>>,
+    /// returns pointers to all elements in HashSet in whatever iteration order it sees fit
+    pub get_elements: unsafe fn(ptr: *const u8) -> Vec<*const u8>,
+    /// number of elements
+    pub len: unsafe fn(ptr: *const u8) -> usize,
+    /// get the element equal to the given one
+    pub get: unsafe fn(ptr: *const u8, element_ptr: *const u8) -> Option<*const u8>,
+    /// removes and drops the element, returns if there was one
+    pub remove: unsafe fn(ptr: *mut u8, element_ptr: *const u8) -> bool,
+    /// removes the element and hands ownership of it to the caller
+    /// returned pointer is created with Box::into_raw
+    pub take: unsafe fn(ptr: *mut u8, element_ptr: *const u8) -> Option<*mut u8>,
+    /// removes and drops all elements
+    pub clear: unsafe fn(ptr: *mut u8),
+    /// drops the HashSet in place
+    pub drop: unsafe fn(ptr: *mut u8),
 }
 
 pub struct HSVtableCreator<T> {
@@ -31,7 +44,13 @@ where
     pub const VTABLE: HSVtable = HSVtable {
         new_at: Self::new_at,
         fill_with: Self::fill_with,
-        get_elements_ref: Self::get_elements_ref,
+        get_elements: Self::get_elements,
+        len: Self::len,
+        get: Self::get,
+        remove: Self::remove,
+        take: Self::take,
+        clear: Self::clear,
+        drop: Self::drop,
     };
 
     unsafe fn new_at(ptr: *mut u8) {
@@ -42,6 +61,10 @@ where
         }
     }
 
+    unsafe fn drop(ptr: *mut u8) {
+        unsafe { std::ptr::drop_in_place(ptr as *mut HashSet<T>) }
+    }
+
     unsafe fn fill_with(ptr: *mut u8, element_ptr: *mut u8) {
         let ptr = ptr as *mut HashSet<T>;
         let element_ptr = element_ptr as *mut T;
@@ -52,30 +75,211 @@ where
         }
     }
 
-    unsafe fn get_elements_ref(ptr: *const u8) -> Vec<ValueReflection<'static>> {
-        let ptr = ptr as *mut HashSet<T>;
-        let mut result = Vec::new();
+    unsafe fn get_elements(ptr: *const u8) -> Vec<*const u8> {
+        let ptr = ptr as *const HashSet<T>;
+        unsafe {
+            let val = &*ptr;
+            val.iter().map(|el| el as *const T as *const u8).collect()
+        }
+    }
+
+    unsafe fn len(ptr: *const u8) -> usize {
+        let ptr = ptr as *const HashSet<T>;
+        unsafe { (*ptr).len() }
+    }
+
+    unsafe fn get(ptr: *const u8, element_ptr: *const u8) -> Option<*const u8> {
+        let ptr = ptr as *const HashSet<T>;
         unsafe {
             let val = &*ptr;
-            for el in val.iter() {
-                result.push(reflect_value_ref(el as *const T as *const u8, &T::MIRROR))
-            }
+            let e = &*(element_ptr as *const T);
+            val.get(e).map(|it| it as *const T as *const u8)
+        }
+    }
+
+    unsafe fn remove(ptr: *mut u8, element_ptr: *const u8) -> bool {
+        let ptr = ptr as *mut HashSet<T>;
+        unsafe {
+            let val = &mut *ptr;
+            let e = &*(element_ptr as *const T);
+            val.remove(e)
+        }
+    }
+
+    unsafe fn take(ptr: *mut u8, element_ptr: *const u8) -> Option<*mut u8> {
+        let ptr = ptr as *mut HashSet<T>;
+        unsafe {
+            let val = &mut *ptr;
+            let e = &*(element_ptr as *const T);
+            val.take(e).map(|it| Box::into_raw(Box::new(it)) as *mut u8)
         }
-        result
+    }
+
+    unsafe fn clear(ptr: *mut u8) {
+        let ptr = ptr as *mut HashSet<T>;
+        unsafe { (*ptr).clear() }
     }
 }
 
+/// Reflection of a HashSet.
+///
+/// A read only reflection can't be turned into a mutable one:
+/// ```compile_fail
+/// # use std::collections::HashSet;
+/// # use std::marker::PhantomData;
+/// # use quicksilver::set::HSReflection;
+/// # use quicksilver::reflections::{Mut, ValueReflection};
+/// # use quicksilver::reflections_ref::reflect_ref;
+/// let val: HashSet<i32> = HashSet::new();
+/// let ValueReflection::HashSet(r) = reflect_ref(&val) else { panic!() };
+/// let mut hs = HSReflection::<Mut> {
+///     element: r.element,
+///     ptr: r.ptr,
+///     vtable: r.vtable,
+///     skip: r.skip,
+///     _access: PhantomData,
+/// };
+/// hs.clear();
+/// ```
 #[repr(C)]
-pub struct HSReflection<'a> {
-    pub element: &'a Type,
-    pub ptr: *mut u8,
-    pub vtable: &'a HSVtable,
+pub struct HSReflection<'a, A: Access = Mut> {
+    pub(crate) element: &'a Type,
+    /// points to the HashSet, only written through if `A` is [`Mut`]
+    pub(crate) ptr: *mut u8,
+    pub(crate) vtable: &'a HSVtable,
     pub skip: bool,
+    pub(crate) _access: PhantomData<A>,
 }
 
-impl HSReflection<'_> {
-    pub fn get_elements_ref(&self) -> Vec<ValueReflection<'_>> {
-        unsafe { (self.vtable.get_elements_ref)(self.ptr) }
+impl<'a, A: Access> HSReflection<'a, A> {
+    /// The type of the elements.
+    pub fn element_ty(&self) -> &'a Type {
+        self.element
+    }
+
+    /// Points to the HashSet, only written through if `A` is [`Mut`].
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.ptr
+    }
+
+    /// Iterates over all elements in whatever order the HashSet sees fit.
+    ///
+    /// The returned reflections borrow the HashSet, so they can't outlive it:
+    /// ```compile_fail
+    /// # use std::collections::HashSet;
+    /// # use quicksilver::reflections::{ValueReflection, reflect};
+    /// let elements = {
+    ///     let mut set: HashSet<String> = HashSet::new();
+    ///     let ValueReflection::HashSet(hs) = reflect(&mut set) else { panic!() };
+    ///     hs.iter().collect::<Vec<_>>()
+    /// };
+    /// ```
+    pub fn iter(&self) -> HSIter<'_> {
+        HSIter {
+            elements: unsafe { (self.vtable.get_elements)(self.ptr) }.into_iter(),
+            element: self.element,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        unsafe { (self.vtable.len)(self.ptr) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// # Safety
+    /// `element` must point to a valid value of the element type.
+    pub unsafe fn contains(&self, element: *const u8) -> bool {
+        unsafe { (self.vtable.get)(self.ptr, element).is_some() }
+    }
+
+    /// Returns the element stored in the HashSet which is equal to `element`.
+    ///
+    /// # Safety
+    /// `element` must point to a valid value of the element type.
+    pub unsafe fn get(&self, element: *const u8) -> Option<ValueRef<'_>> {
+        unsafe {
+            let ptr = (self.vtable.get)(self.ptr, element);
+            ptr.map(|it| reflect_value_ref(it, self.element))
+        }
+    }
+}
+
+impl HSReflection<'_, Mut> {
+    /// Inserts an element, if an equal element is already present the new one is dropped.
+    ///
+    /// # Safety
+    /// `element` must be created with `Box::into_raw` and be of the element type.
+    /// The HashSet takes ownership of it.
+    pub unsafe fn insert(&mut self, element: *mut u8) {
+        unsafe { (self.vtable.fill_with)(self.ptr, element) }
+    }
+
+    /// Removes and drops the element equal to `element`, returns if there was one.
+    ///
+    /// # Safety
+    /// `element` must point to a valid value of the element type, which is not stored in the HashSet.
+    pub unsafe fn remove(&mut self, element: *const u8) -> bool {
+        unsafe { (self.vtable.remove)(self.ptr, element) }
+    }
+
+    pub fn clear(&mut self) {
+        unsafe { (self.vtable.clear)(self.ptr) }
+    }
+
+    /// Edits an element.
+    ///
+    /// Elements can't be mutated inside of a HashSet, so the element is removed,
+    /// handed to `edit` and then inserted again.
+    /// If `edit` panics, the element is still inserted again as `edit` left it.
+    /// Returns `false` if there is no element equal to `element`.
+    ///
+    /// # Safety
+    /// `element` must point to a valid value of the element type, which is not stored in the HashSet.
+    pub unsafe fn edit(&mut self, element: *const u8, edit: impl FnOnce(ValueMut)) -> bool {
+        unsafe {
+            let Some(element) = (self.vtable.take)(self.ptr, element) else {
+                return false;
+            };
+            let element = Reinsert {
+                ptr: self.ptr,
+                vtable: self.vtable,
+                element,
+            };
+            edit(reflect_value(element.element, self.element));
+        }
+        true
+    }
+}
+
+/// Inserts an element removed by [`HSReflection::edit`] again when dropped,
+/// so it isn't lost if the edit panics.
+struct Reinsert<'a> {
+    ptr: *mut u8,
+    vtable: &'a HSVtable,
+    element: *mut u8,
+}
+
+impl Drop for Reinsert<'_> {
+    fn drop(&mut self) {
+        unsafe { (self.vtable.fill_with)(self.ptr, self.element) }
+    }
+}
+
+/// Iterator over the elements of a HashSet, see [`HSReflection::iter`]
+pub struct HSIter<'a> {
+    elements: std::vec::IntoIter<*const u8>,
+    element: &'a Type,
+}
+
+impl<'a> Iterator for HSIter<'a> {
+    type Item = ValueRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let element = self.elements.next()?;
+        unsafe { Some(reflect_value_ref(element, self.element)) }
     }
 }
 
@@ -91,7 +295,13 @@ where
     pub const VTABLE: HSVtable = HSVtable {
         new_at: Self::new_at,
         fill_with: empty_fill_with,
-        get_elements_ref: empty_get_elements_ref,
+        get_elements: empty_get_elements,
+        len: empty_len,
+        get: empty_get,
+        remove: empty_remove,
+        take: empty_take,
+        clear: empty_clear,
+        drop: Self::drop,
     };
 
     unsafe fn new_at(ptr: *mut u8) {
@@ -101,12 +311,36 @@ where
             ptr.write(v);
         }
     }
+
+    unsafe fn drop(ptr: *mut u8) {
+        unsafe { std::ptr::drop_in_place(ptr as *mut HashSet<T>) }
+    }
 }
 
 unsafe fn empty_fill_with(_ptr: *mut u8, _element_ptr: *mut u8) {
     panic!("Not supported on skipped fields");
 }
 
-unsafe fn empty_get_elements_ref(_ptr: *const u8) -> Vec<ValueReflection<'static>> {
+unsafe fn empty_get_elements(_ptr: *const u8) -> Vec<*const u8> {
+    panic!("Not supported on skipped fields");
+}
+
+unsafe fn empty_len(_ptr: *const u8) -> usize {
+    panic!("Not supported on skipped fields");
+}
+
+unsafe fn empty_get(_ptr: *const u8, _element_ptr: *const u8) -> Option<*const u8> {
+    panic!("Not supported on skipped fields");
+}
+
+unsafe fn empty_remove(_ptr: *mut u8, _element_ptr: *const u8) -> bool {
+    panic!("Not supported on skipped fields");
+}
+
+unsafe fn empty_take(_ptr: *mut u8, _element_ptr: *const u8) -> Option<*mut u8> {
+    panic!("Not supported on skipped fields");
+}
+
+unsafe fn empty_clear(_ptr: *mut u8) {
     panic!("Not supported on skipped fields");
 }
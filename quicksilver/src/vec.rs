@@ -2,7 +2,8 @@ use std::marker::PhantomData;
 
 use crate::{
     Quicksilver, Type,
-    reflections::{ValueReflection, reflect_value},
+    default::box_default,
+    reflections::{Access, Mut, ValueMut, ValueRef, reflect_value},
     reflections_ref::reflect_value_ref,
 };
 
@@ -23,6 +24,20 @@ pub struct VecVtable {
     pub get_elem: unsafe fn(ptr: *mut u8, index: usize) -> *mut u8,
     /// get element at index immutably
     pub get_elem_ref: unsafe fn(ptr: *const u8, index: usize) -> *const u8,
+    /// appends element
+    /// element pointer needs to be created with Box::into_raw
+    pub push: unsafe fn(ptr: *mut u8, element: *mut u8),
+    /// inserts element at index, like Vec::insert
+    /// element pointer needs to be created with Box::into_raw
+    pub insert: unsafe fn(ptr: *mut u8, index: usize, element: *mut u8),
+    /// removes and drops the element at index, like Vec::remove
+    pub remove: unsafe fn(ptr: *mut u8, index: usize),
+    /// swaps two elements, like Vec::swap
+    pub swap: unsafe fn(ptr: *mut u8, a: usize, b: usize),
+    /// shortens the Vec and drops the rest, like Vec::truncate
+    pub truncate: unsafe fn(ptr: *mut u8, len: usize),
+    /// drops the Vec in place
+    pub drop: unsafe fn(ptr: *mut u8),
 }
 
 pub struct VecVtableCreator<T> {
@@ -40,6 +55,12 @@ where
         get_len: Self::get_len,
         get_elem: Self::get_elem,
         get_elem_ref: Self::get_elem_ref,
+        push: Self::push,
+        insert: Self::insert,
+        remove: Self::remove,
+        swap: Self::swap,
+        truncate: Self::truncate,
+        drop: Self::drop,
     };
 
     unsafe fn new_at(ptr: *mut u8, capacity: usize) -> *mut u8 {
@@ -52,6 +73,10 @@ where
         }
     }
 
+    unsafe fn drop(ptr: *mut u8) {
+        unsafe { std::ptr::drop_in_place(ptr as *mut Vec<T>) }
+    }
+
     unsafe fn set_len(ptr: *mut u8, len: usize) {
         let ptr = ptr as *mut Vec<T>;
         unsafe {
@@ -94,32 +119,164 @@ where
             el as *const u8
         }
     }
+
+    unsafe fn push(ptr: *mut u8, element: *mut u8) {
+        let ptr = ptr as *mut Vec<T>;
+        unsafe {
+            let val = &mut *ptr;
+            let e: T = *Box::from_raw(element as *mut T);
+            val.push(e);
+        }
+    }
+
+    unsafe fn insert(ptr: *mut u8, index: usize, element: *mut u8) {
+        let ptr = ptr as *mut Vec<T>;
+        unsafe {
+            let val = &mut *ptr;
+            let e: T = *Box::from_raw(element as *mut T);
+            val.insert(index, e);
+        }
+    }
+
+    unsafe fn remove(ptr: *mut u8, index: usize) {
+        let ptr = ptr as *mut Vec<T>;
+        unsafe {
+            let val = &mut *ptr;
+            val.remove(index);
+        }
+    }
+
+    unsafe fn swap(ptr: *mut u8, a: usize, b: usize) {
+        let ptr = ptr as *mut Vec<T>;
+        unsafe {
+            let val = &mut *ptr;
+            val.swap(a, b);
+        }
+    }
+
+    unsafe fn truncate(ptr: *mut u8, len: usize) {
+        let ptr = ptr as *mut Vec<T>;
+        unsafe {
+            let val = &mut *ptr;
+            val.truncate(len);
+        }
+    }
 }
 
+/// Reflection of a Vec.
+///
+/// A read only reflection can't be turned into a mutable one:
+/// ```compile_fail
+/// # use std::marker::PhantomData;
+/// # use quicksilver::vec::VecReflection;
+/// # use quicksilver::reflections::{Mut, ValueReflection};
+/// # use quicksilver::reflections_ref::reflect_ref;
+/// let val: Vec<i32> = Vec::new();
+/// let ValueReflection::Vec(r) = reflect_ref(&val) else { panic!() };
+/// let mut v = VecReflection::<Mut> {
+///     element: r.element,
+///     ptr: r.ptr,
+///     vtable: r.vtable,
+///     skip: r.skip,
+///     _access: PhantomData,
+/// };
+/// v.push_default();
+/// ```
 #[repr(C)]
-pub struct VecReflection<'a> {
-    pub element: &'a Type,
-    pub ptr: *mut u8,
-    pub vtable: &'a VecVtable,
+pub struct VecReflection<'a, A: Access = Mut> {
+    pub(crate) element: &'a Type,
+    /// points to the Vec, only written through if `A` is [`Mut`]
+    pub(crate) ptr: *mut u8,
+    pub(crate) vtable: &'a VecVtable,
     pub skip: bool,
+    pub(crate) _access: PhantomData<A>,
 }
 
-impl VecReflection<'_> {
+impl<'a, A: Access> VecReflection<'a, A> {
+    /// The type of the elements.
+    pub fn element_ty(&self) -> &'a Type {
+        self.element
+    }
+
+    /// Points to the Vec, only written through if `A` is [`Mut`].
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.ptr
+    }
+
     pub fn len(&self) -> usize {
         unsafe { (self.vtable.get_len)(self.ptr) }
     }
 
-    pub fn get(&mut self, index: usize) -> ValueReflection<'_> {
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get_ref(&self, index: usize) -> ValueRef<'_> {
+        unsafe {
+            let ptr = (self.vtable.get_elem_ref)(self.ptr, index);
+            reflect_value_ref(ptr, self.element)
+        }
+    }
+}
+
+impl VecReflection<'_, Mut> {
+    pub fn get(&mut self, index: usize) -> ValueMut<'_> {
         unsafe {
             let ptr = (self.vtable.get_elem)(self.ptr, index);
-            reflect_value(ptr, &self.element)
+            reflect_value(ptr, self.element)
         }
     }
 
-    pub fn get_ref(&self, index: usize) -> ValueReflection<'_> {
+    /// Appends an element.
+    ///
+    /// # Safety
+    /// `element` must be created with `Box::into_raw` and be of the element type.
+    /// The Vec takes ownership of it.
+    pub unsafe fn push(&mut self, element: *mut u8) {
+        unsafe { (self.vtable.push)(self.ptr, element) }
+    }
+
+    /// Appends a default constructed element, see [`construct_default`](crate::default::construct_default).
+    pub fn push_default(&mut self) {
+        unsafe { self.push(box_default(self.element)) }
+    }
+
+    /// Inserts an element at `index`, shifting all elements after it to the right.
+    ///
+    /// # Safety
+    /// `element` must be created with `Box::into_raw` and be of the element type.
+    /// The Vec takes ownership of it.
+    pub unsafe fn insert(&mut self, index: usize, element: *mut u8) {
+        unsafe { (self.vtable.insert)(self.ptr, index, element) }
+    }
+
+    /// Inserts a default constructed element at `index`.
+    pub fn insert_default(&mut self, index: usize) {
+        unsafe { self.insert(index, box_default(self.element)) }
+    }
+
+    /// Removes and drops the element at `index`, shifting all elements after it to the left.
+    pub fn remove(&mut self, index: usize) {
+        unsafe { (self.vtable.remove)(self.ptr, index) }
+    }
+
+    pub fn swap(&mut self, a: usize, b: usize) {
+        unsafe { (self.vtable.swap)(self.ptr, a, b) }
+    }
+
+    /// Keeps the first `len` elements and drops the rest.
+    pub fn truncate(&mut self, len: usize) {
+        unsafe { (self.vtable.truncate)(self.ptr, len) }
+    }
+
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Reserves capacity for at least `additional` more elements.
+    pub fn reserve(&mut self, additional: usize) {
         unsafe {
-            let ptr = (self.vtable.get_elem_ref)(self.ptr, index);
-            reflect_value_ref(ptr, &self.element)
+            (self.vtable.reserve)(self.ptr, additional);
         }
     }
 }
@@ -136,6 +293,12 @@ impl<T> EmptyVecVtableCreator<T> {
         get_len: empty_get_len,
         get_elem: empty_get_elem,
         get_elem_ref: empty_get_elem_ref,
+        push: empty_push,
+        insert: empty_insert,
+        remove: empty_remove,
+        swap: empty_swap,
+        truncate: empty_truncate,
+        drop: Self::drop,
     };
 
     unsafe fn new_at(ptr: *mut u8, capacity: usize) -> *mut u8 {
@@ -147,6 +310,10 @@ impl<T> EmptyVecVtableCreator<T> {
             out as *mut u8
         }
     }
+
+    unsafe fn drop(ptr: *mut u8) {
+        unsafe { std::ptr::drop_in_place(ptr as *mut Vec<T>) }
+    }
 }
 
 unsafe fn empty_set_len(_ptr: *mut u8, _len: usize) {
@@ -168,3 +335,23 @@ unsafe fn empty_get_elem(_ptr: *mut u8, _index: usize) -> *mut u8 {
 unsafe fn empty_get_elem_ref(_ptr: *const u8, _index: usize) -> *const u8 {
     panic!("Not supported on skipped fields");
 }
+
+unsafe fn empty_push(_ptr: *mut u8, _element: *mut u8) {
+    panic!("Not supported on skipped fields");
+}
+
+unsafe fn empty_insert(_ptr: *mut u8, _index: usize, _element: *mut u8) {
+    panic!("Not supported on skipped fields");
+}
+
+unsafe fn empty_remove(_ptr: *mut u8, _index: usize) {
+    panic!("Not supported on skipped fields");
+}
+
+unsafe fn empty_swap(_ptr: *mut u8, _a: usize, _b: usize) {
+    panic!("Not supported on skipped fields");
+}
+
+unsafe fn empty_truncate(_ptr: *mut u8, _len: usize) {
+    panic!("Not supported on skipped fields");
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use quicksilver::Quicksilver;
+use quicksilver::apply::{CollectionPolicy, apply};
+use quicksilver::dynamic::{DynamicStruct, DynamicValue};
+use quicksilver::reflections::reflect;
+
+#[derive(Debug, PartialEq, Quicksilver)]
+struct Window {
+    width: u32,
+    height: u32,
+    title: String,
+}
+
+#[allow(unused)]
+#[derive(Debug, PartialEq, Quicksilver)]
+enum Backend {
+    Software,
+    Gpu { adapter: String, vsync: bool },
+}
+
+#[derive(Debug, PartialEq, Quicksilver)]
+struct Config {
+    window: Window,
+    backend: Backend,
+    plugins: Vec<String>,
+    keys: HashMap<String, Window>,
+    log_file: Option<Window>,
+}
+
+fn defaults() -> Config {
+    Config {
+        window: Window {
+            width: 800,
+            height: 600,
+            title: "Game".to_string(),
+        },
+        backend: Backend::Software,
+        plugins: vec!["core".to_string()],
+        keys: HashMap::from([(
+            "main".to_string(),
+            Window {
+                width: 1,
+                height: 2,
+                title: "main".to_string(),
+            },
+        )]),
+        log_file: None,
+    }
+}
+
+#[test]
+fn apply_layers() {
+    let mut config = defaults();
+
+    let file = r#"{"window":{"width":1024},"backend":{"__enum_variant":"Gpu","vsync":true},"plugins":["physics"],"keys":[{"key":"main","value":{"height":3}},{"key":"debug","value":{"title":"debug"}}],"log_file":[{"title":"log"}]}"#;
+    let patch = DynamicValue::from_json(file, &Config::MIRROR);
+    apply(reflect(&mut config), &patch, CollectionPolicy::Merge).unwrap();
+
+    let cli = DynamicValue::Struct(DynamicStruct {
+        fields: vec![(
+            "window".to_string(),
+            DynamicValue::Struct(DynamicStruct {
+                fields: vec![("title".to_string(), DynamicValue::String("Debug".into()))],
+            }),
+        )],
+    });
+    apply(reflect(&mut config), &cli, CollectionPolicy::Merge).unwrap();
+
+    assert_eq!(
+        config.window,
+        Window {
+            width: 1024,
+            height: 600,
+            title: "Debug".to_string(),
+        }
+    );
+    assert_eq!(
+        config.backend,
+        Backend::Gpu {
+            adapter: String::new(),
+            vsync: true
+        }
+    );
+    assert_eq!(config.plugins, vec!["core", "physics"]);
+    assert_eq!(
+        config.keys["main"],
+        Window {
+            width: 1,
+            height: 3,
+            title: "main".to_string(),
+        }
+    );
+    assert_eq!(config.keys["debug"].title, "debug");
+    assert_eq!(config.log_file.unwrap().title, "log");
+}
+
+#[test]
+fn apply_replace() {
+    let mut config = defaults();
+    let patch = DynamicValue::from_json(r#"{"plugins":["physics"],"keys":[]}"#, &Config::MIRROR);
+    apply(reflect(&mut config), &patch, CollectionPolicy::Replace).unwrap();
+    assert_eq!(config.plugins, vec!["physics"]);
+    assert!(config.keys.is_empty());
+    assert_eq!(config.window, defaults().window);
+}
+
+#[test]
+fn apply_mismatch() {
+    let mut config = defaults();
+    let patch = DynamicValue::Struct(DynamicStruct {
+        fields: vec![
+            ("plugins".to_string(), DynamicValue::List(Vec::new())),
+            (
+                "window".to_string(),
+                DynamicValue::Struct(DynamicStruct {
+                    fields: vec![("width".to_string(), DynamicValue::I32(3))],
+                }),
+            ),
+        ],
+    });
+    let err = apply(reflect(&mut config), &patch, CollectionPolicy::Replace).unwrap_err();
+    assert_eq!(err.to_string(), "expected u32, found i32 at .window.width");
+    // nothing was applied
+    assert_eq!(config, defaults());
+
+    let err = apply(
+        reflect(&mut config),
+        &DynamicValue::Bool(true),
+        CollectionPolicy::Replace,
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "can't apply bool to Config");
+}
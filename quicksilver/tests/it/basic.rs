@@ -44,43 +44,43 @@ fn test_reflection_my_data() {
         panic!()
     };
 
-    assert_eq!(reflected_data.fields.len(), 5);
+    assert_eq!(reflected_data.fields().len(), 5);
 
-    let id_field = &mut reflected_data.fields[0];
-    assert_eq!(id_field.name, "id");
-    if let ValueReflection::U32(id_ref) = &mut id_field.value {
+    let (id_name, mut id_value) = reflected_data.fields_mut().next().unwrap();
+    assert_eq!(id_name, "id");
+    if let ValueReflection::U32(id_ref) = &mut id_value {
         assert_eq!(**id_ref, 123);
         **id_ref = 456; // Modify through reflection
     } else {
         panic!("Expected U32 for 'id' field");
     }
 
-    let name_field = &mut reflected_data.fields[1];
-    assert_eq!(name_field.name, "name");
-    if let ValueReflection::String(name_ref) = &mut name_field.value {
+    let (name_name, mut name_value) = reflected_data.fields_mut().nth(1).unwrap();
+    assert_eq!(name_name, "name");
+    if let ValueReflection::String(name_ref) = &mut name_value {
         assert_eq!(**name_ref, "Test Data");
         **name_ref = "Modified Name".to_string(); // Modify through reflection
     } else {
         panic!("Expected String for 'name' field");
     }
 
-    let value_field = &mut reflected_data.fields[2];
-    assert_eq!(value_field.name, "value");
-    if let ValueReflection::F32(value_ref) = &mut value_field.value {
+    let (value_name, mut value_value) = reflected_data.fields_mut().nth(2).unwrap();
+    assert_eq!(value_name, "value");
+    if let ValueReflection::F32(value_ref) = &mut value_value {
         assert_eq!(**value_ref, 42.5);
         **value_ref = 99.9; // Modify through reflection
     } else {
         panic!("Expected F32 for 'value' field");
     }
 
-    let location_field = &mut reflected_data.fields[3];
-    assert_eq!(location_field.name, "location");
-    if let ValueReflection::Struct(point_reflection) = &mut location_field.value {
-        assert_eq!(point_reflection.fields.len(), 2);
+    let (location_name, mut location_value) = reflected_data.fields_mut().nth(3).unwrap();
+    assert_eq!(location_name, "location");
+    if let ValueReflection::Struct(point_reflection) = &mut location_value {
+        assert_eq!(point_reflection.fields().len(), 2);
 
-        let x_field = &mut point_reflection.fields[0];
-        assert_eq!(x_field.name, "x");
-        if let ValueReflection::I32(x_ref) = &mut x_field.value {
+        let (x_name, mut x_value) = point_reflection.fields_mut().next().unwrap();
+        assert_eq!(x_name, "x");
+        if let ValueReflection::I32(x_ref) = &mut x_value {
             assert_eq!(**x_ref, 10);
             **x_ref = 100; // Modify through reflection
         } else {
@@ -88,9 +88,9 @@ fn test_reflection_my_data() {
         }
 
         // Test 'location.y'
-        let y_field = &mut point_reflection.fields[1];
-        assert_eq!(y_field.name, "y");
-        if let ValueReflection::I32(y_ref) = &mut y_field.value {
+        let (y_name, mut y_value) = point_reflection.fields_mut().nth(1).unwrap();
+        assert_eq!(y_name, "y");
+        if let ValueReflection::I32(y_ref) = &mut y_value {
             assert_eq!(**y_ref, 20);
             **y_ref = 200; // Modify through reflection
         } else {
@@ -100,9 +100,9 @@ fn test_reflection_my_data() {
         panic!("Expected Struct for 'location' field");
     }
 
-    let is_active_field = &mut reflected_data.fields[4];
-    assert_eq!(is_active_field.name, "is_active");
-    if let ValueReflection::I32(is_active_ref) = &mut is_active_field.value {
+    let (is_active_name, mut is_active_value) = reflected_data.fields_mut().nth(4).unwrap();
+    assert_eq!(is_active_name, "is_active");
+    if let ValueReflection::I32(is_active_ref) = &mut is_active_value {
         assert_eq!(**is_active_ref, 1);
         **is_active_ref = 0; // Modify through reflection
     } else {
@@ -129,3 +129,32 @@ struct Point3d {
 fn test_derive() {
     let _mirror = Point3d::MIRROR;
 }
+
+#[test]
+fn test_reflection_to_ref() {
+    let mut point = Point { x: 1, y: 2 };
+    let mut reflected = reflect(&mut point);
+    {
+        let ValueReflection::Struct(s) = reflected.to_ref() else {
+            panic!()
+        };
+        let ValueReflection::I32(x) = s.fields()[0].value() else {
+            panic!()
+        };
+        assert_eq!(**x, 1);
+    }
+    if let ValueReflection::Struct(s) = &mut reflected
+        && let Some((_, ValueReflection::I32(y))) = s.fields_mut().nth(1)
+    {
+        *y = 3;
+    }
+    assert_eq!(reflected.to_json(), r#"{"x":1,"y":3}"#);
+
+    let ValueReflection::Struct(s) = reflected.into_ref() else {
+        panic!()
+    };
+    let ValueReflection::I32(y) = s.fields()[1].value() else {
+        panic!()
+    };
+    assert_eq!(**y, 3);
+}
@@ -0,0 +1,185 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use quicksilver::Quicksilver;
+use quicksilver::compare::{ByReflection, StableHasher, reflect_cmp, reflect_hash};
+use quicksilver::reflections_ref::reflect_ref;
+
+#[derive(Debug, Quicksilver)]
+struct Pos {
+    x: i32,
+    y: i32,
+}
+
+#[allow(unused)]
+#[derive(Debug, Quicksilver)]
+enum Shape {
+    Dot,
+    Circle { radius: f32 },
+    Poly(Vec<Pos>),
+}
+
+#[derive(Debug, Quicksilver)]
+struct Entity {
+    name: String,
+    shape: Shape,
+    tags: HashSet<String>,
+    stats: HashMap<String, i64>,
+    parent: Option<Box<Pos>>,
+}
+
+#[allow(unused)]
+struct Opaque(u32);
+
+#[derive(Quicksilver)]
+struct Cached {
+    id: u32,
+    #[quicksilver(skip)]
+    last: Option<Opaque>,
+    #[quicksilver(skip)]
+    history: Vec<Opaque>,
+    #[quicksilver(skip)]
+    by_name: HashMap<String, Opaque>,
+    #[quicksilver(skip)]
+    seen: HashSet<String>,
+}
+
+fn entity() -> Entity {
+    Entity {
+        name: "frog".to_string(),
+        shape: Shape::Poly(vec![Pos { x: 1, y: 2 }, Pos { x: 3, y: 4 }]),
+        tags: ["green", "wet", "jumpy", "small"].map(String::from).into(),
+        stats: (0..20).map(|i| (format!("stat{i}"), i)).collect(),
+        parent: Some(Box::new(Pos { x: 0, y: 0 })),
+    }
+}
+
+#[test]
+fn equal_values() {
+    // HashSets and HashMaps built separately iterate in different orders
+    let (a, b) = (entity(), entity());
+    assert_eq!(reflect_cmp(&a, &b), Ordering::Equal);
+    assert_eq!(reflect_hash(&a), reflect_hash(&b));
+}
+
+#[test]
+fn structural_order() {
+    let a = entity();
+    let mut b = entity();
+    b.stats.insert("stat3".to_string(), 100);
+    assert_eq!(reflect_cmp(&a, &b), Ordering::Less);
+    assert_eq!(reflect_cmp(&b, &a), Ordering::Greater);
+    assert_ne!(reflect_hash(&a), reflect_hash(&b));
+
+    b = entity();
+    b.parent = None;
+    assert_eq!(reflect_cmp(&a, &b), Ordering::Greater);
+
+    // variants are ordered by declaration
+    assert_eq!(
+        reflect_cmp(&Shape::Dot, &Shape::Circle { radius: -1. }),
+        Ordering::Less
+    );
+    assert_eq!(reflect_cmp(&vec![1, 2], &vec![1, 2, 0]), Ordering::Less);
+}
+
+#[test]
+fn skipped_fields_are_ignored() {
+    let empty = Cached {
+        id: 1,
+        last: None,
+        history: Vec::new(),
+        by_name: HashMap::new(),
+        seen: HashSet::new(),
+    };
+    let full = Cached {
+        id: 1,
+        last: Some(Opaque(1)),
+        history: vec![Opaque(2)],
+        by_name: HashMap::from([("a".to_string(), Opaque(3))]),
+        seen: HashSet::from(["b".to_string()]),
+    };
+    assert_eq!(reflect_cmp(&empty, &full), Ordering::Equal);
+    assert_eq!(reflect_hash(&empty), reflect_hash(&full));
+    assert_eq!(
+        reflect_cmp(&full, &Cached { id: 2, ..empty }),
+        Ordering::Less
+    );
+}
+
+#[test]
+fn boxes_are_looked_through() {
+    let values = (
+        Box::new(Pos { x: 0, y: 5 }),
+        Pos { x: 0, y: 5 },
+        Pos { x: 1, y: 2 },
+    );
+    let boxed = ByReflection(reflect_ref(&values.0));
+    let unboxed = ByReflection(reflect_ref(&values.1));
+    let bigger = ByReflection(reflect_ref(&values.2));
+    assert_eq!(boxed.cmp(&unboxed), Ordering::Equal);
+    assert_eq!(boxed.cmp(&bigger), Ordering::Less);
+    assert_eq!(bigger.cmp(&boxed), Ordering::Greater);
+
+    let hash = |val: &ByReflection| {
+        let mut hasher = StableHasher::default();
+        val.hash(&mut hasher);
+        hasher.finish()
+    };
+    assert_eq!(hash(&boxed), hash(&unboxed));
+    assert_eq!(reflect_hash(&Box::new(Box::new(7u32))), reflect_hash(&7u32));
+    assert_eq!(
+        reflect_cmp(&Some(Box::new(2)), &Some(Box::new(3))),
+        Ordering::Less
+    );
+}
+
+#[test]
+fn floats() {
+    let nan = Shape::Circle { radius: f32::NAN };
+    assert_eq!(reflect_cmp(&nan, &nan), Ordering::Equal);
+    assert_eq!(reflect_hash(&nan), reflect_hash(&nan));
+    assert_eq!(reflect_cmp(&-0.0f64, &0.0), Ordering::Less);
+    assert_ne!(reflect_hash(&-0.0f64), reflect_hash(&0.0f64));
+    assert_eq!(reflect_cmp(&1.5f64, &f64::INFINITY), Ordering::Less);
+}
+
+#[test]
+fn stable_hash() {
+    // must not change between runs, platforms or compiler versions
+    assert_eq!(reflect_hash(&Pos { x: 1, y: 2 }), 0x6a6ab2b2b276e615);
+}
+
+#[test]
+fn dedupe_heterogeneous() {
+    let values = (
+        Pos { x: 1, y: 2 },
+        Pos { x: 1, y: 2 },
+        Shape::Dot,
+        "dot".to_string(),
+        Pos { x: 0, y: 5 },
+    );
+    let reflected = [
+        reflect_ref(&values.0),
+        reflect_ref(&values.1),
+        reflect_ref(&values.2),
+        reflect_ref(&values.3),
+        reflect_ref(&values.4),
+    ];
+
+    let set: HashSet<ByReflection> = reflected
+        .iter()
+        .map(|it| ByReflection(it.to_ref()))
+        .collect();
+    assert_eq!(set.len(), 4);
+
+    let sorted: Vec<_> = reflected
+        .iter()
+        .map(|it| ByReflection(it.to_ref()))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|it| it.0.downcast_ref::<Pos>().map(|p| (p.x, p.y)))
+        .collect();
+    assert_eq!(sorted, [None, Some((0, 5)), Some((1, 2)), None]);
+}
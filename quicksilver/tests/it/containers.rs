@@ -65,6 +65,7 @@ fn hm_roundtrip() {
 }
 
 #[derive(Debug, PartialEq, Quicksilver)]
+#[allow(clippy::box_collection)]
 struct HMHolder2 {
     name: String,
     #[quicksilver(skip)]
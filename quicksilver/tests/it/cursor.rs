@@ -0,0 +1,120 @@
+use std::collections::{HashMap, HashSet};
+
+use quicksilver::Quicksilver;
+use quicksilver::cursor::{CursorValue, cursor};
+use quicksilver::json::{from_json, to_json};
+use quicksilver::reflections_ref::reflect_ref;
+
+#[derive(Debug, Quicksilver, PartialEq)]
+struct World {
+    name: String,
+    entities: Vec<Entity>,
+    lookup: HashMap<u32, String>,
+    flags: HashSet<i64>,
+    #[quicksilver(skip)]
+    cache: HashMap<u8, u8>,
+    boss: Option<Box<Entity>>,
+}
+
+#[derive(Debug, Quicksilver, PartialEq)]
+struct Entity {
+    id: u32,
+    shape: Shape,
+}
+
+#[allow(unused)]
+#[derive(Debug, Quicksilver, PartialEq)]
+enum Shape {
+    Dot,
+    Line(f32, f32),
+    Rect { w: f64, h: f64, label: String },
+}
+
+fn world() -> World {
+    World {
+        name: "quote \" and \\ backslash".to_string(),
+        entities: vec![
+            Entity {
+                id: 1,
+                shape: Shape::Dot,
+            },
+            Entity {
+                id: 2,
+                shape: Shape::Line(0.5, -1.5),
+            },
+        ],
+        lookup: HashMap::from([(1, "one".to_string())]),
+        flags: HashSet::from([-4]),
+        cache: HashMap::from([(1, 2)]),
+        boss: Some(Box::new(Entity {
+            id: 3,
+            shape: Shape::Rect {
+                w: 2.,
+                h: 3.,
+                label: "big".to_string(),
+            },
+        })),
+    }
+}
+
+#[test]
+fn walk_fields() {
+    let world = world();
+    let CursorValue::Struct(s) = cursor(&world).get() else {
+        panic!()
+    };
+    assert_eq!(s.name(), "World");
+    let names: Vec<_> = s.fields().map(|(name, _)| name).collect();
+    assert_eq!(
+        names,
+        ["name", "entities", "lookup", "flags", "cache", "boss"]
+    );
+
+    let CursorValue::Vec(entities) = s.field_by_name("entities").unwrap().get() else {
+        panic!()
+    };
+    assert_eq!(entities.len(), 2);
+    let CursorValue::Struct(entity) = entities.get(1).get() else {
+        panic!()
+    };
+    let CursorValue::RustEnum(shape) = entity.field(1).get() else {
+        panic!()
+    };
+    assert_eq!(shape.variant_name(), "Line");
+    assert!(matches!(shape.field(1).get(), CursorValue::F32(-1.5)));
+
+    // skipped containers appear empty
+    let CursorValue::HashMap(cache) = s.field(4).get() else {
+        panic!()
+    };
+    assert!(cache.is_empty());
+
+    // boxes are looked through
+    let CursorValue::Option(boss) = s.field(5).get() else {
+        panic!()
+    };
+    let CursorValue::Struct(boss) = boss.get().unwrap().get() else {
+        panic!()
+    };
+    let CursorValue::RustEnum(shape) = boss.field(1).get() else {
+        panic!()
+    };
+    let fields: Vec<_> = shape.fields().map(|(name, _)| name).collect();
+    assert_eq!(fields, ["w", "h", "label"]);
+    assert!(matches!(shape.field(2).get(), CursorValue::String(s) if s == "big"));
+}
+
+#[test]
+fn json_matches_reflection() {
+    let world = world();
+    let json = to_json(&world);
+    assert_eq!(json, reflect_ref(&world).to_json());
+    let CursorValue::Struct(s) = reflect_ref(&world).cursor() else {
+        panic!()
+    };
+    assert_eq!(s.name(), "World");
+
+    let mut expected = world;
+    expected.cache.clear();
+    assert_eq!(from_json::<World>(&json), expected);
+}
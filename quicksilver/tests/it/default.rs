@@ -0,0 +1,114 @@
+use std::collections::{HashMap, HashSet};
+use std::mem::MaybeUninit;
+
+use quicksilver::Quicksilver;
+use quicksilver::default::{construct_default, default_of};
+
+#[derive(Debug, PartialEq, Quicksilver)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[repr(C)]
+#[derive(Debug, PartialEq, Quicksilver)]
+#[allow(unused)]
+enum Tile {
+    Wall = 3,
+    Floor,
+}
+
+#[derive(Debug, PartialEq, Quicksilver)]
+#[allow(unused)]
+enum Ability {
+    Attack { who: String, damage: i32 },
+    Shout(String),
+}
+
+#[derive(Debug, PartialEq, Quicksilver)]
+struct Everything {
+    name: String,
+    alive: bool,
+    speed: f64,
+    pos: Point,
+    tile: Tile,
+    ability: Ability,
+    boxed: Box<Point>,
+    list: Vec<Point>,
+    map: HashMap<String, Point>,
+    set: HashSet<String>,
+    maybe: Option<Point>,
+    #[quicksilver(skip)]
+    skipped: Vec<Point>,
+}
+
+#[test]
+fn structural_default() {
+    let val = default_of::<Everything>();
+    let expected = Everything {
+        name: String::new(),
+        alive: false,
+        speed: 0.,
+        pos: Point { x: 0, y: 0 },
+        tile: Tile::Wall,
+        ability: Ability::Attack {
+            who: String::new(),
+            damage: 0,
+        },
+        boxed: Box::new(Point { x: 0, y: 0 }),
+        list: Vec::new(),
+        map: HashMap::new(),
+        set: HashSet::new(),
+        maybe: None,
+        skipped: Vec::new(),
+    };
+    assert_eq!(val, expected);
+}
+
+#[derive(Debug, PartialEq, Quicksilver)]
+#[quicksilver(default)]
+struct Settings {
+    volume: f32,
+    name: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            volume: 0.5,
+            name: "default".into(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Quicksilver, Default)]
+#[quicksilver(default)]
+#[allow(unused)]
+enum Mood {
+    Happy,
+    #[default]
+    Grumpy,
+}
+
+#[derive(Debug, PartialEq, Quicksilver)]
+struct Holder {
+    settings: Settings,
+    mood: Mood,
+}
+
+#[test]
+fn default_impl_is_used() {
+    let val = default_of::<Holder>();
+    assert_eq!(val.settings, Settings::default());
+    assert_eq!(val.mood, Mood::Grumpy);
+}
+
+#[test]
+fn construct_default_raw() {
+    let mut val: MaybeUninit<Vec<Point>> = MaybeUninit::uninit();
+    let val = unsafe {
+        construct_default(&Vec::<Point>::MIRROR, val.as_mut_ptr() as *mut u8);
+        val.assume_init()
+    };
+    assert!(val.is_empty());
+}
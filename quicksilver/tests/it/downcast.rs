@@ -0,0 +1,101 @@
+use quicksilver::Quicksilver;
+use quicksilver::reflections::{ValueReflection, reflect};
+use quicksilver::reflections_ref::reflect_ref;
+
+#[derive(Debug, PartialEq, Quicksilver)]
+struct Pos {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Debug, PartialEq, Quicksilver)]
+struct OtherPos {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Debug, PartialEq, Quicksilver)]
+struct House {
+    pos: Pos,
+    rooms: Vec<Pos>,
+    floors: Box<u32>,
+}
+
+fn house() -> House {
+    House {
+        pos: Pos { x: 1, y: 2 },
+        rooms: vec![Pos { x: 3, y: 4 }],
+        floors: Box::new(2),
+    }
+}
+
+#[test]
+fn downcast_ref() {
+    let house = house();
+    let reflected = reflect_ref(&house);
+    assert_eq!(reflected.downcast_ref::<House>(), Some(&house));
+    assert!(reflected.downcast_ref::<Pos>().is_none());
+
+    let ValueReflection::Struct(s) = reflected else {
+        panic!()
+    };
+    assert_eq!(s.downcast_ref::<House>(), Some(&house));
+    let pos = s.fields()[0].value();
+    assert!(pos.is::<Pos>());
+    assert!(!pos.is::<OtherPos>());
+    assert_eq!(pos.downcast_ref::<Pos>(), Some(&Pos { x: 1, y: 2 }));
+    assert!(pos.downcast_ref::<OtherPos>().is_none());
+
+    let rooms = s.fields()[1].value();
+    assert_eq!(rooms.downcast_ref::<Vec<Pos>>(), Some(&house.rooms));
+    assert!(rooms.downcast_ref::<Vec<OtherPos>>().is_none());
+
+    let floors = s.fields()[2].value();
+    assert_eq!(floors.downcast_ref::<Box<u32>>(), Some(&house.floors));
+    assert!(floors.downcast_ref::<u32>().is_none());
+}
+
+#[test]
+fn downcast_mut() {
+    let mut house = house();
+    let ValueReflection::Struct(s) = reflect(&mut house) else {
+        panic!()
+    };
+    let mut fields = s.into_fields().into_iter();
+    let pos = fields.next().unwrap().into_value();
+    let Err(pos) = pos.downcast_mut::<OtherPos>() else {
+        panic!()
+    };
+    let Ok(pos) = pos.downcast_mut::<Pos>() else {
+        panic!()
+    };
+    pos.x = 10;
+    let Ok(floors) = fields
+        .nth(1)
+        .unwrap()
+        .into_value()
+        .downcast_mut::<Box<u32>>()
+    else {
+        panic!()
+    };
+    **floors += 1;
+    assert_eq!(house.pos, Pos { x: 10, y: 2 });
+    assert_eq!(*house.floors, 3);
+
+    let ValueReflection::Struct(s) = reflect(&mut house) else {
+        panic!()
+    };
+    let Ok(reflected_house) = s.downcast_mut::<House>() else {
+        panic!()
+    };
+    reflected_house.rooms.clear();
+    assert!(house.rooms.is_empty());
+}
+
+#[test]
+fn same_type() {
+    assert!(Vec::<Pos>::MIRROR.same_as(&Vec::<Pos>::MIRROR));
+    assert!(!Vec::<Pos>::MIRROR.same_as(&Vec::<OtherPos>::MIRROR));
+    assert!(!Pos::MIRROR.same_as(&OtherPos::MIRROR));
+    assert!(!i32::MIRROR.same_as(&u32::MIRROR));
+}
@@ -0,0 +1,121 @@
+use std::collections::{HashMap, HashSet};
+
+use quicksilver::Quicksilver;
+use quicksilver::dynamic::{DynamicEnum, DynamicStruct, DynamicValue};
+
+#[derive(Debug, PartialEq, Eq, Hash, Quicksilver)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[allow(unused)]
+#[derive(Debug, PartialEq, Quicksilver)]
+enum Shape {
+    Nothing,
+    Circle { center: Point, radius: Box<u32> },
+    Polygon(Vec<Point>),
+}
+
+#[repr(C)]
+#[allow(unused)]
+#[derive(Debug, PartialEq, Quicksilver)]
+enum Color {
+    Red = 1,
+    Green = 2,
+}
+
+#[derive(Debug, PartialEq, Quicksilver)]
+struct Entity {
+    name: String,
+    shape: Shape,
+    color: Color,
+    tags: HashSet<String>,
+    labels: HashMap<Point, String>,
+    parent: Option<u64>,
+}
+
+fn entity() -> Entity {
+    Entity {
+        name: "Frog".to_string(),
+        shape: Shape::Circle {
+            center: Point { x: 1, y: 2 },
+            radius: Box::new(3),
+        },
+        color: Color::Green,
+        tags: HashSet::from(["green".to_string(), "wet".to_string()]),
+        labels: HashMap::from([(Point { x: 0, y: 0 }, "origin".to_string())]),
+        parent: Some(7),
+    }
+}
+
+#[test]
+fn dynamic_roundtrip() {
+    let val = entity();
+    let dynamic = DynamicValue::from_value(&val);
+    assert_eq!(dynamic.validate(&Entity::MIRROR), Ok(()));
+    assert_eq!(dynamic.to_value::<Entity>(), Ok(val));
+}
+
+#[test]
+fn dynamic_edit() {
+    let DynamicValue::Struct(mut s) = DynamicValue::from_value(&entity()) else {
+        panic!()
+    };
+    *s.field_mut("name").unwrap() = DynamicValue::String("Toad".to_string());
+    *s.field_mut("color").unwrap() = DynamicValue::Enum(DynamicEnum {
+        variant: "Red".to_string(),
+        fields: Vec::new(),
+    });
+    *s.field_mut("shape").unwrap() = DynamicValue::Enum(DynamicEnum {
+        variant: "Polygon".to_string(),
+        fields: vec![(
+            "0".to_string(),
+            DynamicValue::List(vec![DynamicValue::Struct(DynamicStruct {
+                fields: vec![
+                    ("y".to_string(), DynamicValue::I32(5)),
+                    ("x".to_string(), DynamicValue::I32(4)),
+                ],
+            })]),
+        )],
+    });
+    *s.field_mut("parent").unwrap() = DynamicValue::Option(None);
+
+    let val: Entity = DynamicValue::Struct(s).to_value().unwrap();
+    assert_eq!(val.name, "Toad");
+    assert_eq!(val.color, Color::Red);
+    assert_eq!(val.shape, Shape::Polygon(vec![Point { x: 4, y: 5 }]));
+    assert_eq!(val.parent, None);
+    assert_eq!(val.tags.len(), 2);
+}
+
+#[test]
+fn dynamic_validation() {
+    let DynamicValue::Struct(mut s) = DynamicValue::from_value(&entity()) else {
+        panic!()
+    };
+    let DynamicValue::Enum(shape) = s.field_mut("shape").unwrap() else {
+        panic!()
+    };
+    *shape.field_mut("radius").unwrap() = DynamicValue::I32(3);
+    let err = DynamicValue::Struct(s.clone())
+        .to_value::<Entity>()
+        .unwrap_err();
+    assert_eq!(err.path, ".shape.radius");
+    assert_eq!(err.to_string(), "expected u32, found i32 at .shape.radius");
+
+    s.fields.retain(|(name, _)| name != "name");
+    let err = DynamicValue::Struct(s)
+        .validate(&Entity::MIRROR)
+        .unwrap_err();
+    assert_eq!(err.path, ".name");
+    assert_eq!(err.message, "missing field");
+
+    let err = DynamicValue::Enum(DynamicEnum {
+        variant: "Blue".to_string(),
+        fields: Vec::new(),
+    })
+    .validate(&Color::MIRROR)
+    .unwrap_err();
+    assert_eq!(err.to_string(), "Color has no variant Blue");
+}
@@ -0,0 +1,84 @@
+use quicksilver::reflections::{ValueReflection, reflect};
+use quicksilver::reflections_ref::reflect_ref;
+use quicksilver::{Quicksilver, Type};
+
+#[derive(Debug, Quicksilver)]
+struct Player {
+    name: String,
+    hp: u32,
+    #[quicksilver(skip)]
+    cache: Vec<u8>,
+    action: Action,
+}
+
+#[derive(Debug, Quicksilver)]
+struct Pair(i32, i32);
+
+#[allow(unused)]
+#[derive(Debug, Quicksilver)]
+enum Action {
+    Idle,
+    Attack { target: String, damage: u32 },
+}
+
+#[test]
+fn struct_mirror_lookup() {
+    let Type::Struct(mirror) = Player::MIRROR else {
+        unreachable!()
+    };
+    assert_eq!((mirror.index_of)("name"), Some(0));
+    assert_eq!((mirror.index_of)("action"), Some(3));
+    assert_eq!((mirror.index_of)("nope"), None);
+    assert_eq!(mirror.field("hp").map(|f| f.name), Some("hp"));
+
+    let Type::Struct(mirror) = Pair::MIRROR else {
+        unreachable!()
+    };
+    assert_eq!((mirror.index_of)("1"), Some(1));
+}
+
+#[test]
+fn struct_field_by_name() {
+    let mut player = Player {
+        name: "frog".to_string(),
+        hp: 10,
+        cache: vec![1, 2],
+        action: Action::Attack {
+            target: "fly".to_string(),
+            damage: 3,
+        },
+    };
+
+    let ValueReflection::Struct(mut r) = reflect(&mut player) else {
+        panic!()
+    };
+    assert_eq!(r.field_index("hp"), Some(1));
+    let Some(ValueReflection::U32(hp)) = r.field_mut("hp") else {
+        panic!()
+    };
+    *hp = 20;
+    let Some(ValueReflection::RustEnum(mut action)) = r.field_mut("action") else {
+        panic!()
+    };
+    assert_eq!(action.field_index("damage"), Some(1));
+    assert_eq!(action.field_index("hp"), None);
+    let Some(ValueReflection::U32(damage)) = action.field_mut("damage") else {
+        panic!()
+    };
+    *damage = 5;
+    assert!(r.field_mut("missing").is_none());
+    drop(r);
+
+    assert_eq!(player.hp, 20);
+    let ValueReflection::Struct(r) = reflect_ref(&player) else {
+        panic!()
+    };
+    assert!(matches!(r.field("name"), Some(ValueReflection::String(s)) if *s == "frog"));
+    let Some(ValueReflection::RustEnum(action)) = r.field("action") else {
+        panic!()
+    };
+    assert!(matches!(
+        action.field("damage"),
+        Some(ValueReflection::U32(5))
+    ));
+}
@@ -0,0 +1,99 @@
+use std::cell::Cell;
+
+use quicksilver::Quicksilver;
+use quicksilver::json::from_json_into;
+use quicksilver::reflections_ref::reflect_ref;
+
+thread_local! {
+    static DROPPED: Cell<usize> = const { Cell::new(0) };
+}
+
+#[derive(Debug, PartialEq, Default, Quicksilver)]
+struct Tracked {
+    id: i32,
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        DROPPED.set(DROPPED.get() + 1);
+    }
+}
+
+#[allow(unused)]
+#[derive(Debug, PartialEq, Quicksilver)]
+enum Movement {
+    Walk { speed: f32 },
+    Fly { height: f32, wings: Vec<Tracked> },
+}
+
+#[derive(Debug, PartialEq, Quicksilver)]
+struct Tuning {
+    gravity: f32,
+    names: Vec<String>,
+    tracked: Vec<Tracked>,
+    movement: Movement,
+    boss: Option<Box<Tracked>>,
+    #[quicksilver(skip)]
+    cache: Vec<Tracked>,
+}
+
+#[test]
+fn in_place_roundtrip() {
+    let mut val = Tuning {
+        gravity: 9.81,
+        names: vec!["a".into(), "b".into()],
+        tracked: vec![Tracked { id: 1 }],
+        movement: Movement::Walk { speed: 2.5 },
+        boss: None,
+        cache: Vec::new(),
+    };
+    let json = reflect_ref(&val).to_json();
+    val.gravity = 0.;
+    val.names.clear();
+    from_json_into(&mut val, &json);
+    assert_eq!(val.gravity, 9.81);
+    assert_eq!(val.names, vec!["a", "b"]);
+}
+
+#[test]
+fn in_place_reload() {
+    let mut val = Tuning {
+        gravity: 9.81,
+        names: Vec::with_capacity(16),
+        tracked: vec![Tracked { id: 1 }, Tracked { id: 2 }, Tracked { id: 3 }],
+        movement: Movement::Fly {
+            height: 10.,
+            wings: vec![Tracked { id: 4 }, Tracked { id: 5 }],
+        },
+        boss: Some(Box::new(Tracked { id: 6 })),
+        cache: vec![Tracked { id: 7 }],
+    };
+    val.names.push("old".into());
+    let names_ptr = val.names.as_ptr();
+    let tracked_ptr = val.tracked.as_ptr();
+
+    DROPPED.set(0);
+    from_json_into(
+        &mut val,
+        r#"{"gravity":1.5,"names":["x","y"],"tracked":[{"id":10}],"movement":{"__enum_variant":"Walk","speed":3.5},"boss":[{"id":11}],"cache":[]}"#,
+    );
+    // two truncated from tracked, two wings
+    assert_eq!(DROPPED.get(), 4);
+
+    assert_eq!(val.gravity, 1.5);
+    assert_eq!(val.names, vec!["x", "y"]);
+    assert_eq!(val.names.as_ptr(), names_ptr);
+    assert_eq!(val.tracked.as_ptr(), tracked_ptr);
+    assert_eq!(val.movement, Movement::Walk { speed: 3.5 });
+    assert_eq!(val.boss.as_deref(), Some(&Tracked { id: 11 }));
+    assert_eq!(val.cache, vec![Tracked { id: 7 }]);
+
+    DROPPED.set(0);
+    from_json_into(
+        &mut val,
+        r#"{"gravity":1.5,"names":[],"tracked":[],"movement":{"__enum_variant":"Walk","speed":3.5},"boss":[],"cache":[]}"#,
+    );
+    assert_eq!(DROPPED.get(), 2);
+    assert_eq!(val.boss, None);
+    assert!(val.tracked.is_empty());
+}
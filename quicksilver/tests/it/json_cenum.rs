@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+use quicksilver::Quicksilver;
+use quicksilver::dynamic::{DynamicEnum, DynamicValue};
+use quicksilver::json::{
+    JsonOptions, to_json, to_json_with, try_from_json, try_from_json_into, try_from_json_with,
+};
+
+#[repr(C)]
+#[allow(unused)]
+#[derive(Debug, Quicksilver, PartialEq, Clone, Copy)]
+enum Terrain {
+    Grass,
+    Water,
+    Lava,
+}
+
+#[derive(Debug, Quicksilver, PartialEq)]
+struct Tile {
+    terrain: Terrain,
+    layers: Vec<Terrain>,
+    costs: HashMap<u32, Terrain>,
+}
+
+fn tile() -> Tile {
+    Tile {
+        terrain: Terrain::Water,
+        layers: vec![Terrain::Grass, Terrain::Lava],
+        costs: HashMap::from([(2, Terrain::Lava)]),
+    }
+}
+
+fn names() -> JsonOptions {
+    JsonOptions {
+        cenum_names: true,
+        ..JsonOptions::default()
+    }
+}
+
+#[test]
+fn written_by_name() {
+    let json = to_json_with(&tile(), &names());
+    assert_eq!(
+        json,
+        r#"{"terrain":"Water","layers":["Grass","Lava"],"costs":[{"key":2,"value":"Lava"}]}"#
+    );
+    assert_eq!(try_from_json_with(&json, names()), Ok(tile()));
+    // names can be read without the option
+    assert_eq!(try_from_json(&json), Ok(tile()));
+}
+
+#[test]
+fn numbers_by_default() {
+    let json = to_json(&tile());
+    assert_eq!(
+        json,
+        r#"{"terrain":1,"layers":[0,2],"costs":[{"key":2,"value":2}]}"#
+    );
+    assert_eq!(try_from_json_with(&json, names()), Ok(tile()));
+}
+
+#[test]
+fn names_and_numbers_mixed() {
+    let json = r#"{"terrain":"Lava","layers":[1,"Water"],"costs":[]}"#;
+    let tile: Tile = try_from_json(json).unwrap();
+    assert_eq!(tile.terrain, Terrain::Lava);
+    assert_eq!(tile.layers, [Terrain::Water, Terrain::Water]);
+
+    let mut existing = self::tile();
+    try_from_json_into(&mut existing, json).unwrap();
+    assert_eq!(existing, tile);
+
+    let dynamic = DynamicValue::try_from_json(r#""Grass""#, &Terrain::MIRROR).unwrap();
+    assert_eq!(
+        dynamic,
+        DynamicValue::Enum(DynamicEnum {
+            variant: "Grass".to_string(),
+            fields: Vec::new(),
+        })
+    );
+}
+
+#[test]
+fn unknown_variants() {
+    let err = try_from_json::<Tile>(r#"{"terrain":"Sand","layers":[],"costs":[]}"#).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"expected a variant of Terrain (Grass = 0, Water = 1, Lava = 2), found "Sand" at .terrain (line 1, column 12)"#
+    );
+
+    let err = try_from_json::<Vec<Terrain>>("[0, 3]").unwrap_err();
+    assert_eq!(
+        err.expected,
+        "a variant of Terrain (Grass = 0, Water = 1, Lava = 2)"
+    );
+    assert_eq!(err.found, "3");
+    assert_eq!((err.path.as_str(), err.column), ("[1]", 5));
+
+    // names are case sensitive
+    let err = try_from_json::<Terrain>(r#""grass""#).unwrap_err();
+    assert_eq!(err.found, r#""grass""#);
+}
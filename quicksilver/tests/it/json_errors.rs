@@ -0,0 +1,238 @@
+use std::collections::{HashMap, HashSet};
+
+use quicksilver::Quicksilver;
+use quicksilver::dynamic::DynamicValue;
+use quicksilver::json::{
+    JsonError, JsonOptions, from_json, to_json, try_from_json, try_from_json_into,
+};
+
+#[derive(Debug, Quicksilver, PartialEq)]
+struct World {
+    name: String,
+    entities: Vec<Entity>,
+    lookup: HashMap<u32, String>,
+    flags: HashSet<i64>,
+    boss: Option<Box<Entity>>,
+}
+
+#[derive(Debug, Quicksilver, PartialEq)]
+struct Entity {
+    id: u32,
+    shape: Shape,
+    color: Color,
+}
+
+#[allow(unused)]
+#[derive(Debug, Quicksilver, PartialEq)]
+enum Shape {
+    Dot,
+    Line(f32, f32),
+    Rect { w: f32, h: f32, label: String },
+}
+
+#[allow(unused)]
+#[derive(Debug, Quicksilver, PartialEq, Clone, Copy)]
+#[repr(C)]
+enum Color {
+    Red,
+    Green,
+}
+
+fn world() -> World {
+    World {
+        name: "frogs".to_string(),
+        entities: vec![
+            Entity {
+                id: 1,
+                shape: Shape::Dot,
+                color: Color::Red,
+            },
+            Entity {
+                id: 2,
+                shape: Shape::Rect {
+                    w: 0.5,
+                    h: 1.5,
+                    label: "wall".to_string(),
+                },
+                color: Color::Green,
+            },
+        ],
+        lookup: HashMap::from([(1, "one".to_string())]),
+        flags: HashSet::from([-4]),
+        boss: Some(Box::new(Entity {
+            id: 3,
+            shape: Shape::Line(2., 3.),
+            color: Color::Green,
+        })),
+    }
+}
+
+#[test]
+fn positioned_error() {
+    let json = r#"{"name":"frogs","entities":[{"id":1,"shape":{"__enum_variant":"Dot"},"color":0},{"id":x"#;
+    let err = try_from_json::<World>(json).unwrap_err();
+    assert_eq!(
+        err,
+        JsonError {
+            line: 1,
+            column: 87,
+            path: ".entities[1].id".to_string(),
+            expected: "u32".to_string(),
+            found: "'x'".to_string(),
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "expected u32, found 'x' at .entities[1].id (line 1, column 87)"
+    );
+}
+
+#[test]
+fn lines_are_counted() {
+    let json = "{\"name\":\"two lines\",\n\"entities\":[}";
+    let err = try_from_json::<World>(json).unwrap_err();
+    assert_eq!((err.line, err.column), (2, 13));
+    assert_eq!(err.path, ".entities[0]");
+    assert_eq!(err.expected, "'{'");
+    assert_eq!(err.found, "'}'");
+}
+
+#[test]
+fn missing_field() {
+    let err = try_from_json::<Entity>(r#"{"id":1,"colour":0}"#).unwrap_err();
+    assert_eq!(err.expected, "field \"shape\"");
+    assert_eq!(err.found, "'}'");
+    assert_eq!(err.column, 19);
+}
+
+#[derive(Debug, Quicksilver, PartialEq)]
+struct Sprite {
+    id: u32,
+    #[quicksilver(skip)]
+    frames: Vec<u32>,
+    #[quicksilver(skip)]
+    atlas: Option<String>,
+    effect: Effect,
+}
+
+#[allow(unused)]
+#[derive(Debug, Quicksilver, PartialEq)]
+enum Effect {
+    None,
+    Glow {
+        radius: f32,
+        #[quicksilver(skip)]
+        cache: HashMap<u32, u32>,
+    },
+}
+
+#[test]
+fn missing_skipped_fields() {
+    let sprite: Sprite =
+        try_from_json(r#"{"effect":{"__enum_variant":"Glow","radius":2},"id":4}"#).unwrap();
+    assert_eq!(
+        sprite,
+        Sprite {
+            id: 4,
+            frames: Vec::new(),
+            atlas: None,
+            effect: Effect::Glow {
+                radius: 2.,
+                cache: HashMap::new(),
+            },
+        }
+    );
+    // fields which aren't skipped are still required
+    let err = try_from_json::<Sprite>(r#"{"effect":{"__enum_variant":"None"}}"#).unwrap_err();
+    assert_eq!(err.expected, "field \"id\"");
+    let err =
+        try_from_json::<Sprite>(r#"{"id":4,"effect":{"__enum_variant":"Glow"}}"#).unwrap_err();
+    assert_eq!(err.expected, "field \"radius\"");
+    assert_eq!(err.path, ".effect");
+}
+
+#[test]
+fn truncated_input() {
+    let json = to_json(&world());
+    // every prefix is missing something, the values read so far have to be dropped again
+    for (end, _) in json.char_indices().skip(1) {
+        let err = try_from_json::<World>(&json[..end]).unwrap_err();
+        assert!(err.column <= end + 1, "{err}");
+    }
+    assert_eq!(try_from_json::<World>(&json), Ok(world()));
+}
+
+#[test]
+fn trailing_input() {
+    let err = try_from_json::<u32>("12]").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected end of input, found ']' (line 1, column 3)"
+    );
+}
+
+#[test]
+fn bad_enum_variants() {
+    let mut json = to_json(&world());
+    json = json.replacen("\"Dot\"", "\"Circle\"", 1);
+    let err = try_from_json::<World>(&json).unwrap_err();
+    assert_eq!(err.path, ".entities[0].shape");
+    assert_eq!(err.expected, "a variant of Shape");
+    assert_eq!(err.found, "\"Circle\"");
+
+    let err = try_from_json::<Entity>(r#"{"id":1,"shape":{"__enum_variant":"Dot"},"color":7}"#)
+        .unwrap_err();
+    assert_eq!(err.path, ".color");
+    assert_eq!(err.expected, "a variant of Color (Red = 0, Green = 1)");
+    assert_eq!(err.found, "7");
+}
+
+#[test]
+fn errors_inside_containers() {
+    let json = to_json(&world());
+    let cases = [
+        (
+            "\"label\":\"wall\"",
+            "\"label\":wall",
+            ".entities[1].shape.label",
+        ),
+        ("\"value\":\"one\"", "\"value\":1", ".lookup[0].value"),
+        ("[-4]", "[-4,true]", ".flags[1]"),
+        ("\"Line\",\"0\":2", "\"Line\",\"0\":true", ".boss.shape.0"),
+    ];
+    for (from, to, path) in cases {
+        assert!(json.contains(from), "{json}");
+        let err = try_from_json::<World>(&json.replacen(from, to, 1)).unwrap_err();
+        assert_eq!(err.path, path);
+    }
+}
+
+#[test]
+fn in_place_error() {
+    let mut world = world();
+    let err =
+        try_from_json_into(&mut world, r#"{"name":"toads","entities":[{"id":9,"#).unwrap_err();
+    assert_eq!(err.path, ".entities[0]");
+    // valid, but partially overwritten
+    assert_eq!(world.name, "toads");
+    assert_eq!(world.entities[0].id, 9);
+}
+
+#[test]
+fn dynamic_unknown_field() {
+    let options = JsonOptions {
+        deny_unknown_fields: true,
+        ..JsonOptions::default()
+    };
+    let err = DynamicValue::try_from_json_with(r#"{"id":1,"size":2}"#, &Entity::MIRROR, options)
+        .unwrap_err();
+    assert_eq!(err.expected, "a field of Entity");
+    assert_eq!(err.found, "\"size\"");
+    assert_eq!(err.column, 9);
+}
+
+#[test]
+#[should_panic(expected = "expected bool, found 'no' at [1] (line 1, column 7)")]
+fn from_json_panics() {
+    from_json::<HashSet<bool>>("[true,no]");
+}
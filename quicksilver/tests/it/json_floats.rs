@@ -0,0 +1,173 @@
+use quicksilver::Quicksilver;
+use quicksilver::dynamic::DynamicValue;
+use quicksilver::json::{
+    JsonOptions, NonFinite, from_json, to_json, to_json_with, to_json_writer_with, try_from_json,
+    try_from_json_with,
+};
+
+#[derive(Debug, Quicksilver, PartialEq)]
+struct Body {
+    mass: f64,
+    speed: f32,
+}
+
+fn options(non_finite: NonFinite) -> JsonOptions {
+    JsonOptions {
+        non_finite,
+        ..JsonOptions::default()
+    }
+}
+
+#[test]
+fn f64_fields() {
+    let body: Body = from_json(r#"{"mass":1.5,"speed":-0.25}"#);
+    assert_eq!(
+        body,
+        Body {
+            mass: 1.5,
+            speed: -0.25
+        }
+    );
+}
+
+#[test]
+fn edge_values_round_trip() {
+    let doubles = [
+        0.0,
+        -0.0,
+        0.1,
+        1e-7,
+        1e300,
+        -123456.789,
+        f64::EPSILON,
+        f64::MIN_POSITIVE,
+        5e-324,
+        f64::MAX,
+        f64::MIN,
+    ];
+    let floats = [
+        0.0,
+        -0.0,
+        0.1,
+        1e-7,
+        f32::EPSILON,
+        f32::MIN_POSITIVE,
+        1e-45,
+        f32::MAX,
+        f32::MIN,
+    ];
+    for mass in doubles {
+        let json = to_json(&mass);
+        assert_eq!(from_json::<f64>(&json).to_bits(), mass.to_bits(), "{json}");
+    }
+    for speed in floats {
+        let json = to_json(&speed);
+        assert_eq!(from_json::<f32>(&json).to_bits(), speed.to_bits(), "{json}");
+    }
+}
+
+#[test]
+fn exponents() {
+    let cases = [
+        ("1e-7", 1e-7),
+        ("1E7", 1e7),
+        ("-2.5e+2", -250.0),
+        ("0.5e0", 0.5),
+        ("4.9e-324", 5e-324),
+    ];
+    for (json, expected) in cases {
+        assert_eq!(from_json::<f64>(json), expected, "{json}");
+    }
+    let body: Body = from_json(r#"{"mass":6e24,"speed":3E-1}"#);
+    assert_eq!((body.mass, body.speed), (6e24, 0.3));
+}
+
+#[test]
+fn malformed_numbers() {
+    let err = try_from_json::<Body>(r#"{"mass":1e,"speed":0}"#).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected f64, found '1e' at .mass (line 1, column 9)"
+    );
+    let err = try_from_json::<Body>(r#"{"mass":1,"speed":1e99}"#).unwrap_err();
+    assert_eq!(err.expected, "a finite f32");
+    assert_eq!(err.found, "'1e99'");
+    assert_eq!(err.path, ".speed");
+}
+
+#[test]
+fn strict_number_grammar() {
+    for json in ["0", "-0", "0.5", "-0.0e-0", "10", "1E+2"] {
+        assert!(try_from_json::<f64>(json).is_ok(), "{json}");
+    }
+    let malformed = [
+        "01", "-01", "00", "-", "+1", ".5", "-.5", "1.", "1.e5", "1e", "1e+", "--1", "1.5.2",
+        "1e5e5",
+    ];
+    for json in malformed {
+        let err = try_from_json::<f64>(json).unwrap_err();
+        assert_eq!(err.expected, "f64", "{json}");
+        assert_eq!(err.found, format!("'{json}'"), "{json}");
+        assert_eq!(err.column, 1, "{json}");
+    }
+    // also when the value is skipped
+    let err = try_from_json::<Body>(r#"{"mass":1,"speed":2,"extra":[1,01]}"#).unwrap_err();
+    assert_eq!(err.expected, "a number");
+    assert_eq!(err.found, "'01'");
+    assert_eq!(err.column, 32);
+}
+
+#[test]
+fn non_finite_as_null() {
+    // the default, so the output is always valid json
+    let body = Body {
+        mass: f64::NAN,
+        speed: f32::INFINITY,
+    };
+    let json = to_json(&body);
+    assert_eq!(json, r#"{"mass":null,"speed":null}"#);
+    let read: Body = from_json(&json);
+    assert!(read.mass.is_nan() && read.speed.is_nan());
+}
+
+#[test]
+fn non_finite_as_string() {
+    let options = options(NonFinite::String);
+    let values = [f64::NAN, f64::INFINITY, f64::NEG_INFINITY, 1.5];
+    let json = to_json_with(&values.to_vec(), &options);
+    assert_eq!(json, r#"["NaN","inf","-inf",1.5]"#);
+    let read: Vec<f64> = try_from_json_with(&json, options.clone()).unwrap();
+    assert!(read[0].is_nan());
+    assert_eq!(read[1..], values[1..]);
+
+    let err = try_from_json_with::<f32>(r#""1.5""#, options.clone()).unwrap_err();
+    assert_eq!(err.expected, r#""NaN", "inf" or "-inf""#);
+    assert_eq!(err.found, r#""1.5""#);
+
+    let dynamic = DynamicValue::try_from_json_with(r#""-inf""#, &f32::MIRROR, options).unwrap();
+    assert_eq!(dynamic, DynamicValue::F32(f32::NEG_INFINITY));
+}
+
+#[test]
+fn non_finite_as_error() {
+    let options = options(NonFinite::Error);
+    let body = Body {
+        mass: 1.0,
+        speed: f32::NAN,
+    };
+    let err = to_json_writer_with(&body, Vec::new(), &options).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(err.to_string(), "NaN can't be written as json");
+
+    let err = try_from_json_with::<f64>("null", options).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected f64, found 'n' (line 1, column 1)"
+    );
+}
+
+#[test]
+#[should_panic(expected = "inf can't be written as json")]
+fn non_finite_error_panics_in_to_json() {
+    to_json_with(&f64::INFINITY, &options(NonFinite::Error));
+}
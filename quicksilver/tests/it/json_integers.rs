@@ -0,0 +1,120 @@
+use quicksilver::Quicksilver;
+use quicksilver::dynamic::DynamicValue;
+use quicksilver::json::{JsonOptions, from_json, to_json, try_from_json, try_from_json_with};
+
+#[derive(Debug, Quicksilver, PartialEq)]
+struct Counters {
+    hp: u32,
+    delta: i32,
+    gold: i64,
+    ticks: u64,
+    slots: Vec<usize>,
+    offset: isize,
+}
+
+fn counters() -> Counters {
+    Counters {
+        hp: u32::MAX,
+        delta: i32::MIN,
+        gold: i64::MIN,
+        ticks: u64::MAX,
+        slots: vec![0, usize::MAX],
+        offset: isize::MIN,
+    }
+}
+
+fn lenient() -> JsonOptions {
+    JsonOptions {
+        integral_floats: true,
+        ..JsonOptions::default()
+    }
+}
+
+#[test]
+fn bounds_round_trip() {
+    let json = to_json(&counters());
+    assert_eq!(from_json::<Counters>(&json), counters());
+}
+
+#[test]
+fn overflow() {
+    let json = to_json(&counters()).replace("4294967295", "4294967296");
+    let err = try_from_json::<Counters>(&json).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected u32 in 0..=4294967295, found '4294967296' (too large) at .hp (line 1, column 7)"
+    );
+
+    let err =
+        try_from_json::<Vec<u64>>("[1, 99999999999999999999999999999999999999999]").unwrap_err();
+    assert_eq!(err.expected, "u64 in 0..=18446744073709551615");
+    assert_eq!(
+        err.found,
+        "'99999999999999999999999999999999999999999' (too large)"
+    );
+    assert_eq!((err.path.as_str(), err.column), ("[1]", 5));
+}
+
+#[test]
+fn underflow() {
+    let err = try_from_json::<u32>("-1").unwrap_err();
+    assert_eq!(err.expected, "u32 in 0..=4294967295");
+    assert_eq!(err.found, "'-1' (too small)");
+
+    let json = to_json(&counters()).replace("-2147483648", "-2147483649");
+    let err = try_from_json::<Counters>(&json).unwrap_err();
+    assert_eq!(err.expected, "i32 in -2147483648..=2147483647");
+    assert_eq!(err.found, "'-2147483649' (too small)");
+    assert_eq!(err.path, ".delta");
+}
+
+#[test]
+fn floats_are_not_integers() {
+    let err = try_from_json::<Vec<u32>>("[1, 3.0]").unwrap_err();
+    assert_eq!(err.expected, "u32");
+    assert_eq!(err.found, "'3.0' (a float)");
+    assert_eq!(err.path, "[1]");
+
+    let err = try_from_json::<i64>("1e3").unwrap_err();
+    assert_eq!(err.found, "'1e3' (a float)");
+    let err = try_from_json::<i64>("1.2.3").unwrap_err();
+    assert_eq!(err.found, "'1.2.3'");
+}
+
+#[test]
+fn strict_number_grammar() {
+    assert_eq!(try_from_json::<i32>("-0"), Ok(0));
+    for json in ["01", "-01", "00", "-", "+1", "1-"] {
+        let err = try_from_json::<i32>(json).unwrap_err();
+        assert_eq!(err.expected, "i32", "{json}");
+        assert_eq!(err.found, format!("'{json}'"), "{json}");
+        assert_eq!(err.column, 1, "{json}");
+    }
+    let err = try_from_json_with::<u32>("1.", lenient()).unwrap_err();
+    assert_eq!(err.found, "'1.'");
+}
+
+#[test]
+fn lenient_integral_floats() {
+    assert_eq!(try_from_json_with::<u32>("3.0", lenient()), Ok(3));
+    assert_eq!(try_from_json_with::<i64>("-1e3", lenient()), Ok(-1000));
+    assert_eq!(try_from_json_with::<usize>("2.5e1", lenient()), Ok(25));
+
+    let err = try_from_json_with::<u32>("1.5", lenient()).unwrap_err();
+    assert_eq!(err.expected, "u32");
+    assert_eq!(err.found, "'1.5' (not a whole number)");
+
+    let err = try_from_json_with::<u64>("1e20", lenient()).unwrap_err();
+    assert_eq!(err.found, "'1e20' (too large)");
+    let err = try_from_json_with::<u32>("-1.0", lenient()).unwrap_err();
+    assert_eq!(err.found, "'-1.0' (too small)");
+}
+
+#[test]
+fn dynamic_range_errors() {
+    let err = DynamicValue::try_from_json_with("-5", &u64::MIRROR, lenient()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected u64 in 0..=18446744073709551615, found '-5' (too small) (line 1, column 1)"
+    );
+}
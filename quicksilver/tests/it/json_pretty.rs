@@ -0,0 +1,125 @@
+use std::collections::{HashMap, HashSet};
+
+use quicksilver::Quicksilver;
+use quicksilver::json::{JsonOptions, to_json_with, try_from_json, value_to_json_with};
+use quicksilver::reflections_ref::reflect_ref;
+
+#[derive(Debug, Quicksilver, PartialEq)]
+struct Room {
+    name: String,
+    pos: Vec<i32>,
+    doors: Vec<Door>,
+    empty: Vec<u32>,
+    items: HashMap<String, u32>,
+    seen: HashSet<u64>,
+    boss: Option<Kind>,
+}
+
+#[derive(Debug, Quicksilver, PartialEq)]
+struct Door {
+    to: u32,
+}
+
+#[allow(unused)]
+#[derive(Debug, Quicksilver, PartialEq)]
+enum Kind {
+    Frog,
+    Toad { size: f32 },
+}
+
+fn room() -> Room {
+    Room {
+        name: "hall".to_string(),
+        pos: vec![3, 4],
+        doors: vec![Door { to: 1 }, Door { to: 2 }],
+        empty: Vec::new(),
+        items: HashMap::from([
+            ("key".to_string(), 1),
+            ("apple".to_string(), 3),
+            ("coin".to_string(), 12),
+        ]),
+        seen: HashSet::from([30, 10, 20]),
+        boss: Some(Kind::Toad { size: 2.5 }),
+    }
+}
+
+const PRETTY: &str = r#"{
+    "name": "hall",
+    "pos": [3, 4],
+    "doors": [
+        {
+            "to": 1
+        },
+        {
+            "to": 2
+        }
+    ],
+    "empty": [],
+    "items": [
+        {
+            "key": "apple",
+            "value": 3
+        },
+        {
+            "key": "coin",
+            "value": 12
+        },
+        {
+            "key": "key",
+            "value": 1
+        }
+    ],
+    "seen": [10, 20, 30],
+    "boss": [
+        {
+            "__enum_variant": "Toad",
+            "size": 2.5
+        }
+    ]
+}"#;
+
+#[test]
+fn pretty() {
+    let json = to_json_with(&room(), &JsonOptions::pretty());
+    assert_eq!(json, PRETTY);
+    assert_eq!(try_from_json::<Room>(&json), Ok(room()));
+}
+
+#[test]
+fn reflection_matches_cursor() {
+    let options = JsonOptions::pretty();
+    let room = room();
+    assert_eq!(value_to_json_with(&reflect_ref(&room), &options), PRETTY);
+}
+
+#[test]
+fn long_arrays_are_split() {
+    let options = JsonOptions {
+        indent: Some(2),
+        compact_arrays: Some(2),
+        ..JsonOptions::default()
+    };
+    assert_eq!(to_json_with(&vec![1, 2], &options), "[1, 2]");
+    assert_eq!(
+        to_json_with(&vec![1, 2, 3], &options),
+        "[\n  1,\n  2,\n  3\n]"
+    );
+    assert_eq!(to_json_with(&Some(5), &options), "[5]");
+    assert_eq!(to_json_with(&None::<i32>, &options), "[]");
+
+    let options = JsonOptions {
+        indent: Some(2),
+        ..JsonOptions::default()
+    };
+    assert_eq!(to_json_with(&vec![1], &options), "[\n  1\n]");
+}
+
+#[test]
+fn sorted_compact() {
+    let options = JsonOptions {
+        sort_collections: true,
+        ..JsonOptions::default()
+    };
+    let set = HashSet::from(["b".to_string(), "c".to_string(), "a".to_string()]);
+    assert_eq!(to_json_with(&set, &options), r#"["a","b","c"]"#);
+}
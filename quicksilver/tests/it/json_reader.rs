@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+
+use quicksilver::Quicksilver;
+use quicksilver::dynamic::DynamicValue;
+use quicksilver::json::{
+    JsonOptions, to_json, try_from_json, try_from_json_into, try_from_json_with,
+};
+
+#[derive(Debug, Quicksilver, PartialEq)]
+struct Level {
+    name: String,
+    spawns: Vec<Spawn>,
+    tags: HashMap<String, u32>,
+    music: Option<String>,
+}
+
+#[derive(Debug, Quicksilver, PartialEq)]
+struct Spawn {
+    x: i32,
+    y: i32,
+    kind: Kind,
+}
+
+#[allow(unused)]
+#[derive(Debug, Quicksilver, PartialEq)]
+enum Kind {
+    Frog,
+    Item { name: String, count: u32 },
+}
+
+fn level() -> Level {
+    Level {
+        name: "swamp".to_string(),
+        spawns: vec![
+            Spawn {
+                x: 1,
+                y: -2,
+                kind: Kind::Frog,
+            },
+            Spawn {
+                x: 3,
+                y: 4,
+                kind: Kind::Item {
+                    name: "fly".to_string(),
+                    count: 5,
+                },
+            },
+        ],
+        tags: HashMap::from([("depth".to_string(), 3)]),
+        music: None,
+    }
+}
+
+const HAND_WRITTEN: &str = r#"
+{
+    "spawns": [
+        { "y": -2, "x": 1, "kind": { "__enum_variant": "Frog" } },
+        {
+            "kind": {
+                "__enum_variant": "Item",
+                "count": 5,
+                "name": "fly"
+            },
+            "x": 3,
+            "y": 4
+        }
+    ],
+    "music": [ ],
+    "name": "swamp",
+    "tags": [ { "key": "depth", "value": 3 } ]
+}
+"#;
+
+#[test]
+fn whitespace_and_any_order() {
+    assert_eq!(try_from_json::<Level>(HAND_WRITTEN), Ok(level()));
+    let compact = to_json(&level());
+    assert_eq!(try_from_json::<Level>(&compact), Ok(level()));
+}
+
+#[test]
+fn unknown_fields_are_skipped() {
+    let json = r#"{"name":"swamp","author":{"names":["a","b"],"year":2024,"ok":true,"x":null},
+        "spawns":[{"x":1,"y":-2,"kind":{"__enum_variant":"Frog","legs":4}},
+            {"x":3,"y":4,"z":0.5e3,"kind":{"__enum_variant":"Item","name":"fly","count":5}}],
+        "tags":[{"key":"depth","value":3}],"music":[],"version":[1,[2,{}]]}"#;
+    assert_eq!(try_from_json::<Level>(json), Ok(level()));
+}
+
+#[test]
+fn unknown_fields_can_be_denied() {
+    let options = JsonOptions {
+        deny_unknown_fields: true,
+        ..JsonOptions::default()
+    };
+    let json =
+        r#"{"name":"swamp","spawns":[{"x":1,"y":-2,"kind":{"__enum_variant":"Frog","legs":4}}]}"#;
+    let err = try_from_json_with::<Level>(json, options).unwrap_err();
+    assert_eq!(err.path, ".spawns[0].kind");
+    assert_eq!(err.expected, "a field of Frog");
+    assert_eq!(err.found, "\"legs\"");
+    assert_eq!(err.column, 73);
+}
+
+#[test]
+fn duplicate_fields() {
+    let err = try_from_json::<Spawn>(r#"{"x":1,"y":2,"x":3}"#).unwrap_err();
+    assert_eq!(err.expected, "each field only once");
+    assert_eq!(err.found, "\"x\"");
+    assert_eq!(err.column, 14);
+
+    let json = r#"{"x":1,"y":2,"kind":{"__enum_variant":"Item","name":"a","name":"b","count":1}}"#;
+    let err = try_from_json::<Spawn>(json).unwrap_err();
+    assert_eq!(err.path, ".kind");
+    assert_eq!(err.found, "\"name\"");
+}
+
+#[test]
+fn missing_enum_field() {
+    let json = r#"{"x":1,"y":2,"kind":{"__enum_variant":"Item","count":1 }}"#;
+    let err = try_from_json::<Spawn>(json).unwrap_err();
+    assert_eq!(err.path, ".kind");
+    assert_eq!(err.expected, "field \"name\"");
+    assert_eq!(err.found, "'}'");
+    assert_eq!(err.column, 56);
+}
+
+#[test]
+fn stray_commas() {
+    assert!(try_from_json::<Spawn>(r#"{"x":1,,"y":2}"#).is_err());
+    assert!(try_from_json::<Vec<i32>>("[1,2,]").is_err());
+    assert!(try_from_json::<Vec<i32>>("[,1]").is_err());
+    assert_eq!(try_from_json::<Vec<i32>>(" [ 1 ,\t2\r\n] "), Ok(vec![1, 2]));
+}
+
+#[test]
+fn in_place_any_order() {
+    let mut edited = level();
+    let json = r#"{ "music": ["drums"], "name": "bog", "unknown": [] }"#;
+    try_from_json_into(&mut edited, json).unwrap();
+    assert_eq!(edited.name, "bog");
+    assert_eq!(edited.music.as_deref(), Some("drums"));
+    assert_eq!(edited.spawns, level().spawns);
+}
+
+#[test]
+fn dynamic_any_order() {
+    let json = r#"{ "y": 5, "unknown": 1, "x": 6 }"#;
+    let DynamicValue::Struct(s) = DynamicValue::try_from_json(json, &Spawn::MIRROR).unwrap() else {
+        panic!()
+    };
+    let names: Vec<_> = s.fields.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["y", "x"]);
+}
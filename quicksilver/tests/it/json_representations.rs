@@ -0,0 +1,313 @@
+use std::collections::{HashMap, HashSet};
+
+use quicksilver::Quicksilver;
+use quicksilver::dynamic::{DynamicEnum, DynamicValue};
+use quicksilver::json::{
+    EnumTagging, JsonOptions, to_json, to_json_with, try_from_json, try_from_json_into_with,
+    try_from_json_with,
+};
+
+#[derive(Debug, Quicksilver, PartialEq)]
+struct Save {
+    player: Option<String>,
+    pet: Option<Box<Pet>>,
+    items: HashMap<String, u32>,
+    grid: HashMap<u32, Pet>,
+    pets: Vec<Pet>,
+}
+
+#[allow(unused)]
+#[derive(Debug, Quicksilver, PartialEq)]
+enum Pet {
+    Frog,
+    Cat { name: String, lives: u32 },
+}
+
+#[derive(Debug, PartialEq)]
+struct Handle(u32);
+
+#[derive(Debug, Quicksilver, PartialEq)]
+struct Cached {
+    id: u32,
+    #[quicksilver(skip)]
+    handle: Option<Handle>,
+}
+
+#[derive(Debug, Quicksilver, PartialEq, Default)]
+struct Scratch {
+    id: u32,
+    #[quicksilver(skip)]
+    names: Vec<String>,
+    #[quicksilver(skip)]
+    seen: HashSet<u32>,
+    #[quicksilver(skip)]
+    counts: HashMap<String, u32>,
+    #[quicksilver(skip)]
+    grid: HashMap<u32, u32>,
+    #[quicksilver(skip)]
+    last: Option<u32>,
+}
+
+fn save() -> Save {
+    Save {
+        player: Some("frogger".to_string()),
+        pet: None,
+        items: HashMap::from([("fly".to_string(), 3)]),
+        grid: HashMap::from([(7, Pet::Frog)]),
+        pets: vec![
+            Pet::Frog,
+            Pet::Cat {
+                name: "tom".to_string(),
+                lives: 9,
+            },
+        ],
+    }
+}
+
+fn natural(enum_tagging: EnumTagging) -> JsonOptions {
+    JsonOptions {
+        null_options: true,
+        object_maps: true,
+        enum_tagging,
+        ..JsonOptions::default()
+    }
+}
+
+fn round_trip(options: &JsonOptions) -> String {
+    let json = to_json_with(&save(), options);
+    assert_eq!(
+        try_from_json_with(&json, options.clone()),
+        Ok(save()),
+        "{json}"
+    );
+    json
+}
+
+#[test]
+fn default_encoding_is_unchanged() {
+    assert_eq!(
+        to_json(&save()),
+        r#"{"player":["frogger"],"pet":[],"items":[{"key":"fly","value":3}],"grid":[{"key":7,"value":{"__enum_variant":"Frog"}}],"pets":[{"__enum_variant":"Frog"},{"__enum_variant":"Cat","name":"tom","lives":9}]}"#
+    );
+}
+
+#[test]
+fn options_and_maps() {
+    let json = round_trip(&natural(EnumTagging::default()));
+    assert!(
+        json.starts_with(r#"{"player":"frogger","pet":null,"items":{"fly":3},"grid":[{"key":7,"#)
+    );
+}
+
+#[test]
+fn external_tagging() {
+    let json = round_trip(&natural(EnumTagging::External));
+    assert!(json.ends_with(r#""pets":["Frog",{"Cat":{"name":"tom","lives":9}}]}"#));
+}
+
+#[test]
+fn internal_tagging() {
+    let json = round_trip(&natural(EnumTagging::Internal { tag: "type" }));
+    assert!(json.ends_with(r#""pets":[{"type":"Frog"},{"type":"Cat","name":"tom","lives":9}]}"#));
+}
+
+#[test]
+fn adjacent_tagging() {
+    let tagging = EnumTagging::Adjacent {
+        tag: "t",
+        content: "c",
+    };
+    let json = round_trip(&natural(tagging));
+    assert!(json.ends_with(r#""pets":[{"t":"Frog"},{"t":"Cat","c":{"name":"tom","lives":9}}]}"#));
+}
+
+#[test]
+fn pretty_external() {
+    let options = JsonOptions {
+        enum_tagging: EnumTagging::External,
+        ..JsonOptions::pretty()
+    };
+    let json = to_json_with(&save().pets, &options);
+    assert_eq!(
+        json,
+        r#"[
+    "Frog",
+    {
+        "Cat": {
+            "name": "tom",
+            "lives": 9
+        }
+    }
+]"#
+    );
+    assert_eq!(try_from_json_with(&json, options), Ok(save().pets));
+}
+
+#[test]
+fn object_keys_are_escaped() {
+    let options = natural(EnumTagging::External);
+    let items = HashMap::from([("a \"b\"\n".to_string(), 1u32)]);
+    let json = to_json_with(&items, &options);
+    assert_eq!(json, r#"{"a \"b\"\n":1}"#);
+    assert_eq!(try_from_json_with(&json, options), Ok(items));
+}
+
+#[test]
+fn read_in_place() {
+    let options = natural(EnumTagging::External);
+    let mut existing = Save {
+        player: None,
+        pet: Some(Box::new(Pet::Frog)),
+        items: HashMap::from([("old".to_string(), 1)]),
+        grid: HashMap::new(),
+        pets: vec![Pet::Frog],
+    };
+    let json = to_json_with(&save(), &options);
+    try_from_json_into_with(&mut existing, &json, options).unwrap();
+    assert_eq!(existing, save());
+}
+
+#[test]
+fn skipped_options() {
+    let options = natural(EnumTagging::External);
+    let cached = Cached {
+        id: 3,
+        handle: Some(Handle(1)),
+    };
+    let json = to_json_with(&cached, &options);
+    assert_eq!(json, r#"{"id":3,"handle":null}"#);
+    let expected = Cached {
+        id: 3,
+        handle: None,
+    };
+    assert_eq!(try_from_json_with(&json, options.clone()), Ok(expected));
+
+    // values of skipped Options are ignored, whether they are null or not
+    let mut existing = cached;
+    try_from_json_into_with(&mut existing, r#"{"id":4,"handle":null}"#, options.clone()).unwrap();
+    assert_eq!(existing.handle, Some(Handle(1)));
+    try_from_json_into_with(&mut existing, r#"{"id":5,"handle":[1]}"#, options).unwrap();
+    assert_eq!(
+        existing,
+        Cached {
+            id: 5,
+            handle: Some(Handle(1)),
+        }
+    );
+}
+
+#[test]
+fn skipped_containers() {
+    let json = r#"{"id":2,"names":["a"],"seen":[1],"counts":{"a":1},
+        "grid":[{"key":1,"value":2}],"last":[3]}"#;
+    for options in [JsonOptions::default(), natural(EnumTagging::External)] {
+        let read: Scratch = try_from_json_with(json, options.clone()).unwrap();
+        assert_eq!(
+            read,
+            Scratch {
+                id: 2,
+                ..Scratch::default()
+            }
+        );
+        let dynamic =
+            DynamicValue::try_from_json_with(json, &Scratch::MIRROR, options.clone()).unwrap();
+        assert_eq!(dynamic.to_value::<Scratch>(), Ok(read));
+
+        let mut existing = Scratch {
+            id: 1,
+            names: vec!["b".to_string()],
+            seen: HashSet::from([4]),
+            counts: HashMap::from([("b".to_string(), 5)]),
+            grid: HashMap::from([(6, 7)]),
+            last: Some(8),
+        };
+        try_from_json_into_with(&mut existing, json, options).unwrap();
+        assert_eq!(existing.id, 2);
+        assert_eq!(existing.names, ["b"]);
+        assert_eq!(existing.seen, HashSet::from([4]));
+        assert_eq!(existing.counts, HashMap::from([("b".to_string(), 5)]));
+        assert_eq!(existing.grid, HashMap::from([(6, 7)]));
+        assert_eq!(existing.last, Some(8));
+    }
+}
+
+#[test]
+fn read_dynamic() {
+    let options = natural(EnumTagging::External);
+    let json = r#"{"pet":{"Cat":{"lives":3}},"items":{"fly":2},"player":null}"#;
+    let DynamicValue::Struct(value) =
+        DynamicValue::try_from_json_with(json, &Save::MIRROR, options).unwrap()
+    else {
+        panic!()
+    };
+    assert_eq!(value.fields[0].0, "pet");
+    assert_eq!(
+        value.fields[0].1,
+        DynamicValue::Option(Some(Box::new(DynamicValue::Enum(DynamicEnum {
+            variant: "Cat".to_string(),
+            fields: vec![("lives".to_string(), DynamicValue::U32(3))],
+        }))))
+    );
+    assert_eq!(
+        value.fields[1].1,
+        DynamicValue::Map(vec![(
+            DynamicValue::String("fly".to_string()),
+            DynamicValue::U32(2)
+        )])
+    );
+    assert_eq!(value.fields[2].1, DynamicValue::Option(None));
+}
+
+#[test]
+fn variant_errors() {
+    let options = natural(EnumTagging::External);
+    let err = try_from_json_with::<Pet>(r#""Cat""#, options.clone()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"expected the fields of Cat, found "Cat" (line 1, column 1)"#
+    );
+    let err = try_from_json_with::<Pet>(r#"{"Dog":{}}"#, options.clone()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"expected a variant of Pet, found "Dog" (line 1, column 2)"#
+    );
+    let err = try_from_json_with::<Pet>("{}", options).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected a variant of Pet, found '}' (line 1, column 2)"
+    );
+
+    let adjacent = natural(EnumTagging::Adjacent {
+        tag: "t",
+        content: "c",
+    });
+    let err = try_from_json_with::<Pet>(r#"{"t":"Cat"}"#, adjacent.clone()).unwrap_err();
+    assert_eq!(err.expected, "the fields of Cat");
+    let err = try_from_json_with::<Pet>(r#"{"t":"Cat","x":{}}"#, adjacent.clone()).unwrap_err();
+    assert_eq!(err.expected, r#""c" right after "t""#);
+    assert_eq!(err.found, r#""x""#);
+    let err = try_from_json_with::<Pet>(r#"{"c":{},"t":"Cat"}"#, adjacent).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"expected "t" as the first key, found "c" (line 1, column 2)"#
+    );
+
+    // the tag isn't searched for among the fields
+    let json = r#"{"name":"tom","__enum_variant":"Cat","lives":9}"#;
+    let err = try_from_json::<Pet>(json).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        r#"expected "__enum_variant" as the first key, found "name" (line 1, column 2)"#
+    );
+    let err = try_from_json::<Pet>("{}").unwrap_err();
+    assert_eq!(err.expected, r#""__enum_variant" as the first key"#);
+    assert_eq!(err.found, "'}'");
+
+    let err = try_from_json_with::<Option<u32>>("nul", natural(EnumTagging::External)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected null, found 'nul' (line 1, column 1)"
+    );
+    // the default encoding doesn't accept null
+    assert!(try_from_json::<Option<u32>>("null").is_err());
+}
@@ -0,0 +1,108 @@
+use std::io;
+
+use quicksilver::Quicksilver;
+use quicksilver::json::{from_json_reader, to_json, to_json_writer, try_from_json};
+
+#[derive(Debug, Quicksilver, PartialEq)]
+struct Replay {
+    player: String,
+    frames: Vec<Frame>,
+}
+
+#[derive(Debug, Quicksilver, PartialEq, Clone)]
+struct Frame {
+    tick: u64,
+    input: Option<String>,
+}
+
+fn replay() -> Replay {
+    Replay {
+        player: "Grüße 🐸".to_string(),
+        frames: (0..3000)
+            .map(|tick| Frame {
+                tick,
+                input: (tick % 5 == 0).then(|| format!("jump ü{tick}")),
+            })
+            .collect(),
+    }
+}
+
+/// Hands out one byte per read and fails after `limit` bytes, like a slow, flaky socket.
+struct Trickle<'a> {
+    data: &'a [u8],
+    limit: usize,
+}
+
+impl io::Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.limit == 0 {
+            return Err(io::Error::other("connection reset"));
+        }
+        let Some((first, rest)) = self.data.split_first() else {
+            return Ok(0);
+        };
+        buf[0] = *first;
+        self.data = rest;
+        self.limit -= 1;
+        Ok(1)
+    }
+}
+
+#[test]
+fn round_trip() {
+    let replay = replay();
+    let mut json = Vec::new();
+    to_json_writer(&replay, &mut json).unwrap();
+    assert_eq!(from_json_reader::<Replay>(json.as_slice()), Ok(replay));
+}
+
+#[test]
+fn chars_split_between_reads() {
+    let replay = replay();
+    let json = to_json(&replay);
+    let reader = Trickle {
+        data: json.as_bytes(),
+        limit: usize::MAX,
+    };
+    assert_eq!(from_json_reader::<Replay>(reader), Ok(replay));
+}
+
+#[test]
+fn same_errors_as_str() {
+    let json = to_json(&replay()).replacen("\"tick\":7", "\"tick\":x", 1);
+    let from_str = try_from_json::<Replay>(&json).unwrap_err();
+    let from_reader = from_json_reader::<Replay>(json.as_bytes()).unwrap_err();
+    assert_eq!(from_str, from_reader);
+    assert_eq!(from_reader.path, ".frames[7].tick");
+}
+
+#[test]
+fn io_error() {
+    let json = r#"{"player":"frog","frames":[{"tick":1,"input":[]}]}"#;
+    let reader = Trickle {
+        data: json.as_bytes(),
+        limit: 20,
+    };
+    let err = from_json_reader::<Replay>(reader).unwrap_err();
+    assert_eq!(err.path, "");
+    assert_eq!(err.expected, "'\"'");
+    assert_eq!(err.found, "I/O error (connection reset)");
+    assert_eq!(err.column, 21);
+
+    // the value may be complete, but the input is not
+    let reader = Trickle {
+        data: b"12 ",
+        limit: 2,
+    };
+    assert!(from_json_reader::<u64>(reader).is_err());
+}
+
+#[test]
+fn invalid_utf8() {
+    let err = from_json_reader::<String>(&b"\"fr\xC3g\""[..]).unwrap_err();
+    assert_eq!(err.found, "invalid utf-8");
+    assert_eq!(err.column, 4);
+
+    let err = from_json_reader::<String>(&b"\"fr\xE2\x82"[..]).unwrap_err();
+    assert_eq!(err.found, "invalid utf-8");
+}
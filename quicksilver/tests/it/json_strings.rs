@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use quicksilver::Quicksilver;
+use quicksilver::json::{from_json, to_json, try_from_json};
+
+#[derive(Debug, Quicksilver, PartialEq)]
+struct Note {
+    text: String,
+    by_author: HashMap<String, String>,
+}
+
+#[test]
+fn control_chars_round_trip() {
+    let text: String = (0..0x20u8)
+        .map(char::from)
+        .chain("\"\\/\u{7f}".chars())
+        .collect();
+    let note = Note {
+        by_author: HashMap::from([(text.clone(), text.clone())]),
+        text,
+    };
+    let json = to_json(&note);
+    assert!(json.chars().all(|c| c >= ' '), "{json}");
+    assert_eq!(from_json::<Note>(&json), note);
+}
+
+#[test]
+fn escapes_are_written_short() {
+    let note = Note {
+        text: "a\"b\\c\nd\te\r\u{8}\u{c}\u{1}\u{1f}".to_string(),
+        by_author: HashMap::new(),
+    };
+    assert_eq!(
+        to_json(&note),
+        r#"{"text":"a\"b\\c\nd\te\r\b\f\u0001\u001f","by_author":[]}"#
+    );
+}
+
+#[test]
+fn non_bmp_round_trip() {
+    let note = Note {
+        text: "frog 🐸, ünïcödé, 𝄞 and 中文".to_string(),
+        by_author: HashMap::from([("🐸".to_string(), "ribbit".to_string())]),
+    };
+    let json = to_json(&note);
+    // only control chars are escaped, everything else is written as is
+    assert!(json.contains("🐸"));
+    assert_eq!(from_json::<Note>(&json), note);
+}
+
+/// A `\u` escape of `hex`, built at runtime to keep the escapes readable
+fn u(hex: &str) -> String {
+    format!("\\u{hex}")
+}
+
+#[test]
+fn unicode_escapes() {
+    let text = [
+        u("0041"),
+        u("00e9"),
+        u("4e2d"),
+        u("D83D"),
+        u("dc38"),
+        r"\/".into(),
+    ]
+    .concat();
+    let json = format!(r#"{{"text":"{text}","by_author":[]}}"#);
+    assert_eq!(from_json::<Note>(&json).text, "A\u{e9}\u{4e2d}\u{1f438}/");
+}
+
+#[test]
+fn escaped_keys() {
+    // keys are unescaped before they are compared to field names
+    let json = format!(r#"{{"t{}xt":"x","by_author":[]}}"#, u("0065"));
+    assert_eq!(from_json::<Note>(&json).text, "x");
+}
+
+#[test]
+fn invalid_escapes() {
+    let cases = [
+        (r#"{"text":"a\x"#, "an escape sequence", r"'\x'", 11),
+        (r#"{"text":"\u12G4"#, "a hex digit", "'G'", 14),
+        (
+            r#"{"text":"\ud83d"#,
+            "a low surrogate escape",
+            "end of input",
+            16,
+        ),
+        (r#"{"text":"\ud83dx"#, "a low surrogate escape", "'x'", 16),
+        (
+            r#"{"text":"\udc38"#,
+            "a high surrogate escape",
+            r"'\udc38'",
+            10,
+        ),
+        (r#"{"text":"\"#, "an escape sequence", "end of input", 11),
+    ];
+    for (json, expected, found, column) in cases {
+        let err = try_from_json::<Note>(json).unwrap_err();
+        assert_eq!(err.expected, expected, "{json}");
+        assert_eq!(err.found, found, "{json}");
+        assert_eq!(err.column, column, "{json}");
+        assert_eq!(err.path, ".text", "{json}");
+    }
+
+    // a second escape that isn't a low surrogate
+    let json = format!(r#"{{"text":"{}{}"#, u("d83d"), u("0041"));
+    let err = try_from_json::<Note>(&json).unwrap_err();
+    assert_eq!(err.expected, "a low surrogate escape");
+    assert_eq!(err.found, format!("'{}'", u("0041")));
+    assert_eq!(err.column, 16);
+}
+
+#[test]
+fn unescaped_control_chars() {
+    let cases = [
+        ("{\"text\":\"a\nb\"", r"'\n'", 11),
+        ("{\"text\":\"\t\"", r"'\t'", 10),
+        ("{\"text\":\"ab\u{0}\"", r"'\0'", 12),
+        ("{\"text\":\"\u{1f}\"", r"'\u{1f}'", 10),
+    ];
+    for (json, found, column) in cases {
+        let err = try_from_json::<Note>(json).unwrap_err();
+        assert_eq!(err.expected, "an escaped control character", "{json:?}");
+        assert_eq!(err.found, found, "{json:?}");
+        assert_eq!(err.line, 1, "{json:?}");
+        assert_eq!(err.column, column, "{json:?}");
+        assert_eq!(err.path, ".text", "{json:?}");
+    }
+    // DEL isn't a control char for json
+    let json = "{\"text\":\"\u{7f}\",\"by_author\":[]}";
+    assert_eq!(try_from_json::<Note>(json).unwrap().text, "\u{7f}");
+}
@@ -0,0 +1,105 @@
+use std::io;
+
+use quicksilver::Quicksilver;
+use quicksilver::json::{
+    JsonOptions, to_json, to_json_with, to_json_writer, to_json_writer_with, value_to_json_writer,
+};
+use quicksilver::reflections_ref::reflect_ref;
+
+#[derive(Debug, Quicksilver)]
+struct Save {
+    name: String,
+    cells: Vec<Cell>,
+}
+
+#[derive(Debug, Quicksilver, Clone)]
+struct Cell {
+    x: i64,
+    label: String,
+}
+
+fn save() -> Save {
+    Save {
+        name: "big".to_string(),
+        cells: (0..2000)
+            .map(|x| Cell {
+                x,
+                label: format!("cell {x}"),
+            })
+            .collect(),
+    }
+}
+
+/// Remembers every write, fails once `limit` bytes were written.
+struct Recorder {
+    writes: Vec<usize>,
+    out: Vec<u8>,
+    limit: usize,
+}
+
+impl Recorder {
+    fn new(limit: usize) -> Self {
+        Self {
+            writes: Vec::new(),
+            out: Vec::new(),
+            limit,
+        }
+    }
+}
+
+impl io::Write for Recorder {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.out.len() + buf.len() > self.limit {
+            return Err(io::Error::other("disk full"));
+        }
+        self.writes.push(buf.len());
+        self.out.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn writes_in_chunks() {
+    let save = save();
+    let mut out = Recorder::new(usize::MAX);
+    to_json_writer(&save, &mut out).unwrap();
+    assert_eq!(String::from_utf8(out.out).unwrap(), to_json(&save));
+    assert!(out.writes.len() > 1);
+    // a chunk is written as soon as it is full, plus at most one field
+    assert!(
+        out.writes.iter().all(|&len| len < 9 * 1024),
+        "{:?}",
+        out.writes
+    );
+}
+
+#[test]
+fn same_output_for_all_apis() {
+    let save = save();
+    let options = JsonOptions::pretty();
+    let mut streamed = Vec::new();
+    to_json_writer_with(&save, &mut streamed, &options).unwrap();
+    assert_eq!(
+        String::from_utf8(streamed).unwrap(),
+        to_json_with(&save, &options)
+    );
+
+    let mut reflected = Vec::new();
+    value_to_json_writer(&reflect_ref(&save), &mut reflected, &options).unwrap();
+    assert_eq!(
+        String::from_utf8(reflected).unwrap(),
+        to_json_with(&save, &options)
+    );
+}
+
+#[test]
+fn io_errors_are_returned() {
+    let mut out = Recorder::new(10_000);
+    let err = to_json_writer(&save(), &mut out).unwrap_err();
+    assert_eq!(err.to_string(), "disk full");
+    assert!(out.out.len() <= 10_000);
+}
@@ -1,6 +1,27 @@
+mod apply;
 mod basic;
 mod cenum;
+mod compare;
 mod containers;
+mod cursor;
+mod default;
+mod downcast;
+mod dynamic;
+mod fields;
+mod in_place;
+mod json_cenum;
+mod json_errors;
+mod json_floats;
+mod json_integers;
+mod json_pretty;
+mod json_reader;
+mod json_representations;
+mod json_stream;
+mod json_strings;
+mod json_writer;
+mod mutation;
+mod numeric;
+mod pretty;
 mod proxy;
 mod regression;
 mod rust_enum;
@@ -0,0 +1,398 @@
+use std::collections::{HashMap, HashSet};
+use std::panic::{self, AssertUnwindSafe};
+
+use quicksilver::Quicksilver;
+use quicksilver::reflections::{ValueReflection, reflect};
+
+#[derive(Debug, PartialEq, Quicksilver)]
+struct Named {
+    name: String,
+}
+
+#[derive(Debug, PartialEq, Quicksilver)]
+struct VecHolder {
+    names: Vec<Named>,
+    #[quicksilver(skip)]
+    skipped: Vec<Named>,
+}
+
+fn named(name: &str) -> Named {
+    Named { name: name.into() }
+}
+
+#[test]
+fn vec_mutation() {
+    let mut val = VecHolder {
+        names: vec![named("a"), named("b")],
+        skipped: Vec::new(),
+    };
+    {
+        let ValueReflection::Struct(mut s) = reflect(&mut val) else {
+            panic!()
+        };
+        let Some((_, ValueReflection::Vec(mut v))) = s.fields_mut().next() else {
+            panic!()
+        };
+        unsafe { v.push(Box::into_raw(Box::new(named("c"))) as *mut u8) };
+        v.push_default();
+        unsafe { v.insert(0, Box::into_raw(Box::new(named("first"))) as *mut u8) };
+        v.insert_default(1);
+        assert_eq!(v.len(), 6);
+        v.remove(2);
+        v.swap(0, 1);
+        v.reserve(100);
+        let ValueReflection::Struct(mut inner) = v.get(1) else {
+            panic!()
+        };
+        let Some((_, ValueReflection::String(name))) = inner.fields_mut().next() else {
+            panic!()
+        };
+        *name = "edited".into();
+    }
+    assert_eq!(
+        val.names,
+        vec![
+            named(""),
+            named("edited"),
+            named("b"),
+            named("c"),
+            named("")
+        ]
+    );
+    assert!(val.names.capacity() >= 100);
+
+    {
+        let ValueReflection::Struct(mut s) = reflect(&mut val) else {
+            panic!()
+        };
+        let Some((_, ValueReflection::Vec(mut v))) = s.fields_mut().next() else {
+            panic!()
+        };
+        v.truncate(2);
+        assert_eq!(v.len(), 2);
+    }
+    assert_eq!(val.names, vec![named(""), named("edited")]);
+
+    {
+        let ValueReflection::Struct(mut s) = reflect(&mut val) else {
+            panic!()
+        };
+        let Some((_, ValueReflection::Vec(mut v))) = s.fields_mut().next() else {
+            panic!()
+        };
+        v.clear();
+        assert!(v.is_empty());
+    }
+    assert!(val.names.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "Not supported on skipped fields")]
+fn vec_mutation_skipped() {
+    let mut val = VecHolder {
+        names: Vec::new(),
+        skipped: Vec::new(),
+    };
+    let ValueReflection::Struct(mut s) = reflect(&mut val) else {
+        panic!()
+    };
+    let Some((_, ValueReflection::Vec(mut v))) = s.fields_mut().nth(1) else {
+        panic!()
+    };
+    v.push_default();
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Quicksilver)]
+struct Pos {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Debug, PartialEq, Quicksilver)]
+struct MapHolder {
+    map: HashMap<Pos, String>,
+    set: HashSet<Pos>,
+}
+
+fn ptr<T>(val: &T) -> *const u8 {
+    val as *const T as *const u8
+}
+
+fn boxed<T>(val: T) -> *mut u8 {
+    Box::into_raw(Box::new(val)) as *mut u8
+}
+
+#[test]
+fn map_mutation() {
+    let mut val = MapHolder {
+        map: HashMap::new(),
+        set: HashSet::new(),
+    };
+    val.map.insert(Pos { x: 1, y: 1 }, "one".into());
+    {
+        let ValueReflection::Struct(mut s) = reflect(&mut val) else {
+            panic!()
+        };
+        let Some((_, ValueReflection::HashMap(mut hm))) = s.fields_mut().next() else {
+            panic!()
+        };
+        unsafe {
+            hm.insert(boxed(Pos { x: 2, y: 2 }), boxed(String::from("two")));
+            hm.insert(boxed(Pos { x: 3, y: 3 }), boxed(String::from("three")));
+            assert_eq!(hm.len(), 3);
+            assert!(hm.contains(ptr(&Pos { x: 2, y: 2 })));
+            assert!(!hm.contains(ptr(&Pos { x: 4, y: 4 })));
+
+            let Some(ValueReflection::String(two)) = hm.get(ptr(&Pos { x: 2, y: 2 })) else {
+                panic!()
+            };
+            assert_eq!(*two, "two");
+
+            let Some(ValueReflection::String(one)) = hm.get_mut(ptr(&Pos { x: 1, y: 1 })) else {
+                panic!()
+            };
+            *one = "uno".into();
+
+            assert!(hm.remove(ptr(&Pos { x: 3, y: 3 })));
+            assert!(!hm.remove(ptr(&Pos { x: 3, y: 3 })));
+
+            let edited = hm.edit_key(ptr(&Pos { x: 2, y: 2 }), |key| {
+                let ValueReflection::Struct(mut key) = key else {
+                    panic!()
+                };
+                let Some((_, ValueReflection::I32(x))) = key.fields_mut().next() else {
+                    panic!()
+                };
+                *x = 20;
+            });
+            assert!(edited);
+            assert!(!hm.edit_key(ptr(&Pos { x: 2, y: 2 }), |_| panic!()));
+        }
+    }
+    let expected: HashMap<Pos, String> = [
+        (Pos { x: 1, y: 1 }, "uno".to_string()),
+        (Pos { x: 20, y: 2 }, "two".to_string()),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(val.map, expected);
+
+    {
+        let ValueReflection::Struct(mut s) = reflect(&mut val) else {
+            panic!()
+        };
+        let Some((_, ValueReflection::HashMap(mut hm))) = s.fields_mut().next() else {
+            panic!()
+        };
+        hm.clear();
+        assert!(hm.is_empty());
+    }
+    assert!(val.map.is_empty());
+}
+
+#[test]
+fn set_mutation() {
+    let mut val = MapHolder {
+        map: HashMap::new(),
+        set: HashSet::new(),
+    };
+    {
+        let ValueReflection::Struct(mut s) = reflect(&mut val) else {
+            panic!()
+        };
+        let Some((_, ValueReflection::HashSet(mut hs))) = s.fields_mut().nth(1) else {
+            panic!()
+        };
+        unsafe {
+            hs.insert(boxed(Pos { x: 1, y: 1 }));
+            hs.insert(boxed(Pos { x: 2, y: 2 }));
+            hs.insert(boxed(Pos { x: 2, y: 2 }));
+            assert_eq!(hs.len(), 2);
+            assert!(hs.contains(ptr(&Pos { x: 1, y: 1 })));
+            assert!(hs.get(ptr(&Pos { x: 1, y: 1 })).is_some());
+            assert!(hs.get(ptr(&Pos { x: 5, y: 1 })).is_none());
+            assert!(hs.remove(ptr(&Pos { x: 1, y: 1 })));
+            assert!(!hs.contains(ptr(&Pos { x: 1, y: 1 })));
+            let edited = hs.edit(ptr(&Pos { x: 2, y: 2 }), |element| {
+                let ValueReflection::Struct(mut element) = element else {
+                    panic!()
+                };
+                let Some((_, ValueReflection::I32(y))) = element.fields_mut().nth(1) else {
+                    panic!()
+                };
+                *y = 7;
+            });
+            assert!(edited);
+        }
+    }
+    assert_eq!(val.set, [Pos { x: 2, y: 7 }].into_iter().collect());
+
+    {
+        let ValueReflection::Struct(mut s) = reflect(&mut val) else {
+            panic!()
+        };
+        let Some((_, ValueReflection::HashSet(mut hs))) = s.fields_mut().nth(1) else {
+            panic!()
+        };
+        hs.clear();
+    }
+    assert!(val.set.is_empty());
+}
+
+#[test]
+fn panicking_edit_keeps_entry() {
+    let mut val = MapHolder {
+        map: HashMap::new(),
+        set: HashSet::new(),
+    };
+    val.map.insert(Pos { x: 1, y: 1 }, "one".into());
+    val.set.insert(Pos { x: 2, y: 2 });
+    {
+        let ValueReflection::Struct(mut s) = reflect(&mut val) else {
+            panic!()
+        };
+        let mut fields = s.fields_mut();
+        let Some((_, ValueReflection::HashMap(mut hm))) = fields.next() else {
+            panic!()
+        };
+        let Some((_, ValueReflection::HashSet(mut hs))) = fields.next() else {
+            panic!()
+        };
+        let result = panic::catch_unwind(AssertUnwindSafe(|| unsafe {
+            hm.edit_key(ptr(&Pos { x: 1, y: 1 }), |key| {
+                let ValueReflection::Struct(mut key) = key else {
+                    panic!()
+                };
+                let Some(ValueReflection::I32(x)) = key.field_mut("x") else {
+                    panic!()
+                };
+                *x = 10;
+                panic!("edit failed");
+            })
+        }));
+        assert!(result.is_err());
+        let result = panic::catch_unwind(AssertUnwindSafe(|| unsafe {
+            hs.edit(ptr(&Pos { x: 2, y: 2 }), |_| panic!("edit failed"))
+        }));
+        assert!(result.is_err());
+    }
+    assert_eq!(val.map.len(), 1);
+    assert_eq!(val.map[&Pos { x: 10, y: 1 }], "one");
+    assert_eq!(val.set, [Pos { x: 2, y: 2 }].into_iter().collect());
+}
+
+#[derive(Debug, PartialEq, Quicksilver)]
+#[allow(unused)]
+enum Ideology {
+    None,
+    CatPerson(String),
+    CatDogPerson { cats: usize, dogs: usize },
+}
+
+#[derive(Debug, PartialEq, Quicksilver)]
+struct Person {
+    nickname: Option<String>,
+    ideology: Ideology,
+}
+
+#[test]
+fn option_mutation() {
+    let mut val = Person {
+        nickname: None,
+        ideology: Ideology::None,
+    };
+    let ValueReflection::Struct(mut s) = reflect(&mut val) else {
+        panic!()
+    };
+    let Some((_, ValueReflection::Option(mut o))) = s.fields_mut().next() else {
+        panic!()
+    };
+    o.set_some_default();
+    let Some(ValueReflection::String(nick)) = o.get() else {
+        panic!()
+    };
+    assert_eq!(*nick, "");
+    *nick = "Frosch".into();
+    assert!(o.get_ref().is_some());
+    o.set_none();
+    assert!(o.get_ref().is_none());
+    unsafe { o.set_some(Box::into_raw(Box::new(String::from("Kampf"))) as *mut u8) };
+    drop(s);
+    assert_eq!(val.nickname, Some("Kampf".into()));
+}
+
+#[test]
+fn switch_enum_variant() {
+    let mut val = Person {
+        nickname: None,
+        ideology: Ideology::CatPerson("Garfield".into()),
+    };
+    {
+        let ValueReflection::Struct(mut s) = reflect(&mut val) else {
+            panic!()
+        };
+        let Some((_, ValueReflection::RustEnum(mut e))) = s.fields_mut().nth(1) else {
+            panic!()
+        };
+        assert_eq!(e.variant_name, "CatPerson");
+        e.switch_variant(2);
+        assert_eq!(e.variant_name, "CatDogPerson");
+        assert_eq!(e.variant_idx, 2);
+        let Some((_, ValueReflection::USize(dogs))) = e.fields_mut().nth(1) else {
+            panic!()
+        };
+        *dogs = 3;
+    }
+    assert_eq!(val.ideology, Ideology::CatDogPerson { cats: 0, dogs: 3 });
+
+    let ValueReflection::Struct(mut s) = reflect(&mut val) else {
+        panic!()
+    };
+    let Some((_, ValueReflection::RustEnum(mut e))) = s.fields_mut().nth(1) else {
+        panic!()
+    };
+    e.switch_variant(1);
+    e.switch_variant(0);
+    drop(s);
+    assert_eq!(val.ideology, Ideology::None);
+}
+
+#[test]
+fn map_iteration() {
+    let mut val = MapHolder {
+        map: HashMap::new(),
+        set: HashSet::new(),
+    };
+    val.map.insert(Pos { x: 1, y: 1 }, "one".into());
+    val.map.insert(Pos { x: 2, y: 2 }, "two".into());
+    val.set.insert(Pos { x: 3, y: 3 });
+    {
+        let ValueReflection::Struct(mut s) = reflect(&mut val) else {
+            panic!()
+        };
+        let mut fields = s.fields_mut();
+        let (_, mut map) = fields.next().unwrap();
+        let (_, set) = fields.next().unwrap();
+        let ValueReflection::HashMap(hm) = &mut map else {
+            panic!()
+        };
+        for (key, value) in hm.iter_mut() {
+            let (ValueReflection::Struct(key), ValueReflection::String(value)) = (key, value)
+            else {
+                panic!()
+            };
+            let ValueReflection::I32(x) = key.fields()[0].value() else {
+                panic!()
+            };
+            value.push_str(&format!("{}", **x));
+        }
+        assert_eq!(hm.iter().count(), 2);
+
+        let ValueReflection::HashSet(hs) = &set else {
+            panic!()
+        };
+        assert_eq!(hs.iter().count(), 1);
+    }
+    assert_eq!(val.map[&Pos { x: 1, y: 1 }], "one1");
+    assert_eq!(val.map[&Pos { x: 2, y: 2 }], "two2");
+}
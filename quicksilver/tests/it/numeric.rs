@@ -0,0 +1,101 @@
+use quicksilver::Quicksilver;
+use quicksilver::numeric::{Number, NumericError, NumericKind};
+use quicksilver::reflections::{ValueReflection, reflect};
+use quicksilver::reflections_ref::reflect_ref;
+
+#[derive(Debug, Quicksilver)]
+struct Stats {
+    hp: u32,
+    speed: f32,
+    gold: i64,
+    boxed: Box<usize>,
+    name: String,
+}
+
+fn stats() -> Stats {
+    Stats {
+        hp: 10,
+        speed: 1.5,
+        gold: -3,
+        boxed: Box::new(7),
+        name: "frog".to_string(),
+    }
+}
+
+#[test]
+fn read_numbers() {
+    let stats = stats();
+    let ValueReflection::Struct(r) = reflect_ref(&stats) else {
+        panic!()
+    };
+    let numbers: Vec<_> = r.fields().iter().map(|f| f.value().as_number()).collect();
+    assert_eq!(
+        numbers,
+        [
+            Some(Number::Int(10)),
+            Some(Number::Float(1.5)),
+            Some(Number::Int(-3)),
+            Some(Number::Int(7)),
+            None,
+        ]
+    );
+    assert_eq!(r.fields()[1].value().as_i128(), None);
+    assert_eq!(r.fields()[2].value().as_f64(), Some(-3.));
+    assert_eq!(
+        r.fields()[3].value().numeric_kind(),
+        Some(NumericKind::USize)
+    );
+    assert_eq!(r.fields()[4].value().numeric_kind(), None);
+}
+
+#[test]
+fn kinds() {
+    assert_eq!(NumericKind::of(&u32::MIRROR), Some(NumericKind::U32));
+    assert_eq!(NumericKind::of(&String::MIRROR), None);
+    assert_eq!(NumericKind::I64.bits(), 64);
+    assert!(!NumericKind::U32.is_signed());
+    assert!(NumericKind::F32.is_signed());
+    assert!(NumericKind::F64.is_float());
+    assert_eq!(NumericKind::U64.max(), Number::Int(u64::MAX as i128));
+    assert_eq!(NumericKind::I32.min(), Number::Int(i32::MIN as i128));
+    assert_eq!(NumericKind::F32.max().as_f64(), f32::MAX as f64);
+}
+
+#[test]
+fn set_numbers() {
+    let mut stats = stats();
+    let ValueReflection::Struct(mut r) = reflect(&mut stats) else {
+        panic!()
+    };
+    let fields: Vec<_> = r.fields_mut().map(|(_, value)| value).collect();
+    let Ok([mut hp, mut speed, mut gold, mut boxed, mut name]) = <[_; 5]>::try_from(fields) else {
+        panic!()
+    };
+    hp.set_from_f64(42.).unwrap();
+    speed.set_from_i128(3).unwrap();
+    gold.set_from_i128(i64::MIN as i128).unwrap();
+    boxed.set_from_f64(8.).unwrap();
+
+    assert_eq!(
+        hp.set_from_i128(-1),
+        Err(NumericError::OutOfRange {
+            value: Number::Int(-1),
+            kind: NumericKind::U32
+        })
+    );
+    assert_eq!(hp.set_from_f64(1.5), Err(NumericError::NotAnInteger(1.5)));
+    assert!(hp.set_from_f64(f64::NAN).is_err());
+    assert!(gold.set_from_f64(1e300).is_err());
+    assert!(speed.set_from_f64(1e300).is_err());
+    assert_eq!(name.set_from_i128(1), Err(NumericError::NotNumeric));
+    assert_eq!(
+        boxed.set_from_i128(-2).unwrap_err().to_string(),
+        "-2 is out of range for usize"
+    );
+    drop(r);
+
+    assert_eq!(stats.hp, 42);
+    assert_eq!(stats.speed, 3.);
+    assert_eq!(stats.gold, i64::MIN);
+    assert_eq!(*stats.boxed, 8);
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use quicksilver::Quicksilver;
+use quicksilver::pretty::{PrettyOptions, PrettyStyle, pretty, pretty_with};
+
+#[derive(Debug, Quicksilver)]
+struct Pos {
+    x: i32,
+    y: i32,
+}
+
+#[allow(unused)]
+#[derive(Debug, Quicksilver)]
+enum Task {
+    Idle,
+    Walk { to: Pos },
+    Say(String),
+}
+
+#[derive(Debug, Quicksilver)]
+struct Npc {
+    name: String,
+    pos: Pos,
+    speed: f32,
+    tasks: Vec<Task>,
+    inventory: HashMap<String, u32>,
+    target: Option<Box<Pos>>,
+}
+
+fn npc() -> Npc {
+    Npc {
+        name: "Frog".to_string(),
+        pos: Pos { x: 1, y: 2 },
+        speed: 1.,
+        tasks: vec![
+            Task::Walk {
+                to: Pos { x: 3, y: 4 },
+            },
+            Task::Say("Ribbit ribbit".to_string()),
+            Task::Idle,
+        ],
+        inventory: HashMap::from([("fly".to_string(), 3), ("bug".to_string(), 1)]),
+        target: None,
+    }
+}
+
+#[test]
+fn pretty_rust() {
+    let options = PrettyOptions {
+        sort_map_keys: true,
+        ..Default::default()
+    };
+    let expected = r#"Npc {
+    name: "Frog",
+    pos: Pos {
+        x: 1,
+        y: 2,
+    },
+    speed: 1.0,
+    tasks: [
+        Walk {
+            to: Pos {
+                x: 3,
+                y: 4,
+            },
+        },
+        Say(
+            "Ribbit ribbit",
+        ),
+        Idle,
+    ],
+    inventory: {
+        "bug": 1,
+        "fly": 3,
+    },
+    target: None,
+}"#;
+    assert_eq!(pretty_with(&npc(), options).to_string(), expected);
+}
+
+#[test]
+fn pretty_limits() {
+    let options = PrettyOptions {
+        max_depth: Some(2),
+        max_elements: Some(2),
+        max_string_len: Some(5),
+        field_filter: Some(|name| name != "inventory"),
+        ..Default::default()
+    };
+    let mut val = npc();
+    val.target = Some(Box::new(Pos { x: 5, y: 6 }));
+    let expected = r#"Npc {
+    name: "Frog",
+    pos: Pos {
+        x: 1,
+        y: 2,
+    },
+    speed: 1.0,
+    tasks: [
+        Walk { .. },
+        Say(..),
+        .. 1 more
+    ],
+    target: Some(Pos {
+        x: 5,
+        y: 6,
+    }),
+}"#;
+    assert_eq!(pretty_with(&val, options.clone()).to_string(), expected);
+
+    assert_eq!(
+        pretty_with(&"Ribbit ribbit".to_string(), options).to_string(),
+        r#""Ribbi"..."#
+    );
+    assert_eq!(
+        pretty(&Pos { x: 1, y: 2 }).to_string(),
+        "Pos {\n    x: 1,\n    y: 2,\n}"
+    );
+}
+
+#[test]
+fn pretty_yaml() {
+    let options = PrettyOptions {
+        style: PrettyStyle::Yaml,
+        sort_map_keys: true,
+        ..Default::default()
+    };
+    let expected = r#"name: "Frog"
+pos:
+  x: 1
+  y: 2
+speed: 1.0
+tasks:
+  -
+    Walk:
+      to:
+        x: 3
+        y: 4
+  -
+    Say:
+      - "Ribbit ribbit"
+  - Idle
+inventory:
+  "bug": 1
+  "fly": 3
+target: ~"#;
+    assert_eq!(pretty_with(&npc(), options).to_string(), expected);
+}
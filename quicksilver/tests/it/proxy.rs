@@ -15,6 +15,7 @@ struct EntityWrapper(Entity);
 impl ::quicksilver::Quicksilver for EntityWrapper {
     const MIRROR: ::quicksilver::Type = ::quicksilver::Type::Struct(&::quicksilver::Struct {
         name: "EntityWrapper",
+        type_id: ::std::any::TypeId::of::<Self>(),
         size: ::std::mem::size_of::<Self>(),
         align: align_of::<Self>(),
         fields: &[
@@ -29,6 +30,13 @@ impl ::quicksilver::Quicksilver for EntityWrapper {
                 offset: ::std::mem::offset_of!(Self, 0.generation),
             },
         ],
+        index_of: |name| match name {
+            "id" => Some(0),
+            "generation" => Some(1),
+            _ => None,
+        },
+        drop: |ptr| unsafe { ::std::ptr::drop_in_place(ptr as *mut Self) },
+        default: None,
     });
 }
 
@@ -44,7 +52,7 @@ struct Container {
 
 #[test]
 fn subst_roundtrip() {
-    let mut val = Container {
+    let val = Container {
         leader: Entity {
             id: 1,
             generation: 1,
@@ -73,7 +81,7 @@ fn subst_roundtrip() {
         .into_iter()
         .collect(),
     };
-    let s = reflect_ref(&mut val).to_json();
+    let s = reflect_ref(&val).to_json();
     println!("{}", &s);
     let val2 = from_json::<Container>(&s);
     dbg!(&val2);
@@ -91,11 +99,11 @@ enum ContainerEnum {
 
 #[test]
 fn subst_roundtrip_enum_one() {
-    let mut val = ContainerEnum::One(Entity {
+    let val = ContainerEnum::One(Entity {
         id: 1,
         generation: 1,
     });
-    let s = reflect_ref(&mut val).to_json();
+    let s = reflect_ref(&val).to_json();
     println!("{}", &s);
     let val2 = from_json::<ContainerEnum>(&s);
     dbg!(&val2);
@@ -104,7 +112,7 @@ fn subst_roundtrip_enum_one() {
 
 #[test]
 fn subst_roundtrip_enum_more() {
-    let mut val = ContainerEnum::More {
+    let val = ContainerEnum::More {
         followers: vec![
             Entity {
                 id: 2,
@@ -120,7 +128,7 @@ fn subst_roundtrip_enum_more() {
             },
         ],
     };
-    let s = reflect_ref(&mut val).to_json();
+    let s = reflect_ref(&val).to_json();
     println!("{}", &s);
     let val2 = from_json::<ContainerEnum>(&s);
     dbg!(&val2);
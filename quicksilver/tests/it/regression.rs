@@ -14,7 +14,7 @@ pub struct DrawPos(pub FPos);
 #[test]
 fn drawpos_deser() {
     let s = r#"{"0":{"x":384,"y":192}}"#;
-    let val2 = from_json::<DrawPos>(&s);
+    let val2 = from_json::<DrawPos>(s);
     dbg!(&val2);
 }
 
@@ -19,10 +19,10 @@ macro_rules! error_single {
 
 #[proc_macro_derive(Quicksilver, attributes(quicksilver))]
 pub fn derive_quicksilver(input: TokenStream) -> TokenStream {
-    return match inner(input) {
+    match inner(input) {
         Ok(tt) => tt,
         Err(err) => err.to_compile_error(),
-    };
+    }
 }
 
 #[derive(Debug)]
@@ -35,6 +35,7 @@ fn inner(item: TokenStream) -> Result<TokenStream, MacroError> {
     let mut iter = item.into_iter().peekable();
 
     let mut repr = Repr::Rust;
+    let mut use_default = false;
     loop {
         if matches!(iter.peek(), Some(TT::Ident(ident))
                 if ["pub", "pub(crate)"].contains(&ident.to_string().as_str()))
@@ -48,25 +49,38 @@ fn inner(item: TokenStream) -> Result<TokenStream, MacroError> {
         {
             let _hashtag = iter.next();
             let group = iter.next();
-            match group {
-                Some(TT::Group(group)) => {
-                    let mut iter = group.stream().into_iter();
-                    match (iter.next(), iter.next(), iter.next()) {
-                        (Some(TT::Ident(repr_ident)), Some(TT::Group(repr_group)), None)
-                            if repr_ident.to_string() == "repr" =>
-                        {
-                            let mut iter = repr_group.stream().into_iter();
-                            match (iter.next(), iter.next()) {
-                                (Some(TT::Ident(c)), None) if c.to_string() == "C" => {
-                                    repr = Repr::C; // <= the thing I want to know
-                                }
-                                _ => {}
+            if let Some(TT::Group(group)) = group {
+                let mut iter = group.stream().into_iter();
+                match (iter.next(), iter.next(), iter.next()) {
+                    (Some(TT::Ident(repr_ident)), Some(TT::Group(repr_group)), None)
+                        if repr_ident.to_string() == "repr" =>
+                    {
+                        let mut iter = repr_group.stream().into_iter();
+                        match (iter.next(), iter.next()) {
+                            (Some(TT::Ident(c)), None) if c.to_string() == "C" => {
+                                repr = Repr::C; // <= the thing I want to know
                             }
+                            _ => {}
                         }
-                        _ => {}
                     }
+                    (Some(TT::Ident(qs_ident)), Some(TT::Group(qs_group)), None)
+                        if qs_ident.to_string() == "quicksilver" =>
+                    {
+                        let mut iter = qs_group.stream().into_iter();
+                        match (iter.next(), iter.next()) {
+                            (Some(TT::Ident(d)), None) if d.to_string() == "default" => {
+                                use_default = true;
+                            }
+                            _ => {
+                                error!(
+                                    &[TT::Group(qs_group.clone())],
+                                    "Unknown quicksilver attribute."
+                                )
+                            }
+                        }
+                    }
+                    _ => {}
                 }
-                _ => {}
             }
             continue;
         }
@@ -91,13 +105,13 @@ fn inner(item: TokenStream) -> Result<TokenStream, MacroError> {
             "struct" => {
                 let name = name.to_string();
                 let fields = parse_fields(fields.stream())?;
-                generate_struct_impl(name, fields)
+                generate_struct_impl(name, fields, use_default)
             }
             "enum" => {
                 let name = name.to_string();
                 match repr {
-                    Repr::Rust => generate_rust_enum_impl(name, fields.stream()),
-                    Repr::C => generate_c_enum_impl(name, fields.stream()),
+                    Repr::Rust => generate_rust_enum_impl(name, fields.stream(), use_default),
+                    Repr::C => generate_c_enum_impl(name, fields.stream(), use_default),
                 }
             }
             other => error_single!(keyword, "Unknown keyword {other:?}"),
@@ -113,15 +127,28 @@ fn inner(item: TokenStream) -> Result<TokenStream, MacroError> {
             assert_eq!("struct", s.to_string());
             let name = name.to_string();
             let fields = parse_fields(fields.stream())?;
-            generate_struct_impl(name, fields)
+            generate_struct_impl(name, fields, use_default)
         }
-        other @ _ => {
+        other => {
             panic!("Unsupported struct shape.\n{other:?}")
         }
     }
 }
 
-fn generate_struct_impl(name: String, fields: Vec<Field>) -> Result<TokenStream, MacroError> {
+/// mirror entry for `#[quicksilver(default)]`
+fn default_text(use_default: bool) -> &'static str {
+    if use_default {
+        "Some(|ptr| unsafe { ::std::ptr::write(ptr as *mut Self, <Self as ::std::default::Default>::default()) })"
+    } else {
+        "None"
+    }
+}
+
+fn generate_struct_impl(
+    name: String,
+    fields: Vec<Field>,
+    use_default: bool,
+) -> Result<TokenStream, MacroError> {
     let result = &mut String::new();
     write!(
         result,
@@ -129,24 +156,28 @@ fn generate_struct_impl(name: String, fields: Vec<Field>) -> Result<TokenStream,
 impl ::quicksilver::Quicksilver for {name} {{
     const MIRROR: ::quicksilver::Type = ::quicksilver::Type::Struct(&::quicksilver::Struct {{
         name: "{name}",
+        type_id: ::std::any::TypeId::of::<Self>(),
         size: ::std::mem::size_of::<Self>(),
         align: align_of::<Self>(),
         fields: &["#
     )
     .unwrap();
 
+    let mut index_arms = String::new();
     for (i, field) in fields.into_iter().enumerate() {
-        generate_field(
-            result,
-            &field.name.unwrap_or_else(|| format!("{i}")),
-            &field.mirror,
-        );
+        let field_name = field.name.unwrap_or_else(|| format!("{i}"));
+        write!(index_arms, r#""{field_name}" => Some({i}), "#).unwrap();
+        generate_field(result, &field_name, &field.mirror);
     }
 
+    let default = default_text(use_default);
     write!(
         result,
         r#"
         ],
+        index_of: |name| match name {{ {index_arms}_ => None }},
+        drop: |ptr| unsafe {{ ::std::ptr::drop_in_place(ptr as *mut Self) }},
+        default: {default},
     }});
 }}
 "#
@@ -184,7 +215,7 @@ fn parse_fields(input: TokenStream) -> Result<Vec<Field>, MacroError> {
     // because types can use commas inside themselves like `HashMap<X,Y>`
     // we need to check that we are not inside the type of a field via nesting level
     let mut level = 0;
-    while matches!(current, Some(_)) {
+    while current.is_some() {
         if matches!(current, Some(TT::Punct(ref comma)) if comma.as_char() == ',') && level == 0 {
             result.push(parse_field(&buffer)?);
             buffer.clear();
@@ -211,7 +242,7 @@ fn parse_fields(input: TokenStream) -> Result<Vec<Field>, MacroError> {
 
         current = iter.next();
     }
-    if buffer.len() > 0 {
+    if !buffer.is_empty() {
         result.push(parse_field(&buffer)?);
     }
 
@@ -273,7 +304,7 @@ fn parse_field(mut buffer: &[TokenTree]) -> Result<Field, MacroError> {
     let r = match (iter.next(), iter.next(), iter.next()) {
         (Some(TT::Ident(ty)), None, None) => {
             let name = None;
-            let mirror = parse_mirror(&buffer, &ty.to_string(), skip, &substitution)?;
+            let mirror = parse_mirror(buffer, &ty.to_string(), skip, &substitution)?;
             Field {
                 name,
                 mirror,
@@ -285,7 +316,7 @@ fn parse_field(mut buffer: &[TokenTree]) -> Result<Field, MacroError> {
         {
             let name = Some(name.to_string());
             let buffer = &buffer[2..];
-            let mirror = parse_mirror(&buffer, &ty.to_string(), skip, &substitution)?;
+            let mirror = parse_mirror(buffer, &ty.to_string(), skip, &substitution)?;
             let ty = buffer
                 .iter()
                 .map(|it| it.to_string())
@@ -295,7 +326,7 @@ fn parse_field(mut buffer: &[TokenTree]) -> Result<Field, MacroError> {
         }
         (Some(TT::Ident(ty)), Some(TT::Punct(stair)), Some(_)) if stair.as_char() == '<' => {
             let name = None;
-            let mirror = parse_mirror(&buffer, &ty.to_string(), skip, &substitution)?;
+            let mirror = parse_mirror(buffer, &ty.to_string(), skip, &substitution)?;
             let ty = buffer
                 .iter()
                 .map(|it| it.to_string())
@@ -339,7 +370,7 @@ fn parse_mirror<'a>(
     } else {
         let mut result = String::new();
         if skip {
-            result.push_str("<");
+            result.push('<');
         }
         result.push_str(ty);
         result.push_str("::");
@@ -369,7 +400,11 @@ fn chunked<I>(a: impl IntoIterator<Item = I>, chunk_size: usize) -> impl Iterato
     })
 }
 
-fn generate_c_enum_impl(name: String, input: TokenStream) -> Result<TokenStream, MacroError> {
+fn generate_c_enum_impl(
+    name: String,
+    input: TokenStream,
+    use_default: bool,
+) -> Result<TokenStream, MacroError> {
     let result = &mut String::new();
     write!(
         result,
@@ -377,6 +412,7 @@ fn generate_c_enum_impl(name: String, input: TokenStream) -> Result<TokenStream,
 impl ::quicksilver::Quicksilver for {name} {{
     const MIRROR: ::quicksilver::Type = ::quicksilver::Type::CEnum(&::quicksilver::CEnum {{
         name: "{name}",
+        type_id: ::std::any::TypeId::of::<Self>(),
         size: ::std::mem::size_of::<Self>(),
         align: ::std::mem::align_of::<Self>(),
         variants: &["#
@@ -407,10 +443,12 @@ impl ::quicksilver::Quicksilver for {name} {{
         write!(result, r#"({i}, "{name}"),"#).unwrap();
     }
 
+    let default = default_text(use_default);
     write!(
         result,
         r#"
         ],
+        default: {default},
     }});
 }}
 "#
@@ -422,6 +460,7 @@ impl ::quicksilver::Quicksilver for {name} {{
 fn generate_rust_enum_impl(
     enum_name: String,
     input: TokenStream,
+    use_default: bool,
 ) -> Result<TokenStream, MacroError> {
     let result = &mut String::new();
     let variants = parse_rust_enum_variants(input)?;
@@ -448,12 +487,14 @@ fn generate_rust_enum_impl(
     let reflect_ref_text = &mut String::new();
 
     reflect_text.push_str(
-        r#"let enum_val: &mut Self = unsafe { &mut *(ptr as *mut Self) };
+        r#"let quicksilver_enum_ptr = ptr as *mut u8;
+let enum_val: &mut Self = unsafe { &mut *(ptr as *mut Self) };
 match enum_val {
 "#,
     );
     reflect_ref_text.push_str(
-        r#"let enum_val: &Self = unsafe { &*(ptr as *const Self) };
+        r#"let quicksilver_enum_ptr = ptr as *mut u8;
+let enum_val: &Self = unsafe { &*(ptr as *const Self) };
 match enum_val {
 "#,
     );
@@ -495,23 +536,15 @@ match enum_val {
         write!(
             reflect_text,
             r#"
-::quicksilver::reflections::RustEnumReflection {{
-                    name: "{enum_name}",
-                    variant_name: "{variant_name}",
-                    variant_idx: {variant_idx},
-                    ty: &Self::MIRROR,
-                    fields: vec!["#
+{{
+                    let fields = vec!["#
         )
         .unwrap();
         write!(
             reflect_ref_text,
             r#"
-::quicksilver::reflections::RustEnumReflection {{
-                    name: "{enum_name}",
-                    variant_name: "{variant_name}",
-                    variant_idx: {variant_idx},
-                    ty: &Self::MIRROR,
-                    fields: vec!["#
+{{
+                    let fields = vec!["#
         )
         .unwrap();
         for (i, field) in v.fields.iter().enumerate() {
@@ -521,31 +554,43 @@ match enum_val {
             write!(
                 reflect_text,
                 r#"
-::quicksilver::reflections::FieldReflection {{
-    name: "{name}",
-    value: unsafe {{
-        ::quicksilver::reflections::reflect_value(&raw mut *{var_name} as *mut u8, &{mirror})
-    }},
+unsafe {{
+    ::quicksilver::reflections::FieldReflection::from_raw(
+        "{name}",
+        ::quicksilver::reflections::reflect_value(&raw mut *{var_name} as *mut u8, &{mirror}),
+    )
 }},"#
             )
             .unwrap();
             write!(
                 reflect_ref_text,
                 r#"
-::quicksilver::reflections::FieldReflection {{
-    name: "{name}",
-    value: unsafe {{
-        ::quicksilver::reflections_ref::reflect_value_ref(&raw const *{var_name} as *const u8, &{mirror})
-    }},
+unsafe {{
+    ::quicksilver::reflections::FieldReflection::from_raw(
+        "{name}",
+        ::quicksilver::reflections_ref::reflect_value_ref(&raw const *{var_name} as *const u8, &{mirror}),
+    )
 }},"#
             )
             .unwrap();
         }
-        reflect_text.push_str("],},");
-        reflect_ref_text.push_str("],},");
+        let end = format!(
+            r#"];
+                    unsafe {{
+                        ::quicksilver::reflections::RustEnumReflection::from_raw(
+                            quicksilver_enum_ptr,
+                            &Self::MIRROR,
+                            {variant_idx},
+                            fields,
+                        )
+                    }}
+                }},"#
+        );
+        reflect_text.push_str(&end);
+        reflect_ref_text.push_str(&end);
     }
-    reflect_text.push_str("}");
-    reflect_ref_text.push_str("}");
+    reflect_text.push('}');
+    reflect_ref_text.push('}');
 
     let write_text = &mut String::new();
     write_text.push_str(
@@ -567,15 +612,15 @@ match (variant, fields) {"#,
         for (i, field) in variant.fields.iter().enumerate() {
             let ty = &field.ty;
             if let Some(ref name) = field.name {
-                write!(
+                writeln!(
                     write_text,
-                    "let {name} = unsafe {{ Box::from_raw(*{name} as *mut {ty}) }};\n"
+                    "let {name} = unsafe {{ Box::from_raw(*{name} as *mut {ty}) }};"
                 )
                 .unwrap()
             } else {
-                write!(
+                writeln!(
                     write_text,
-                    "let val{i} = unsafe {{ Box::from_raw(*val{i} as *mut {ty}) }};\n"
+                    "let val{i} = unsafe {{ Box::from_raw(*val{i} as *mut {ty}) }};"
                 )
                 .unwrap()
             }
@@ -611,7 +656,7 @@ match (variant, fields) {"#,
 
         write_text.push_str(",)};");
 
-        write_text.push_str("}");
+        write_text.push('}');
     }
 
     write!(
@@ -622,18 +667,56 @@ _ => unreachable!("Illegal operation: setting enum variant {{variant}} on {enum_
     )
     .unwrap();
 
+    let variant_index_text = &mut String::new();
+    let field_ptr_text = &mut String::new();
+    for (variant_idx, variant) in variants.iter().enumerate() {
+        let variant_name = &variant.name;
+        write!(
+            variant_index_text,
+            "Self::{variant_name} {{ .. }} => {variant_idx},"
+        )
+        .unwrap();
+        for (i, field) in variant.fields.iter().enumerate() {
+            // bind only the requested field, so no variable goes unused
+            let pattern = match field.name {
+                Some(ref name) => format!("{{ {name}: val, .. }}"),
+                None => format!("({}val, ..)", "_, ".repeat(i)),
+            };
+            write!(
+                field_ptr_text,
+                "(Self::{variant_name} {pattern}, {i}) => &raw const *val as *const u8,"
+            )
+            .unwrap();
+        }
+    }
+
+    let default = default_text(use_default);
     write!(
         result,
         r#"
 impl ::quicksilver::Quicksilver for {enum_name} {{
     const MIRROR: ::quicksilver::Type = ::quicksilver::Type::RustEnum(&::quicksilver::RustEnum {{
         name: "{enum_name}",
+        type_id: ::std::any::TypeId::of::<Self>(),
         size: ::std::mem::size_of::<Self>(),
         align: ::std::mem::align_of::<Self>(),
         variants: &[{variant_text}],
         reflect: |ptr| {{ {reflect_text} }},
         reflect_ref: |ptr| {{ {reflect_ref_text} }},
         write: |this, variant, fields| {{ {write_text} }},
+        variant_index: |ptr| {{
+            let enum_val: &Self = unsafe {{ &*(ptr as *const Self) }};
+            match enum_val {{ {variant_index_text} }}
+        }},
+        field_ptr: |ptr, field| {{
+            let enum_val: &Self = unsafe {{ &*(ptr as *const Self) }};
+            match (enum_val, field) {{
+                {field_ptr_text}
+                _ => panic!("{enum_name}: current variant has no field with index {{field}}"),
+            }}
+        }},
+        drop: |ptr| unsafe {{ ::std::ptr::drop_in_place(ptr as *mut Self) }},
+        default: {default},
     }});
 }}
 "#
@@ -652,8 +735,14 @@ struct RustEnumVariant {
 fn parse_rust_enum_variants(input: TokenStream) -> Result<Vec<RustEnumVariant>, MacroError> {
     let mut r = Vec::new();
 
-    let mut iter = input.into_iter();
+    let mut iter = input.into_iter().peekable();
     loop {
+        // variant attributes like `#[default]` are not relevant to us
+        if matches!(iter.peek(), Some(TT::Punct(hashtag)) if hashtag.as_char() == '#') {
+            let _hashtag = iter.next();
+            let _group = iter.next();
+            continue;
+        }
         match (iter.next(), iter.next()) {
             (None, None) => {
                 break;